@@ -0,0 +1,56 @@
+//! Reference remote signer, answering the signing requests of nodes started with
+//! `--remote-signer` with the keys of a local keystore.
+
+use std::{path::PathBuf, sync::Arc};
+
+use clap::Parser;
+use logion_node::remote_signer::{read_token, serve, SignerAddress};
+use sc_keystore::LocalKeystore;
+use sp_core::crypto::{key_types, SecretString};
+use sp_keystore::Keystore;
+
+/// Signs Aura blocks and GRANDPA votes on behalf of a Logion node.
+#[derive(Debug, Parser)]
+#[command(name = "logion-remote-signer")]
+struct Cli {
+	/// The address to listen at, `unix:<path>` or `<host>:<port>`.
+	#[arg(long, value_name = "ADDRESS")]
+	listen: SignerAddress,
+
+	/// A file holding the token the node must authenticate with.
+	#[arg(long, value_name = "PATH")]
+	token_file: PathBuf,
+
+	/// The keystore holding the session keys, e.g. filled with `logion-node key session insert`.
+	#[arg(long, value_name = "PATH", required_unless_present = "suri", conflicts_with = "suri")]
+	keystore_path: Option<PathBuf>,
+
+	/// The password of the keystore.
+	#[arg(long)]
+	password: Option<String>,
+
+	/// Sign with the session keys derived from given secret URI (e.g. `//Alice`), kept in memory.
+	/// For testing only.
+	#[arg(long)]
+	suri: Option<String>,
+}
+
+fn main() -> Result<(), String> {
+	let cli = Cli::parse();
+	let token = read_token(&cli.token_file)?;
+	let keystore = match (&cli.keystore_path, &cli.suri) {
+		(Some(path), _) => LocalKeystore::open(path, cli.password.clone().map(SecretString::new))
+			.map_err(|e| format!("Unable to open keystore {}: {}", path.display(), e))?,
+		(None, Some(suri)) => {
+			let keystore = LocalKeystore::in_memory();
+			keystore
+				.sr25519_generate_new(key_types::AURA, Some(suri))
+				.and_then(|_| keystore.ed25519_generate_new(key_types::GRANDPA, Some(suri)))
+				.map_err(|e| format!("Invalid secret URI: {}", e))?;
+			keystore
+		},
+		(None, None) => return Err("Either --keystore-path or --suri is required".into()),
+	};
+	eprintln!("Listening at {}", cli.listen);
+	serve(&cli.listen, Arc::new(keystore), token).map_err(|e| format!("{}: {}", cli.listen, e))
+}
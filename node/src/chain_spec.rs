@@ -7,7 +7,9 @@ use logion_node_runtime::{
 	opaque::SessionKeys,
 };
 use pallet_lo_authority_list::GenesisHostData;
+use sc_chain_spec::ChainSpecExtension;
 use sc_service::ChainType;
+use serde::{Deserialize, Serialize};
 use serde_json::json;
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use sp_consensus_grandpa::AuthorityId as GrandpaId;
@@ -18,8 +20,16 @@ use std::str::FromStr;
 // The URL for the telemetry server.
 // const STAGING_TELEMETRY_URL: &str = "wss://telemetry.polkadot.io/submit/";
 
+/// Node-specific fields of the chain specification.
+#[derive(Default, Clone, Debug, Serialize, Deserialize, ChainSpecExtension)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Extensions {
+	/// The consensus parameters of the network.
+	pub consensus: crate::consensus::ConsensusParams,
+}
+
 /// Specialized `ChainSpec`. This is a specialization of the general Substrate ChainSpec type.
-pub type ChainSpec = sc_service::GenericChainSpec<RuntimeGenesisConfig>;
+pub type ChainSpec = sc_service::GenericChainSpec<RuntimeGenesisConfig, Extensions>;
 
 /// Generate a crypto pair from seed.
 pub fn get_from_seed<TPublic: Public>(seed: &str) -> <TPublic::Pair as Pair>::Public {
@@ -54,7 +64,7 @@ pub fn authority_keys_from_seed(s: &str) -> (AccountId, AuraId, GrandpaId) {
 pub fn development_config() -> Result<ChainSpec, String> {
 	Ok(ChainSpec::builder(
 		WASM_BINARY.ok_or_else(|| "Development wasm not available".to_string())?,
-		None,
+		Extensions::default(),
 	)
 	.with_name("logion Development")
 	.with_id("logion_dev")
@@ -108,7 +118,7 @@ pub fn mvp_config() -> Result<ChainSpec, String> {
 
 	Ok(ChainSpec::builder(
 		WASM_BINARY.ok_or_else(|| "Development wasm not available".to_string())?,
-		None,
+		Extensions::default(),
 	)
 	.with_name("logion MVP")
 	.with_id("logion_mvp")
@@ -153,7 +163,7 @@ pub fn mvp_config() -> Result<ChainSpec, String> {
 pub fn test_config() -> Result<ChainSpec, String> {
 	Ok(ChainSpec::builder(
 		WASM_BINARY.ok_or_else(|| "Development wasm not available".to_string())?,
-		None,
+		Extensions::default(),
 	)
 	.with_name("Logion Testnet")
 	.with_id("logion_test")
@@ -204,7 +214,25 @@ pub fn test_config() -> Result<ChainSpec, String> {
 	.build())
 }
 
-const INITIAL_BALANCE: Balance = 100_000_000_000_000_000_000_000;
+/// The genesis of a chain forked off an existing state, see `fork-off`. Only the authorities and
+/// the sudo key are set, the rest of the state being taken from the forked chain.
+pub fn fork_off_config(
+	initial_authorities: Vec<(AccountId, AuraId, GrandpaId)>,
+	root_key: AccountId,
+) -> Result<ChainSpec, String> {
+	Ok(ChainSpec::builder(
+		WASM_BINARY.ok_or_else(|| "Development wasm not available".to_string())?,
+		Extensions::default(),
+	)
+	.with_name("Logion Fork")
+	.with_id("logion_fork")
+	.with_chain_type(ChainType::Local)
+	.with_genesis_config_patch(logion_genesis(initial_authorities, root_key, vec![], vec![]))
+	.with_properties(default_properties())
+	.build())
+}
+
+pub(crate) const INITIAL_BALANCE: Balance = 100_000_000_000_000_000_000_000;
 
 /// Configure initial storage state for pallets.
 fn logion_genesis(
@@ -0,0 +1,559 @@
+//! Compatibility check of a runtime upgrade, enforcing the rules of
+//! `docs/storage_backward_compatibility.md`.
+//!
+//! The metadata of both runtimes is compared:
+//! - the value (and key) types of the storage items kept by the new runtime must decode the data
+//!   written by the old one: structs keep their fields, enums keep their variants at the same
+//!   index and may only get new ones. Items whose change comes with a migration are given with
+//!   `--migrated`. Changes of storage items cleared every block (`System.Events`) are only
+//!   noted;
+//! - the calls (and signed extensions) of the old runtime must keep their index and arguments,
+//!   unless `transaction_version` is bumped;
+//! - `spec_version` must be bumped.
+
+use std::{
+	borrow::Cow,
+	collections::{BTreeMap, HashMap},
+	fs,
+	path::PathBuf,
+	sync::Arc,
+};
+
+use codec::Decode;
+use frame_metadata::{
+	v14::{PalletMetadata, RuntimeMetadataV14, StorageEntryType},
+	RuntimeMetadata, RuntimeMetadataPrefixed,
+};
+use logion_node_runtime::{opaque::Block, Hash};
+use sc_cli::{CliConfiguration, DatabaseParams, SharedParams};
+use sc_client_api::{Backend, StorageProvider, UsageProvider};
+use sc_executor::{RuntimeVersionOf, WasmExecutor};
+use scale_info::{form::PortableForm, PortableRegistry, TypeDef};
+use sp_core::{
+	storage::StorageKey,
+	traits::{CallContext, CodeExecutor, RuntimeCode, WrappedRuntimeCode},
+};
+use sp_state_machine::BasicExternalities;
+use sp_version::RuntimeVersion;
+
+const CODE_KEY: &[u8] = b":code";
+
+/// The storage items cleared every block, which an upgrade never has to decode.
+const TRANSIENT_STORAGE: &[&str] = &["System.Events"];
+
+/// The `check-upgrade` command.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct CheckUpgradeCmd {
+	/// The current runtime: a Wasm file, or `on-chain` to read the runtime of the last finalized
+	/// block of the local database of `--chain`.
+	#[arg(long)]
+	pub old: String,
+
+	/// The Wasm file of the new runtime.
+	#[arg(long)]
+	pub new: PathBuf,
+
+	/// A storage item (`Pallet.Item`) whose incompatible change is handled by a migration of the
+	/// new runtime. May be repeated.
+	#[arg(long)]
+	pub migrated: Vec<String>,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl CheckUpgradeCmd {
+	/// Returns true if the old runtime has to be read from the local database.
+	pub fn on_chain(&self) -> bool {
+		self.old == "on-chain"
+	}
+
+	/// Compares the runtimes, the old one being read from `client` if given. Fails if the upgrade
+	/// is not backward compatible.
+	pub fn run<C, B>(&self, client: Option<Arc<C>>) -> sc_cli::Result<()>
+	where
+		C: StorageProvider<Block, B> + UsageProvider<Block>,
+		B: Backend<Block>,
+	{
+		let old_code = match client {
+			Some(client) => code_at(client.as_ref(), client.usage_info().chain.finalized_hash)?,
+			None => fs::read(&self.old)
+				.map_err(|e| format!("Unable to read {}: {}", self.old, e))?,
+		};
+		let new_code = fs::read(&self.new)
+			.map_err(|e| format!("Unable to read {}: {}", self.new.display(), e))?;
+
+		let old = WasmRuntime::load(&old_code)?;
+		let new = WasmRuntime::load(&new_code)?;
+		let report = check(&old, &new, old_code == new_code, &self.migrated);
+
+		println!(
+			"{} {} -> {} {} (transaction_version {} -> {})",
+			old.version.spec_name,
+			old.version.spec_version,
+			new.version.spec_name,
+			new.version.spec_version,
+			old.version.transaction_version,
+			new.version.transaction_version,
+		);
+		for note in &report.notes {
+			println!("note: {}", note);
+		}
+		for error in &report.errors {
+			println!("error: {}", error);
+		}
+		if report.errors.is_empty() {
+			println!("Upgrade is backward compatible");
+			Ok(())
+		} else {
+			Err(format!("{} compatibility error(s) found", report.errors.len()).into())
+		}
+	}
+}
+
+impl CliConfiguration for CheckUpgradeCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+/// Reads the runtime code of the state of block `hash`.
+pub(crate) fn code_at<C, B>(client: &C, hash: Hash) -> Result<Vec<u8>, String>
+where
+	C: StorageProvider<Block, B>,
+	B: Backend<Block>,
+{
+	client
+		.storage(hash, &StorageKey(CODE_KEY.to_vec()))
+		.map_err(|e| e.to_string())?
+		.map(|code| code.0)
+		.ok_or_else(|| format!("No runtime code in state of block {}", hash))
+}
+
+/// The version and metadata of a runtime.
+pub(crate) struct WasmRuntime {
+	pub(crate) version: RuntimeVersion,
+	pub(crate) metadata: RuntimeMetadataV14,
+}
+
+impl WasmRuntime {
+	/// Reads the version and metadata of the Wasm runtime `code`.
+	pub(crate) fn load(code: &[u8]) -> Result<Self, String> {
+		let executor = WasmExecutor::<sp_io::SubstrateHostFunctions>::builder().build();
+		let fetcher = WrappedRuntimeCode(Cow::Borrowed(code));
+		let runtime_code = RuntimeCode {
+			code_fetcher: &fetcher,
+			hash: sp_core::blake2_256(code).to_vec(),
+			heap_pages: None,
+		};
+		let mut ext = BasicExternalities::default();
+
+		let version = executor
+			.runtime_version(&mut ext, &runtime_code)
+			.map_err(|e| format!("Unable to read runtime version: {}", e))?;
+		let (result, _) = executor.call(
+			&mut ext,
+			&runtime_code,
+			"Metadata_metadata",
+			&[],
+			CallContext::Offchain,
+		);
+		let encoded = result.map_err(|e| format!("Unable to read metadata: {}", e))?;
+		let metadata = <Vec<u8>>::decode(&mut &encoded[..])
+			.and_then(|opaque| RuntimeMetadataPrefixed::decode(&mut &opaque[..]))
+			.map_err(|e| format!("Unable to decode metadata: {}", e))?;
+		match metadata.1 {
+			RuntimeMetadata::V14(metadata) => Ok(Self { version, metadata }),
+			_ => Err("Unsupported metadata version, V14 expected".into()),
+		}
+	}
+}
+
+/// The outcome of a check.
+#[derive(Default, Debug)]
+struct Report {
+	/// The changes preventing the upgrade.
+	errors: Vec<String>,
+	/// The changes worth knowing, which do not prevent the upgrade.
+	notes: Vec<String>,
+}
+
+fn check(old: &WasmRuntime, new: &WasmRuntime, same_code: bool, migrated: &[String]) -> Report {
+	let mut report = Report::default();
+	let old_version = &old.version;
+	let new_version = &new.version;
+	if old_version.spec_name != new_version.spec_name {
+		report.errors.push(format!(
+			"spec_name changed from {} to {}",
+			old_version.spec_name, new_version.spec_name
+		));
+	}
+	if !same_code && new_version.spec_version <= old_version.spec_version {
+		report.errors.push(format!(
+			"spec_version must be bumped (old {}, new {})",
+			old_version.spec_version, new_version.spec_version
+		));
+	}
+
+	let mut types = TypeComparator::new(&old.metadata.types, &new.metadata.types);
+	let new_pallets: BTreeMap<&str, &PalletMetadata<PortableForm>> =
+		new.metadata.pallets.iter().map(|pallet| (pallet.name.as_str(), pallet)).collect();
+	let mut call_changes = Vec::new();
+	for old_pallet in &old.metadata.pallets {
+		let Some(new_pallet) = new_pallets.get(old_pallet.name.as_str()) else {
+			report.notes.push(format!("pallet {} removed", old_pallet.name));
+			if old_pallet.calls.is_some() {
+				call_changes.push(format!("calls of removed pallet {}", old_pallet.name));
+			}
+			continue
+		};
+		check_storage(old_pallet, new_pallet, &mut types, migrated, &mut report);
+		if let Some(old_calls) = &old_pallet.calls {
+			let name = &old_pallet.name;
+			match &new_pallet.calls {
+				None => call_changes.push(format!("calls of {} removed", name)),
+				Some(_) if old_pallet.index != new_pallet.index => call_changes.push(format!(
+					"call index of pallet {} changed from {} to {}",
+					name, old_pallet.index, new_pallet.index
+				)),
+				Some(new_calls) =>
+					if let Err(e) = types.compare(old_calls.ty.id, new_calls.ty.id) {
+						call_changes.push(format!("{} calls: {}", name, e));
+					},
+			}
+		}
+	}
+
+	let old_extensions = &old.metadata.extrinsic.signed_extensions;
+	let new_extensions = &new.metadata.extrinsic.signed_extensions;
+	if old_extensions.len() != new_extensions.len() {
+		call_changes.push("signed extensions added or removed".into());
+	} else {
+		for (old_extension, new_extension) in old_extensions.iter().zip(new_extensions) {
+			let identifier = &old_extension.identifier;
+			if identifier != &new_extension.identifier {
+				call_changes.push(format!(
+					"signed extension {} replaced by {}",
+					identifier, new_extension.identifier
+				));
+			} else if let Err(e) = types
+				.compare(old_extension.ty.id, new_extension.ty.id)
+				.and_then(|()| {
+					types.compare(
+						old_extension.additional_signed.id,
+						new_extension.additional_signed.id,
+					)
+				}) {
+				call_changes.push(format!("signed extension {}: {}", identifier, e));
+			}
+		}
+	}
+
+	if new_version.transaction_version > old_version.transaction_version {
+		report.notes.extend(call_changes);
+	} else {
+		report.errors.extend(
+			call_changes
+				.into_iter()
+				.map(|change| format!("{} (transaction_version must be bumped)", change)),
+		);
+	}
+	report
+}
+
+fn check_storage(
+	old_pallet: &PalletMetadata<PortableForm>,
+	new_pallet: &PalletMetadata<PortableForm>,
+	types: &mut TypeComparator,
+	migrated: &[String],
+	report: &mut Report,
+) {
+	let Some(old_storage) = &old_pallet.storage else { return };
+	let new_entries: BTreeMap<&str, &StorageEntryType<PortableForm>> = new_pallet
+		.storage
+		.iter()
+		.flat_map(|storage| &storage.entries)
+		.map(|entry| (entry.name.as_str(), &entry.ty))
+		.collect();
+	for entry in &old_storage.entries {
+		let name = format!("{}.{}", old_pallet.name, entry.name);
+		let Some(new_ty) = new_entries.get(entry.name.as_str()) else {
+			report.notes.push(format!("storage {} removed", name));
+			continue
+		};
+		let result = match (&entry.ty, new_ty) {
+			(StorageEntryType::Plain(old), StorageEntryType::Plain(new)) =>
+				types.compare(old.id, new.id),
+			(
+				StorageEntryType::Map { hashers: old_hashers, key: old_key, value: old_value },
+				StorageEntryType::Map { hashers: new_hashers, key: new_key, value: new_value },
+			) =>
+				if old_hashers != new_hashers {
+					Err("hashers changed".into())
+				} else {
+					types
+						.compare(old_key.id, new_key.id)
+						.map_err(|e| format!("key: {}", e))
+						.and_then(|()| types.compare(old_value.id, new_value.id))
+				},
+			_ => Err("changed from value to map or conversely".into()),
+		};
+		if let Err(e) = result {
+			if migrated.contains(&name) {
+				report.notes.push(format!("storage {} migrated: {}", name, e));
+			} else if TRANSIENT_STORAGE.contains(&name.as_str()) {
+				report.notes.push(format!("storage {} changed, cleared every block: {}", name, e));
+			} else {
+				report.errors.push(format!("storage {}: {}", name, e));
+			}
+		}
+	}
+}
+
+/// Tells if the values encoded with a type of the old registry are decoded by a type of the new
+/// registry.
+struct TypeComparator<'a> {
+	old: &'a PortableRegistry,
+	new: &'a PortableRegistry,
+	/// The outcome of the pairs already compared. Pairs being compared are assumed compatible, to
+	/// stop on recursive types.
+	results: HashMap<(u32, u32), Result<(), String>>,
+}
+
+impl<'a> TypeComparator<'a> {
+	fn new(old: &'a PortableRegistry, new: &'a PortableRegistry) -> Self {
+		Self { old, new, results: HashMap::new() }
+	}
+
+	fn compare(&mut self, old_id: u32, new_id: u32) -> Result<(), String> {
+		if let Some(result) = self.results.get(&(old_id, new_id)) {
+			return result.clone()
+		}
+		self.results.insert((old_id, new_id), Ok(()));
+		let old = self.old.resolve(old_id).ok_or_else(|| format!("unknown type {}", old_id))?;
+		let new = self.new.resolve(new_id).ok_or_else(|| format!("unknown type {}", new_id))?;
+		let result = match (&old.type_def, &new.type_def) {
+			(TypeDef::Composite(old), TypeDef::Composite(new)) =>
+				if old.fields.len() != new.fields.len() {
+					Err(format!(
+						"fields added or removed ({} -> {})",
+						old.fields.len(),
+						new.fields.len()
+					))
+				} else {
+					old.fields.iter().zip(&new.fields).enumerate().try_for_each(
+						|(i, (old, new))| {
+							self.compare(old.ty.id, new.ty.id).map_err(|e| {
+								let name = old.name.clone().unwrap_or_else(|| i.to_string());
+								format!("field {}: {}", name, e)
+							})
+						},
+					)
+				},
+			(TypeDef::Variant(old), TypeDef::Variant(new)) =>
+				old.variants.iter().try_for_each(|old_variant| {
+					let by_name = new.variants.iter().find(|v| v.name == old_variant.name);
+					let by_index = new.variants.iter().find(|v| v.index == old_variant.index);
+					match (by_name, by_index) {
+						(Some(moved), _) if moved.index != old_variant.index => Err(format!(
+							"variant {} moved from index {} to {}",
+							old_variant.name, old_variant.index, moved.index
+						)),
+						// Renaming a variant does not change its encoding.
+						(_, Some(new_variant)) =>
+							if old_variant.fields.len() != new_variant.fields.len() {
+								Err(format!(
+									"fields of variant {} added or removed",
+									old_variant.name
+								))
+							} else {
+								old_variant
+									.fields
+									.iter()
+									.zip(&new_variant.fields)
+									.try_for_each(|(old, new)| self.compare(old.ty.id, new.ty.id))
+									.map_err(|e| format!("variant {}: {}", old_variant.name, e))
+							},
+						_ => Err(format!("variant {} removed", old_variant.name)),
+					}
+				}),
+			(TypeDef::Sequence(old), TypeDef::Sequence(new)) =>
+				self.compare(old.type_param.id, new.type_param.id),
+			(TypeDef::Array(old), TypeDef::Array(new)) if old.len == new.len =>
+				self.compare(old.type_param.id, new.type_param.id),
+			(TypeDef::Tuple(old), TypeDef::Tuple(new)) if old.fields.len() == new.fields.len() =>
+				old.fields.iter().zip(&new.fields).try_for_each(|(old, new)| {
+					self.compare(old.id, new.id)
+				}),
+			(TypeDef::Primitive(old), TypeDef::Primitive(new)) if old == new => Ok(()),
+			(TypeDef::Compact(old), TypeDef::Compact(new)) =>
+				self.compare(old.type_param.id, new.type_param.id),
+			(TypeDef::BitSequence(old), TypeDef::BitSequence(new)) => self
+				.compare(old.bit_store_type.id, new.bit_store_type.id)
+				.and_then(|()| self.compare(old.bit_order_type.id, new.bit_order_type.id)),
+			_ => Err("type changed".into()),
+		};
+		let result = match old.path.segments.last() {
+			Some(name) => result.map_err(|e| format!("{}: {}", name, e)),
+			None => result,
+		};
+		self.results.insert((old_id, new_id), result.clone());
+		result
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use frame_metadata::v14::{PalletStorageMetadata, StorageEntryMetadata, StorageEntryModifier};
+	use scale_info::{meta_type, Registry, TypeInfo};
+
+	#[allow(dead_code)]
+	mod old {
+		use scale_info::TypeInfo;
+
+		#[derive(TypeInfo)]
+		pub struct Loc {
+			pub owner: u64,
+			pub void_info: Option<VoidInfo>,
+		}
+
+		#[derive(TypeInfo)]
+		pub enum VoidInfo {
+			V1 { reason: Vec<u8> },
+		}
+	}
+
+	#[allow(dead_code)]
+	mod grown {
+		use scale_info::TypeInfo;
+
+		#[derive(TypeInfo)]
+		pub struct Loc {
+			pub owner: u64,
+			pub void_info: Option<VoidInfo>,
+		}
+
+		#[derive(TypeInfo)]
+		pub enum VoidInfo {
+			V1 { reason: Vec<u8> },
+			V2 { reason: Vec<u8>, replacer: u128 },
+		}
+	}
+
+	#[allow(dead_code)]
+	mod reordered {
+		use scale_info::TypeInfo;
+
+		#[derive(TypeInfo)]
+		pub struct Loc {
+			pub owner: u64,
+			pub void_info: Option<VoidInfo>,
+		}
+
+		#[derive(TypeInfo)]
+		pub enum VoidInfo {
+			V2 { reason: Vec<u8>, replacer: u128 },
+			V1 { reason: Vec<u8> },
+		}
+	}
+
+	#[allow(dead_code)]
+	mod extended {
+		use scale_info::TypeInfo;
+
+		#[derive(TypeInfo)]
+		pub struct Loc {
+			pub owner: u64,
+			pub void_info: Option<super::old::VoidInfo>,
+			pub sponsorship: Option<u128>,
+		}
+	}
+
+	fn registry<T: TypeInfo + 'static>() -> (PortableRegistry, u32) {
+		let mut registry = Registry::new();
+		let id = registry.register_type(&meta_type::<T>()).id;
+		(registry.into(), id)
+	}
+
+	fn compare<Old: TypeInfo + 'static, New: TypeInfo + 'static>() -> Result<(), String> {
+		let (old, old_id) = registry::<Old>();
+		let (new, new_id) = registry::<New>();
+		TypeComparator::new(&old, &new).compare(old_id, new_id)
+	}
+
+	#[test]
+	fn enums_may_grow_at_the_end() {
+		assert_eq!(compare::<old::Loc, grown::Loc>(), Ok(()));
+	}
+
+	#[test]
+	fn reordered_variants_are_rejected() {
+		let error = compare::<old::Loc, reordered::Loc>().unwrap_err();
+		assert!(error.contains("variant V1 moved from index 0 to 1"), "{}", error);
+	}
+
+	#[test]
+	fn added_fields_are_rejected() {
+		let error = compare::<old::Loc, extended::Loc>().unwrap_err();
+		assert!(error.contains("fields added or removed (2 -> 3)"), "{}", error);
+	}
+
+	fn pallet(name: &str, entry: &str, ty: u32) -> PalletMetadata<PortableForm> {
+		PalletMetadata {
+			name: name.into(),
+			storage: Some(PalletStorageMetadata {
+				prefix: name.into(),
+				entries: vec![StorageEntryMetadata {
+					name: entry.into(),
+					modifier: StorageEntryModifier::Default,
+					ty: StorageEntryType::Plain(ty.into()),
+					default: vec![],
+					docs: vec![],
+				}],
+			}),
+			calls: None,
+			event: None,
+			constants: vec![],
+			error: None,
+			index: 0,
+		}
+	}
+
+	#[test]
+	fn event_changes_are_noted() {
+		let (old, old_id) = registry::<old::Loc>();
+		let (new, new_id) = registry::<extended::Loc>();
+		let mut types = TypeComparator::new(&old, &new);
+		let mut report = Report::default();
+
+		check_storage(
+			&pallet("System", "Events", old_id),
+			&pallet("System", "Events", new_id),
+			&mut types,
+			&[],
+			&mut report,
+		);
+		check_storage(
+			&pallet("LogionLoc", "LocMap", old_id),
+			&pallet("LogionLoc", "LocMap", new_id),
+			&mut types,
+			&[],
+			&mut report,
+		);
+
+		assert_eq!(report.notes.len(), 1);
+		assert!(report.notes[0].starts_with("storage System.Events changed"), "{:?}", report);
+		assert_eq!(report.errors.len(), 1);
+		assert!(report.errors[0].starts_with("storage LogionLoc.LocMap"), "{:?}", report);
+	}
+}
@@ -7,13 +7,95 @@ pub struct Cli {
 
 	#[clap(flatten)]
 	pub run: RunCmd,
+
+	#[clap(flatten)]
+	pub node: NodeOptions,
+}
+
+/// Logion-specific options of a running node.
+#[derive(Debug, Clone, clap::Args)]
+pub struct NodeOptions {
+	/// Only accept connections with the nodes of the legal officers registered on chain, the boot
+	/// nodes and the nodes given with `--reserved-nodes`.
+	///
+	/// Whatever this flag, the nodes of the legal officers are kept as reserved peers, at the
+	/// addresses given with `--bootnodes` or `--reserved-nodes`.
+	#[arg(long)]
+	pub permissioned: bool,
+
+	/// Stop an authority node whose peer ID or session keys do not match its on-chain
+	/// registration, instead of only logging warnings. The check runs once the node is synced.
+	#[arg(long)]
+	pub strict: bool,
+
+	/// Index the Logion events of finalized blocks in a local database, queried with the
+	/// `logion_queryEvents` RPC.
+	#[arg(long)]
+	pub logion_indexer: bool,
+
+	/// The first block indexed by `--logion-indexer` when its database is empty. By default, the
+	/// first block whose state is available.
+	#[arg(long, value_name = "BLOCK", requires = "logion_indexer")]
+	pub logion_indexer_from: Option<logion_node_runtime::BlockNumber>,
+
+	/// Deliver the events of finalized blocks to the HTTP endpoints configured in given JSON file.
+	#[arg(long, value_name = "PATH")]
+	pub webhooks: Option<std::path::PathBuf>,
+
+	/// Development mode: seal blocks `instant`ly (one per transaction), on `manual` calls to the
+	/// `engine_createBlock` RPC, or every given number of milliseconds (`interval=<ms>`), instead
+	/// of running Aura and GRANDPA.
+	#[arg(long, value_name = "MODE")]
+	pub sealing: Option<crate::sealing::Sealing>,
+
+	/// Sign Aura blocks and GRANDPA votes with the remote signer listening at given address
+	/// (`unix:<path>` or `<host>:<port>`) instead of the local keystore.
+	#[arg(long, value_name = "ADDRESS", requires = "remote_signer_token_file")]
+	pub remote_signer: Option<crate::remote_signer::SignerAddress>,
+
+	/// A file holding the token authenticating the node to the remote signer.
+	#[arg(long, value_name = "PATH", requires = "remote_signer")]
+	pub remote_signer_token_file: Option<std::path::PathBuf>,
+
+	#[allow(missing_docs)]
+	#[command(flatten)]
+	pub consensus: ConsensusOverrides,
+}
+
+/// Overrides of the consensus parameters of the chain specification.
+#[derive(Debug, Clone, clap::Args)]
+pub struct ConsensusOverrides {
+	/// The minimum period, in blocks, on which GRANDPA justifications are generated and imported.
+	#[arg(long, value_name = "BLOCKS")]
+	pub grandpa_justification_period: Option<u32>,
+
+	/// The duration, in milliseconds, of GRANDPA gossip rounds.
+	#[arg(long, value_name = "MILLIS")]
+	pub grandpa_gossip_duration: Option<u64>,
+
+	/// GRANDPA votes for blocks at least this number of blocks before the best block.
+	#[arg(long, value_name = "BLOCKS")]
+	pub grandpa_vote_before_best_by: Option<u32>,
+
+	/// The portion of the slot given to block proposal, in (0, 1].
+	#[arg(long, value_name = "PORTION")]
+	pub block_proposal_slot_portion: Option<f32>,
+
+	/// Author fewer blocks as finality lags, with the backoff parameters of the chain
+	/// specification or the default ones.
+	#[arg(long, conflicts_with = "no_backoff_authoring")]
+	pub backoff_authoring: bool,
+
+	/// Never back off authoring, whatever the chain specification.
+	#[arg(long)]
+	pub no_backoff_authoring: bool,
 }
 
 #[derive(Debug, clap::Subcommand)]
 pub enum Subcommand {
 	/// Key management cli utilities
 	#[command(subcommand)]
-	Key(sc_cli::KeySubcommand),
+	Key(KeySubcommand),
 
 	/// Build a chain specification.
 	BuildSpec(sc_cli::BuildSpecCmd),
@@ -47,4 +129,45 @@ pub enum Subcommand {
 
 	/// Db meta columns information.
 	ChainInfo(sc_cli::ChainInfoCmd),
+
+	/// Export an offline-verifiable proof of existence of a LOC or collection item.
+	ExportProof(crate::proof::ExportProofCmd),
+
+	/// Verify a proof bundle produced by `export-proof`, without network access.
+	VerifyProof(crate::proof::VerifyProofCmd),
+
+	/// Build the chain spec of a local test chain from the state of an existing chain.
+	ForkOff(crate::fork_off::ForkOffCmd),
+
+	/// Export the Logion data of a block as batches of `import_*` calls.
+	ExportLogionData(crate::logion_data::ExportLogionDataCmd),
+
+	/// Compare the state of a target chain with the export of `export-logion-data`.
+	VerifyLogionData(crate::logion_data::VerifyLogionDataCmd),
+
+	/// Check that a runtime upgrade is backward compatible.
+	CheckUpgrade(crate::check_upgrade::CheckUpgradeCmd),
+
+	/// Decode a block or an extrinsic.
+	Inspect(crate::inspect::InspectCmd),
+
+	/// Build, sign and submit transactions, for keys kept offline.
+	#[command(subcommand)]
+	Tx(crate::tx::TxCmd),
+}
+
+/// Key management subcommands.
+#[derive(Debug, clap::Subcommand)]
+pub enum KeySubcommand {
+	#[allow(missing_docs)]
+	#[command(flatten)]
+	Substrate(sc_cli::KeySubcommand),
+
+	/// Session keys of validators.
+	#[command(subcommand)]
+	Session(crate::session_keys::SessionKeyCmd),
+
+	/// Export and import the record of signed slots and votes, protecting against double signing.
+	#[command(subcommand)]
+	SigningRecord(crate::double_sign::SigningRecordCmd),
 }
@@ -1,8 +1,10 @@
 use crate::{
 	benchmarking::{inherent_benchmark_data, RemarkBuilder, TransferKeepAliveBuilder},
 	chain_spec,
-	cli::{Cli, Subcommand},
+	cli::{Cli, KeySubcommand, Subcommand},
+	double_sign::SigningRecordCmd,
 	service,
+	session_keys::SessionKeyCmd,
 };
 use frame_benchmarking_cli::{BenchmarkCmd, ExtrinsicFactory, SUBSTRATE_REFERENCE_HARDWARE};
 use logion_node_runtime::{Block, EXISTENTIAL_DEPOSIT};
@@ -51,7 +53,37 @@ pub fn run() -> sc_cli::Result<()> {
 	let cli = Cli::from_args();
 
 	match &cli.subcommand {
-		Some(Subcommand::Key(cmd)) => cmd.run(&cli),
+		Some(Subcommand::Key(KeySubcommand::Substrate(cmd))) => cmd.run(&cli),
+		Some(Subcommand::Key(KeySubcommand::Session(cmd))) => match cmd {
+			SessionKeyCmd::Generate(cmd) => cmd.run(),
+			SessionKeyCmd::Inspect(cmd) => cmd.run(),
+			SessionKeyCmd::Insert(cmd) => {
+				let runner = cli.create_runner(cmd)?;
+				runner.sync_run(|config| cmd.run(&config.keystore))
+			},
+			SessionKeyCmd::Verify(cmd) => {
+				let runner = cli.create_runner(cmd)?;
+				runner.sync_run(|config| {
+					let PartialComponents { client, keystore_container, .. } =
+						service::new_partial(&config)?;
+					cmd.run(client.as_ref(), keystore_container.keystore())
+				})
+			},
+		},
+		Some(Subcommand::Key(KeySubcommand::SigningRecord(cmd))) => match cmd {
+			SigningRecordCmd::Export(cmd) => {
+				let runner = cli.create_runner(cmd)?;
+				runner.sync_run(|config| cmd.run(&config))
+			},
+			SigningRecordCmd::Import(cmd) => {
+				let runner = cli.create_runner(cmd)?;
+				runner.sync_run(|config| {
+					// Opening the database also makes sure that the node is stopped.
+					let PartialComponents { client, .. } = service::new_partial(&config)?;
+					cmd.run(&config, client.chain_info().genesis_hash)
+				})
+			},
+		},
 		Some(Subcommand::BuildSpec(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			runner.sync_run(|config| cmd.run(config.chain_spec, config.network))
@@ -179,10 +211,69 @@ pub fn run() -> sc_cli::Result<()> {
 			let runner = cli.create_runner(cmd)?;
 			runner.sync_run(|config| cmd.run::<Block>(&config))
 		},
+		Some(Subcommand::ExportProof(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| {
+				let PartialComponents { client, .. } = service::new_partial(&config)?;
+				cmd.run(client.as_ref())
+			})
+		},
+		Some(Subcommand::VerifyProof(cmd)) => {
+			let chain_spec = cmd.chain.as_deref().map(|id| cli.load_spec(id)).transpose()?;
+			cmd.run(chain_spec.as_deref())
+		},
+		Some(Subcommand::ForkOff(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| {
+				if cmd.from_db() {
+					let PartialComponents { client, .. } = service::new_partial(&config)?;
+					cmd.run(Some(client))
+				} else {
+					cmd.run::<service::FullClient, service::FullBackend>(None)
+				}
+			})
+		},
+		Some(Subcommand::ExportLogionData(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| {
+				let PartialComponents { client, .. } = service::new_partial(&config)?;
+				cmd.run(client)
+			})
+		},
+		Some(Subcommand::VerifyLogionData(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| {
+				if cmd.from_db() {
+					let PartialComponents { client, .. } = service::new_partial(&config)?;
+					cmd.run(Some(client))
+				} else {
+					cmd.run::<service::FullClient, service::FullBackend>(None)
+				}
+			})
+		},
+		Some(Subcommand::CheckUpgrade(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| {
+				if cmd.on_chain() {
+					let PartialComponents { client, .. } = service::new_partial(&config)?;
+					cmd.run(Some(client))
+				} else {
+					cmd.run::<service::FullClient, service::FullBackend>(None)
+				}
+			})
+		},
+		Some(Subcommand::Inspect(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| {
+				let PartialComponents { client, .. } = service::new_partial(&config)?;
+				cmd.run(client.as_ref())
+			})
+		},
+		Some(Subcommand::Tx(cmd)) => cmd.run(),
 		None => {
 			let runner = cli.create_runner(&cli.run)?;
 			runner.run_node_until_exit(|config| async move {
-				service::new_full(config).map_err(sc_cli::Error::Service)
+				service::new_full(config, cli.node.clone()).map_err(sc_cli::Error::Service)
 			})
 		},
 	}
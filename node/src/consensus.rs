@@ -0,0 +1,235 @@
+//! Consensus parameters, tunable per network without a node rebuild.
+//!
+//! They are read from the `consensus` extension of the chain specification, e.g.:
+//!
+//! ```json
+//! "consensus": {
+//!   "grandpaJustificationPeriod": 512,
+//!   "grandpaGossipDuration": 333,
+//!   "backoffAuthoring": { "unfinalizedSlack": 50, "authoringBias": 2, "maxInterval": 100 }
+//! }
+//! ```
+//!
+//! Missing values take their default, the one of Substrate's node template. Node operators may
+//! override them with command line options (see [`ConsensusOverrides`]).
+
+use std::time::Duration;
+
+use logion_node_runtime::{opaque::Block, BlockNumber};
+use sc_chain_spec::ChainSpec;
+use sc_consensus_grandpa::{
+	BeforeBestBlockBy, ThreeQuartersOfTheUnfinalizedChain, VotingRule, VotingRulesBuilder,
+};
+use sc_consensus_slots::{BackoffAuthoringOnFinalizedHeadLagging, SlotProportion};
+use serde::{Deserialize, Serialize};
+use sp_blockchain::HeaderBackend;
+
+use crate::cli::ConsensusOverrides;
+
+/// The consensus parameters of a network.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ConsensusParams {
+	/// The minimum period, in blocks, on which GRANDPA justifications are generated and imported.
+	pub grandpa_justification_period: u32,
+	/// The duration, in milliseconds, of GRANDPA gossip rounds.
+	pub grandpa_gossip_duration: u64,
+	/// GRANDPA votes for blocks at least this number of blocks before the best block.
+	pub grandpa_vote_before_best_by: BlockNumber,
+	/// GRANDPA votes for at most three quarters of the unfinalized chain.
+	pub grandpa_vote_three_quarters_of_unfinalized: bool,
+	/// The portion of the slot given to block proposal, in `(0, 1]`.
+	pub block_proposal_slot_portion: f32,
+	/// If set, validators author fewer blocks as finality lags.
+	pub backoff_authoring: Option<BackoffAuthoring>,
+}
+
+impl Default for ConsensusParams {
+	fn default() -> Self {
+		Self {
+			grandpa_justification_period: 512,
+			grandpa_gossip_duration: 333,
+			grandpa_vote_before_best_by: 2,
+			grandpa_vote_three_quarters_of_unfinalized: true,
+			block_proposal_slot_portion: 2f32 / 3f32,
+			backoff_authoring: None,
+		}
+	}
+}
+
+/// The backoff of block authoring on finality lag, see
+/// [`BackoffAuthoringOnFinalizedHeadLagging`].
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BackoffAuthoring {
+	/// The number of unfinalized blocks authored before backing off.
+	pub unfinalized_slack: BlockNumber,
+	/// The higher, the faster the authoring interval grows with the lag.
+	pub authoring_bias: BlockNumber,
+	/// The maximum number of slots between two authored blocks.
+	pub max_interval: BlockNumber,
+}
+
+impl Default for BackoffAuthoring {
+	fn default() -> Self {
+		let backoff = BackoffAuthoringOnFinalizedHeadLagging::<BlockNumber>::default();
+		Self {
+			unfinalized_slack: backoff.unfinalized_slack,
+			authoring_bias: backoff.authoring_bias,
+			max_interval: backoff.max_interval,
+		}
+	}
+}
+
+impl ConsensusParams {
+	/// The parameters of `chain_spec`, the default ones if it has none.
+	pub fn from_chain_spec(chain_spec: &dyn ChainSpec) -> Self {
+		sc_chain_spec::get_extension::<Self>(chain_spec.extensions())
+			.cloned()
+			.unwrap_or_default()
+	}
+
+	/// Applies the command line `overrides`.
+	pub fn with_overrides(mut self, overrides: &ConsensusOverrides) -> Self {
+		if let Some(period) = overrides.grandpa_justification_period {
+			self.grandpa_justification_period = period;
+		}
+		if let Some(duration) = overrides.grandpa_gossip_duration {
+			self.grandpa_gossip_duration = duration;
+		}
+		if let Some(blocks) = overrides.grandpa_vote_before_best_by {
+			self.grandpa_vote_before_best_by = blocks;
+		}
+		if let Some(portion) = overrides.block_proposal_slot_portion {
+			self.block_proposal_slot_portion = portion;
+		}
+		if overrides.backoff_authoring {
+			self.backoff_authoring = Some(self.backoff_authoring.unwrap_or_default());
+		}
+		if overrides.no_backoff_authoring {
+			self.backoff_authoring = None;
+		}
+		self
+	}
+
+	/// Checks that the parameters are usable.
+	pub fn validate(&self) -> Result<(), String> {
+		if self.grandpa_justification_period == 0 {
+			return Err("The GRANDPA justification period must be positive".into())
+		}
+		if self.grandpa_gossip_duration == 0 {
+			return Err("The GRANDPA gossip duration must be positive".into())
+		}
+		let portion = self.block_proposal_slot_portion;
+		if !(portion > 0.0 && portion <= 1.0) {
+			return Err(format!(
+				"Invalid block proposal slot portion {}, expected a value in (0, 1]",
+				portion
+			))
+		}
+		match self.backoff_authoring {
+			Some(backoff) if backoff.max_interval == 0 =>
+				Err("The maximum authoring interval must be positive".into()),
+			_ => Ok(()),
+		}
+	}
+
+	/// The duration of GRANDPA gossip rounds.
+	pub fn gossip_duration(&self) -> Duration {
+		Duration::from_millis(self.grandpa_gossip_duration)
+	}
+
+	/// The portion of the slot given to block proposal.
+	pub fn slot_proportion(&self) -> SlotProportion {
+		SlotProportion::new(self.block_proposal_slot_portion)
+	}
+
+	/// The backoff strategy of Aura, if any.
+	pub fn backoff_strategy(
+		&self,
+	) -> Option<BackoffAuthoringOnFinalizedHeadLagging<BlockNumber>> {
+		self.backoff_authoring.map(|backoff| BackoffAuthoringOnFinalizedHeadLagging {
+			unfinalized_slack: backoff.unfinalized_slack,
+			authoring_bias: backoff.authoring_bias,
+			max_interval: backoff.max_interval,
+		})
+	}
+
+	/// The rules restricting the blocks GRANDPA votes for.
+	pub fn voting_rule<C>(&self) -> impl VotingRule<Block, C> + Clone
+	where
+		C: HeaderBackend<Block> + 'static,
+	{
+		let mut builder = VotingRulesBuilder::new();
+		if self.grandpa_vote_before_best_by > 0 {
+			builder = builder.add(BeforeBestBlockBy(self.grandpa_vote_before_best_by));
+		}
+		if self.grandpa_vote_three_quarters_of_unfinalized {
+			builder = builder.add(ThreeQuartersOfTheUnfinalizedChain);
+		}
+		builder.build()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::chain_spec::{development_config, Extensions};
+
+	fn no_overrides() -> ConsensusOverrides {
+		ConsensusOverrides {
+			grandpa_justification_period: None,
+			grandpa_gossip_duration: None,
+			grandpa_vote_before_best_by: None,
+			block_proposal_slot_portion: None,
+			backoff_authoring: false,
+			no_backoff_authoring: false,
+		}
+	}
+
+	#[test]
+	fn reads_chain_spec_extension() {
+		let spec: Extensions = serde_json::from_value(serde_json::json!({
+			"consensus": {"grandpaGossipDuration": 500, "backoffAuthoring": {"maxInterval": 10}}
+		}))
+		.unwrap();
+
+		assert_eq!(spec.consensus.grandpa_gossip_duration, 500);
+		assert_eq!(spec.consensus.grandpa_justification_period, 512);
+		assert_eq!(
+			spec.consensus.backoff_authoring,
+			Some(BackoffAuthoring { max_interval: 10, ..Default::default() })
+		);
+		assert_eq!(
+			serde_json::from_str::<Extensions>("{}").unwrap().consensus,
+			ConsensusParams::default()
+		);
+		assert_eq!(
+			ConsensusParams::from_chain_spec(&development_config().unwrap()),
+			ConsensusParams::default()
+		);
+	}
+
+	#[test]
+	fn applies_overrides() {
+		let params = ConsensusParams::default().with_overrides(&ConsensusOverrides {
+			grandpa_justification_period: Some(256),
+			block_proposal_slot_portion: Some(0.5),
+			backoff_authoring: true,
+			..no_overrides()
+		});
+
+		assert_eq!(params.grandpa_justification_period, 256);
+		assert_eq!(params.grandpa_gossip_duration, 333);
+		assert_eq!(params.block_proposal_slot_portion, 0.5);
+		assert_eq!(params.backoff_authoring, Some(BackoffAuthoring::default()));
+		assert!(params.validate().is_ok());
+		let params = params.with_overrides(&ConsensusOverrides {
+			no_backoff_authoring: true,
+			block_proposal_slot_portion: Some(1.5),
+			..no_overrides()
+		});
+		assert_eq!(params.backoff_authoring, None);
+		assert!(params.validate().is_err());
+	}
+}
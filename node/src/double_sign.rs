@@ -0,0 +1,626 @@
+//! Protection of validators against double signing.
+//!
+//! When the same session keys run on two machines, e.g. during a failover, both would author a
+//! block for the slots of the validator and cast conflicting GRANDPA votes. An authority node
+//! keeps a [`SigningRecord`] of the last slot it proposed a block for and of the GRANDPA votes of
+//! the latest rounds, and consults it before signing:
+//!
+//! - [`ProtectedProposerFactory`] refuses to propose a block for a slot which is not after the
+//!   recorded one;
+//! - [`ProtectedKeystore`] refuses to sign a GRANDPA vote conflicting with a recorded one, or of
+//!   a round older than the recorded ones.
+//!
+//! The record is persisted, before signing, in a JSON file of the node's configuration
+//! directory. `key signing-record export` and `import` move it along with the keys.
+
+use std::{
+	io::Write,
+	path::{Path, PathBuf},
+	sync::{Arc, Mutex},
+	time::Duration,
+};
+
+use codec::DecodeAll;
+use futures::{
+	future::{self, Either, Ready},
+	Future, TryFutureExt,
+};
+use logion_node_runtime::{BlockNumber, Hash};
+use sc_cli::{CliConfiguration, DatabaseParams, SharedParams};
+use sc_service::Configuration;
+use serde::{Deserialize, Serialize};
+use sp_consensus::{Environment, Proposal, Proposer};
+use sp_consensus_aura::{Slot, AURA_ENGINE_ID};
+use sp_consensus_grandpa::{RoundNumber, SetId};
+use sp_core::{
+	crypto::{key_types, KeyTypeId},
+	ecdsa, ed25519, sr25519,
+};
+use sp_inherents::InherentData;
+use sp_keystore::{Error, Keystore, KeystorePtr};
+use sp_runtime::{traits::Block as BlockT, Digest};
+
+const LOG_TARGET: &str = "logion-double-sign";
+
+/// The number of GRANDPA rounds whose votes are kept, votes of earlier rounds being refused.
+const KEPT_ROUNDS: RoundNumber = 64;
+
+/// The path of the signing record of the node configured by `config`.
+pub fn record_path(config: &Configuration) -> PathBuf {
+	config.base_path.config_dir(config.chain_spec.id()).join("logion-signing-record.json")
+}
+
+/// The kind of a GRANDPA vote.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub enum VoteKind {
+	/// A prevote.
+	Prevote,
+	/// A precommit.
+	Precommit,
+	/// The proposal of the primary voter of a round.
+	PrimaryPropose,
+}
+
+/// A signed GRANDPA vote.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub struct GrandpaVote {
+	/// The authority set ID.
+	pub set_id: SetId,
+	/// The round.
+	pub round: RoundNumber,
+	/// The kind of vote.
+	pub kind: VoteKind,
+	/// The hash of the voted block.
+	pub target_hash: Hash,
+	/// The number of the voted block.
+	pub target_number: BlockNumber,
+}
+
+impl GrandpaVote {
+	/// Decodes the payload signed by a GRANDPA voter: the vote, the round and the set ID.
+	pub fn decode(payload: &[u8]) -> Option<Self> {
+		let (message, round, set_id) =
+			<(finality_grandpa::Message<Hash, BlockNumber>, RoundNumber, SetId)>::decode_all(
+				&mut &payload[..],
+			)
+			.ok()?;
+		let (kind, target_hash, target_number) = match message {
+			finality_grandpa::Message::Prevote(vote) =>
+				(VoteKind::Prevote, vote.target_hash, vote.target_number),
+			finality_grandpa::Message::Precommit(vote) =>
+				(VoteKind::Precommit, vote.target_hash, vote.target_number),
+			finality_grandpa::Message::PrimaryPropose(vote) =>
+				(VoteKind::PrimaryPropose, vote.target_hash, vote.target_number),
+		};
+		Some(Self { set_id, round, kind, target_hash, target_number })
+	}
+
+	fn round_key(&self) -> (SetId, RoundNumber) {
+		(self.set_id, self.round)
+	}
+}
+
+/// What a validator signed, also the import/export format.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SigningRecord {
+	/// The genesis hash of the chain.
+	pub genesis_hash: Hash,
+	/// The last slot a block was proposed for.
+	pub last_slot: Option<u64>,
+	/// The set ID and round of the oldest kept votes, votes of earlier rounds are refused.
+	pub first_round: (SetId, RoundNumber),
+	/// The GRANDPA votes of the latest rounds.
+	pub votes: Vec<GrandpaVote>,
+}
+
+impl SigningRecord {
+	/// An empty record for chain `genesis_hash`.
+	pub fn new(genesis_hash: Hash) -> Self {
+		Self { genesis_hash, last_slot: None, first_round: (0, 0), votes: Vec::new() }
+	}
+
+	/// Records a block proposal for `slot`, failing if the last recorded slot is not before.
+	pub fn record_slot(&mut self, slot: u64) -> Result<(), String> {
+		match self.last_slot {
+			Some(last_slot) if slot <= last_slot =>
+				Err(format!("Slot {} is not after slot {}, which has a block", slot, last_slot)),
+			_ => {
+				self.last_slot = Some(slot);
+				Ok(())
+			},
+		}
+	}
+
+	/// Records `vote`, failing if it conflicts with a recorded vote or is older than the recorded
+	/// ones. Returns `false` if the vote was already recorded.
+	pub fn record_vote(&mut self, vote: GrandpaVote) -> Result<bool, String> {
+		if vote.round_key() < self.first_round {
+			return Err(format!(
+				"Round {} of set {} is older than the recorded votes",
+				vote.round, vote.set_id
+			))
+		}
+		let same_round = |recorded: &&GrandpaVote| {
+			recorded.round_key() == vote.round_key() && recorded.kind == vote.kind
+		};
+		let cast: Vec<bool> =
+			self.votes.iter().filter(same_round).map(|recorded| *recorded == vote).collect();
+		if cast.is_empty() {
+			self.votes.push(vote);
+			self.prune();
+			Ok(true)
+		} else if cast.iter().all(|same| *same) {
+			Ok(false)
+		} else {
+			Err(format!(
+				"A conflicting {:?} was cast in round {} of set {}",
+				vote.kind, vote.round, vote.set_id
+			))
+		}
+	}
+
+	/// Adds the slots and votes of `other`, a record of the same chain.
+	pub fn merge(&mut self, other: SigningRecord) -> Result<(), String> {
+		if other.genesis_hash != self.genesis_hash {
+			return Err(format!("The record is of another chain (genesis {})", other.genesis_hash))
+		}
+		self.last_slot = self.last_slot.max(other.last_slot);
+		self.first_round = self.first_round.max(other.first_round);
+		// Conflicting votes are all kept, any vote of their round then being refused.
+		self.votes.extend(other.votes);
+		self.votes.sort();
+		self.votes.dedup();
+		self.prune();
+		Ok(())
+	}
+
+	fn prune(&mut self) {
+		if let Some((set_id, round)) = self.votes.iter().map(GrandpaVote::round_key).max() {
+			let first_round = (set_id, round.saturating_sub(KEPT_ROUNDS - 1));
+			self.first_round = self.first_round.max(first_round);
+		}
+		let first_round = self.first_round;
+		self.votes.retain(|vote| vote.round_key() >= first_round);
+	}
+}
+
+/// Reads the record stored at `path`, if any.
+pub fn read_record(path: &Path) -> Result<Option<SigningRecord>, String> {
+	if !path.exists() {
+		return Ok(None)
+	}
+	let content =
+		std::fs::read(path).map_err(|e| format!("Unable to read {}: {}", path.display(), e))?;
+	serde_json::from_slice(&content)
+		.map(Some)
+		.map_err(|e| format!("Invalid signing record {}: {}", path.display(), e))
+}
+
+/// Writes `record` at `path`. The record is on disk when the function returns, so that a signature
+/// is never produced before it is recorded.
+pub fn write_record(path: &Path, record: &SigningRecord) -> Result<(), String> {
+	let content = serde_json::to_vec_pretty(record).map_err(|e| e.to_string())?;
+	// Written aside then renamed, so that a crash never leaves a truncated file. Both the file and
+	// the rename are synced: otherwise, a power loss could lose the record, or leave an empty file.
+	let tmp_path = path.with_extension("tmp");
+	let parent = match path.parent() {
+		Some(parent) if !parent.as_os_str().is_empty() => parent,
+		_ => Path::new("."),
+	};
+	std::fs::File::create(&tmp_path)
+		.and_then(|mut file| {
+			file.write_all(&content)?;
+			file.sync_all()
+		})
+		.and_then(|_| std::fs::rename(&tmp_path, path))
+		.and_then(|_| std::fs::File::open(parent)?.sync_all())
+		.map_err(|e| format!("Unable to write {}: {}", path.display(), e))
+}
+
+/// The signing record of a running node.
+pub struct SigningRecordDb {
+	path: PathBuf,
+	record: Mutex<SigningRecord>,
+}
+
+impl SigningRecordDb {
+	/// Loads the record stored at `path`. A record of another chain is replaced by an empty one.
+	pub fn open(path: PathBuf, genesis_hash: Hash) -> Result<Self, String> {
+		let record = match read_record(&path)? {
+			Some(record) if record.genesis_hash == genesis_hash => record,
+			Some(record) => {
+				log::warn!(
+					target: LOG_TARGET,
+					"Replacing signing record {} of chain {}",
+					path.display(),
+					record.genesis_hash
+				);
+				SigningRecord::new(genesis_hash)
+			},
+			None => SigningRecord::new(genesis_hash),
+		};
+		Ok(Self { path, record: Mutex::new(record) })
+	}
+
+	/// Records a block proposal for `slot`, see [`SigningRecord::record_slot`].
+	pub fn record_slot(&self, slot: u64) -> Result<(), String> {
+		self.update(|record| record.record_slot(slot).map(|()| true))
+	}
+
+	/// Records `vote`, see [`SigningRecord::record_vote`].
+	pub fn record_vote(&self, vote: GrandpaVote) -> Result<(), String> {
+		self.update(|record| record.record_vote(vote))
+	}
+
+	fn update(
+		&self,
+		f: impl FnOnce(&mut SigningRecord) -> Result<bool, String>,
+	) -> Result<(), String> {
+		let mut record = self.record.lock().expect("Record lock is not poisoned; qed");
+		let mut updated = record.clone();
+		if f(&mut updated)? {
+			// Persisted before signing, so that a crash never forgets a signature.
+			write_record(&self.path, &updated)?;
+			*record = updated;
+		}
+		Ok(())
+	}
+}
+
+/// A proposer factory whose proposers refuse to propose a block for an already used slot.
+pub struct ProtectedProposerFactory<E> {
+	inner: E,
+	db: Arc<SigningRecordDb>,
+}
+
+impl<E> ProtectedProposerFactory<E> {
+	/// Protects the proposers of `inner` with `db`.
+	pub fn new(inner: E, db: Arc<SigningRecordDb>) -> Self {
+		Self { inner, db }
+	}
+}
+
+impl<B, E> Environment<B> for ProtectedProposerFactory<E>
+where
+	B: BlockT,
+	E: Environment<B>,
+{
+	type Proposer = ProtectedProposer<E::Proposer>;
+	type CreateProposer = std::pin::Pin<
+		Box<dyn Future<Output = Result<Self::Proposer, Self::Error>> + Send + 'static>,
+	>;
+	type Error = E::Error;
+
+	fn init(&mut self, parent_header: &B::Header) -> Self::CreateProposer {
+		let db = self.db.clone();
+		Box::pin(
+			self.inner.init(parent_header).map_ok(move |inner| ProtectedProposer { inner, db }),
+		)
+	}
+}
+
+/// A proposer checking the slot of its block against the signing record.
+pub struct ProtectedProposer<P> {
+	inner: P,
+	db: Arc<SigningRecordDb>,
+}
+
+impl<B, P> Proposer<B> for ProtectedProposer<P>
+where
+	B: BlockT,
+	P: Proposer<B>,
+	P::Error: Send,
+	Proposal<B, P::Proof>: Send,
+{
+	type Error = P::Error;
+	type Proposal = Either<P::Proposal, Ready<Result<Proposal<B, P::Proof>, P::Error>>>;
+	type ProofRecording = P::ProofRecording;
+	type Proof = P::Proof;
+
+	fn propose(
+		self,
+		inherent_data: InherentData,
+		inherent_digests: Digest,
+		max_duration: Duration,
+		block_size_limit: Option<usize>,
+	) -> Self::Proposal {
+		let slot = inherent_digests
+			.logs()
+			.iter()
+			.find_map(|log| log.pre_runtime_try_to::<Slot>(&AURA_ENGINE_ID));
+		if let Some(Err(e)) = slot.map(|slot| self.db.record_slot(slot.into())) {
+			log::error!(target: LOG_TARGET, "Refusing to propose a block: {}", e);
+			return Either::Right(future::ready(Err(sp_consensus::Error::Other(e.into()).into())))
+		}
+		Either::Left(self.inner.propose(
+			inherent_data,
+			inherent_digests,
+			max_duration,
+			block_size_limit,
+		))
+	}
+}
+
+/// A keystore refusing to sign GRANDPA votes conflicting with the signing record.
+pub struct ProtectedKeystore {
+	inner: KeystorePtr,
+	db: Arc<SigningRecordDb>,
+}
+
+impl ProtectedKeystore {
+	/// Protects `inner` with `db`.
+	pub fn new(inner: KeystorePtr, db: Arc<SigningRecordDb>) -> Self {
+		Self { inner, db }
+	}
+}
+
+impl Keystore for ProtectedKeystore {
+	fn sr25519_public_keys(&self, key_type: KeyTypeId) -> Vec<sr25519::Public> {
+		self.inner.sr25519_public_keys(key_type)
+	}
+
+	fn sr25519_generate_new(
+		&self,
+		key_type: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<sr25519::Public, Error> {
+		self.inner.sr25519_generate_new(key_type, seed)
+	}
+
+	fn sr25519_sign(
+		&self,
+		key_type: KeyTypeId,
+		public: &sr25519::Public,
+		msg: &[u8],
+	) -> Result<Option<sr25519::Signature>, Error> {
+		self.inner.sr25519_sign(key_type, public, msg)
+	}
+
+	fn sr25519_vrf_sign(
+		&self,
+		key_type: KeyTypeId,
+		public: &sr25519::Public,
+		data: &sr25519::vrf::VrfSignData,
+	) -> Result<Option<sr25519::vrf::VrfSignature>, Error> {
+		self.inner.sr25519_vrf_sign(key_type, public, data)
+	}
+
+	fn sr25519_vrf_pre_output(
+		&self,
+		key_type: KeyTypeId,
+		public: &sr25519::Public,
+		input: &sr25519::vrf::VrfInput,
+	) -> Result<Option<sr25519::vrf::VrfPreOutput>, Error> {
+		self.inner.sr25519_vrf_pre_output(key_type, public, input)
+	}
+
+	fn ed25519_public_keys(&self, key_type: KeyTypeId) -> Vec<ed25519::Public> {
+		self.inner.ed25519_public_keys(key_type)
+	}
+
+	fn ed25519_generate_new(
+		&self,
+		key_type: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<ed25519::Public, Error> {
+		self.inner.ed25519_generate_new(key_type, seed)
+	}
+
+	fn ed25519_sign(
+		&self,
+		key_type: KeyTypeId,
+		public: &ed25519::Public,
+		msg: &[u8],
+	) -> Result<Option<ed25519::Signature>, Error> {
+		if key_type == key_types::GRANDPA {
+			let vote = GrandpaVote::decode(msg)
+				.ok_or_else(|| Error::Other("Refusing to sign an unknown GRANDPA message".into()))?;
+			self.db.record_vote(vote).map_err(|e| {
+				log::error!(target: LOG_TARGET, "Refusing to vote: {}", e);
+				Error::Other(e)
+			})?;
+		}
+		self.inner.ed25519_sign(key_type, public, msg)
+	}
+
+	fn ecdsa_public_keys(&self, key_type: KeyTypeId) -> Vec<ecdsa::Public> {
+		self.inner.ecdsa_public_keys(key_type)
+	}
+
+	fn ecdsa_generate_new(
+		&self,
+		key_type: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<ecdsa::Public, Error> {
+		self.inner.ecdsa_generate_new(key_type, seed)
+	}
+
+	fn ecdsa_sign(
+		&self,
+		key_type: KeyTypeId,
+		public: &ecdsa::Public,
+		msg: &[u8],
+	) -> Result<Option<ecdsa::Signature>, Error> {
+		self.inner.ecdsa_sign(key_type, public, msg)
+	}
+
+	fn ecdsa_sign_prehashed(
+		&self,
+		key_type: KeyTypeId,
+		public: &ecdsa::Public,
+		msg: &[u8; 32],
+	) -> Result<Option<ecdsa::Signature>, Error> {
+		self.inner.ecdsa_sign_prehashed(key_type, public, msg)
+	}
+
+	fn insert(&self, key_type: KeyTypeId, suri: &str, public: &[u8]) -> Result<(), ()> {
+		self.inner.insert(key_type, suri, public)
+	}
+
+	fn keys(&self, key_type: KeyTypeId) -> Result<Vec<Vec<u8>>, Error> {
+		self.inner.keys(key_type)
+	}
+
+	fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+		self.inner.has_keys(public_keys)
+	}
+}
+
+/// The `key signing-record` subcommands. The node must be stopped.
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum SigningRecordCmd {
+	/// Print the signing record of the node.
+	Export(ExportSigningRecordCmd),
+	/// Add an exported signing record to the one of the node.
+	Import(ImportSigningRecordCmd),
+}
+
+/// The `key signing-record export` command.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct ExportSigningRecordCmd {
+	/// The file to write the record to, instead of the standard output.
+	#[arg(long, short)]
+	pub output: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl ExportSigningRecordCmd {
+	/// Exports the record of the node configured by `config`.
+	pub fn run(&self, config: &Configuration) -> sc_cli::Result<()> {
+		let path = record_path(config);
+		let record = read_record(&path)?
+			.ok_or_else(|| format!("No signing record at {}", path.display()))?;
+		match &self.output {
+			Some(output) => write_record(output, &record)?,
+			None => println!(
+				"{}",
+				serde_json::to_string_pretty(&record)
+					.map_err(|e| format!("Unable to serialize: {}", e))?
+			),
+		}
+		Ok(())
+	}
+}
+
+impl CliConfiguration for ExportSigningRecordCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+}
+
+/// The `key signing-record import` command.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct ImportSigningRecordCmd {
+	/// The exported record.
+	pub input: PathBuf,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl ImportSigningRecordCmd {
+	/// Merges the exported record into the one of the node configured by `config`, whose chain
+	/// has genesis `genesis_hash`.
+	pub fn run(&self, config: &Configuration, genesis_hash: Hash) -> sc_cli::Result<()> {
+		let imported = read_record(&self.input)?
+			.ok_or_else(|| format!("{} not found", self.input.display()))?;
+		let path = record_path(config);
+		let mut record = read_record(&path)?
+			.filter(|record| record.genesis_hash == genesis_hash)
+			.unwrap_or_else(|| SigningRecord::new(genesis_hash));
+		record.merge(imported)?;
+		write_record(&path, &record)?;
+		println!(
+			"Last slot: {:?}, {} GRANDPA votes from round {} of set {}",
+			record.last_slot,
+			record.votes.len(),
+			record.first_round.1,
+			record.first_round.0
+		);
+		Ok(())
+	}
+}
+
+impl CliConfiguration for ImportSigningRecordCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Encode;
+
+	fn vote(round: RoundNumber, kind: VoteKind, target_number: BlockNumber) -> GrandpaVote {
+		GrandpaVote {
+			set_id: 1,
+			round,
+			kind,
+			target_hash: Hash::repeat_byte(target_number as u8),
+			target_number,
+		}
+	}
+
+	#[test]
+	fn decodes_signed_votes() {
+		let prevote = finality_grandpa::Prevote::new(Hash::repeat_byte(7), 7);
+		let message = finality_grandpa::Message::<Hash, BlockNumber>::Prevote(prevote);
+		let payload = sp_consensus_grandpa::localized_payload(42, 1, &message);
+
+		assert_eq!(GrandpaVote::decode(&payload), Some(vote(42, VoteKind::Prevote, 7)));
+		assert_eq!(GrandpaVote::decode(&(42u64, 1u64).encode()), None);
+	}
+
+	#[test]
+	fn refuses_double_signing() {
+		let mut record = SigningRecord::new(Hash::zero());
+
+		assert!(record.record_slot(10).is_ok());
+		assert!(record.record_slot(10).is_err());
+		assert!(record.record_slot(9).is_err());
+		assert!(record.record_slot(11).is_ok());
+
+		assert_eq!(record.record_vote(vote(5, VoteKind::Prevote, 7)), Ok(true));
+		assert_eq!(record.record_vote(vote(5, VoteKind::Prevote, 7)), Ok(false));
+		assert!(record.record_vote(vote(5, VoteKind::Prevote, 8)).is_err());
+		assert_eq!(record.record_vote(vote(5, VoteKind::Precommit, 8)), Ok(true));
+		assert_eq!(record.record_vote(vote(5 + KEPT_ROUNDS, VoteKind::Prevote, 9)), Ok(true));
+		assert_eq!(record.votes.len(), 1);
+		assert!(record.record_vote(vote(5, VoteKind::Precommit, 8)).is_err());
+	}
+
+	#[test]
+	fn merges_records() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("record.json");
+		let db = SigningRecordDb::open(path.clone(), Hash::zero()).unwrap();
+		db.record_slot(10).unwrap();
+		db.record_vote(vote(5, VoteKind::Prevote, 7)).unwrap();
+		let mut other = SigningRecord::new(Hash::zero());
+		other.record_slot(12).unwrap();
+		other.record_vote(vote(5, VoteKind::Prevote, 8)).unwrap();
+
+		let mut record = read_record(&path).unwrap().unwrap();
+		record.merge(other).unwrap();
+
+		assert_eq!(record.last_slot, Some(12));
+		assert!(record.record_vote(vote(5, VoteKind::Prevote, 7)).is_err());
+		assert!(record.merge(SigningRecord::new(Hash::repeat_byte(1))).is_err());
+	}
+}
@@ -0,0 +1,257 @@
+//! Decoding of the runtime events of a block.
+//!
+//! Events are decoded with the metadata of the runtime which deposited them (the runtime of the
+//! parent block's state), so that the events of blocks produced before a runtime upgrade are
+//! decoded correctly. They are described with the metadata's type information: the names of the
+//! pallet and of the event variant, and the accounts and LOCs the event refers to, found in the
+//! fields whose declared type is an account ID or a LOC ID.
+
+use std::{
+	collections::HashMap,
+	sync::{Arc, Mutex},
+};
+
+use codec::{Compact, Decode, DecodeAll, Encode};
+use frame_metadata::v14::RuntimeMetadataV14;
+use logion_node_runtime::{opaque::Block, AccountId, BlockNumber, Hash, LocId};
+use sc_client_api::{Backend, StorageProvider};
+use scale_info::{form::PortableForm, PortableRegistry, TypeDef, Variant};
+use serde::{Deserialize, Serialize};
+use sp_blockchain::HeaderBackend;
+use sp_core::storage::{well_known_keys::CODE, StorageKey};
+use sp_runtime::traits::{Header, Zero};
+
+use crate::{
+	check_upgrade::{code_at, WasmRuntime},
+	scale_json::{decode_value, storage_type},
+};
+
+/// A runtime event, with the accounts and LOCs it refers to.
+#[derive(Serialize, Deserialize, Encode, Decode, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodedEvent {
+	/// The number of the block which deposited the event.
+	pub block_number: BlockNumber,
+	/// The hash of the block which deposited the event.
+	pub block_hash: Hash,
+	/// The index of the event in the block.
+	pub event_index: u32,
+	/// The name of the pallet which deposited the event (e.g. `LogionLoc`).
+	pub pallet: String,
+	/// The name of the event (e.g. `LocCreated`).
+	pub variant: String,
+	/// The accounts found in the event's fields.
+	pub accounts: Vec<AccountId>,
+	/// The LOC IDs found in the event's fields.
+	pub loc_ids: Vec<LocId>,
+	/// The SCALE-encoded `RuntimeEvent`.
+	#[serde(with = "sp_core::bytes")]
+	pub data: Vec<u8>,
+}
+
+pub(crate) fn events_key() -> StorageKey {
+	StorageKey([sp_core::twox_128(b"System"), sp_core::twox_128(b"Events")].concat())
+}
+
+/// Decodes the events of blocks, caching the metadata of the runtimes by code hash.
+#[derive(Default)]
+pub struct EventDecoder {
+	metadata: Mutex<HashMap<Hash, Arc<RuntimeMetadataV14>>>,
+}
+
+impl EventDecoder {
+	/// Reads and decodes the events deposited by block `hash`.
+	pub fn block_events<C, B>(&self, client: &C, hash: Hash) -> Result<Vec<DecodedEvent>, String>
+	where
+		C: StorageProvider<Block, B> + HeaderBackend<Block>,
+		B: Backend<Block>,
+	{
+		let header = client
+			.header(hash)
+			.map_err(|e| e.to_string())?
+			.ok_or_else(|| format!("Block {} not found", hash))?;
+		let Some(data) = client.storage(hash, &events_key()).map_err(|e| e.to_string())? else {
+			return Ok(Vec::new())
+		};
+		// A block is executed by the runtime of its parent's state.
+		let state_hash = if header.number().is_zero() { hash } else { *header.parent_hash() };
+		let metadata = self.metadata(client, state_hash)?;
+		decode_events(&metadata, *header.number(), hash, &data.0)
+			.map_err(|e| format!("Unable to decode events of block {}: {}", hash, e))
+	}
+
+	fn metadata<C, B>(
+		&self,
+		client: &C,
+		state_hash: Hash,
+	) -> Result<Arc<RuntimeMetadataV14>, String>
+	where
+		C: StorageProvider<Block, B>,
+		B: Backend<Block>,
+	{
+		let code_hash = client
+			.storage_hash(state_hash, &StorageKey(CODE.to_vec()))
+			.map_err(|e| e.to_string())?
+			.ok_or_else(|| format!("No runtime code in state of block {}", state_hash))?;
+		if let Some(metadata) = self.cached(&code_hash) {
+			return Ok(metadata)
+		}
+		let metadata = Arc::new(WasmRuntime::load(&code_at(client, state_hash)?)?.metadata);
+		self.metadata
+			.lock()
+			.expect("Metadata lock is not poisoned; qed")
+			.insert(code_hash, metadata.clone());
+		Ok(metadata)
+	}
+
+	fn cached(&self, code_hash: &Hash) -> Option<Arc<RuntimeMetadataV14>> {
+		self.metadata.lock().expect("Metadata lock is not poisoned; qed").get(code_hash).cloned()
+	}
+}
+
+/// Decodes `data`, the encoded `System.Events` of a block, with `metadata`.
+pub fn decode_events(
+	metadata: &RuntimeMetadataV14,
+	block_number: BlockNumber,
+	block_hash: Hash,
+	data: &[u8],
+) -> Result<Vec<DecodedEvent>, String> {
+	let types = &metadata.types;
+	let events_type =
+		storage_type(metadata, "System", "Events").ok_or("System.Events not found in metadata")?;
+	let record_type = match types.resolve(events_type).map(|ty| &ty.type_def) {
+		Some(TypeDef::Sequence(sequence)) => sequence.type_param.id,
+		_ => return Err("System.Events is not a sequence".into()),
+	};
+	let record_fields = match types.resolve(record_type).map(|ty| &ty.type_def) {
+		Some(TypeDef::Composite(composite)) => &composite.fields,
+		_ => return Err("Event records are not structs".into()),
+	};
+
+	let input = &mut &data[..];
+	let count = Compact::<u32>::decode(input).map_err(|e| e.to_string())?.0;
+	let mut events = Vec::new();
+	for event_index in 0..count {
+		let mut event = None;
+		for field in record_fields.iter() {
+			let start = *input;
+			decode_value(types, field.ty.id, input).map_err(|e| e.to_string())?;
+			if field.name.as_deref() == Some("event") {
+				let data = start[..start.len() - input.len()].to_vec();
+				event = Some(decode_event(
+					types,
+					field.ty.id,
+					block_number,
+					block_hash,
+					event_index,
+					data,
+				));
+			}
+		}
+		events.push(event.ok_or("Event records have no event")?);
+	}
+	Ok(events)
+}
+
+/// Describes the encoded `RuntimeEvent` `data`, of type `event_type`.
+fn decode_event(
+	types: &PortableRegistry,
+	event_type: u32,
+	block_number: BlockNumber,
+	block_hash: Hash,
+	event_index: u32,
+	data: Vec<u8>,
+) -> DecodedEvent {
+	let mut accounts = Vec::new();
+	let mut loc_ids = Vec::new();
+	let (pallet, variant) = describe(types, event_type, &data, &mut accounts, &mut loc_ids)
+		.unwrap_or_else(|| ("Unknown".into(), "Unknown".into()));
+	DecodedEvent { block_number, block_hash, event_index, pallet, variant, accounts, loc_ids, data }
+}
+
+fn describe(
+	types: &PortableRegistry,
+	event_type: u32,
+	data: &[u8],
+	accounts: &mut Vec<AccountId>,
+	loc_ids: &mut Vec<LocId>,
+) -> Option<(String, String)> {
+	// `RuntimeEvent` has one variant per pallet, wrapping the pallet's `Event` type.
+	let (pallet_index, data) = data.split_first()?;
+	let pallet = variants(types, event_type)?
+		.iter()
+		.find(|variant| variant.index == *pallet_index)?;
+	let (event_index, mut input) = data.split_first()?;
+	let event = variants(types, pallet.fields.first()?.ty.id)?
+		.iter()
+		.find(|variant| variant.index == *event_index)?;
+
+	for field in event.fields.iter() {
+		let start = input;
+		decode_value(types, field.ty.id, &mut input).ok()?;
+		let mut field_data = &start[..start.len() - input.len()];
+		match field.type_name.as_deref() {
+			Some(type_name) if type_name.contains("AccountId") =>
+				accounts.extend(decode_one_or_optional::<AccountId>(&mut field_data)),
+			Some(type_name) if type_name.contains("LocId") =>
+				loc_ids.extend(decode_one_or_optional::<LocId>(&mut field_data)),
+			_ => {},
+		}
+	}
+	Some((pallet.name.clone(), event.name.clone()))
+}
+
+fn variants(types: &PortableRegistry, id: u32) -> Option<&[Variant<PortableForm>]> {
+	match &types.resolve(id)?.type_def {
+		TypeDef::Variant(def) => Some(&def.variants),
+		_ => None,
+	}
+}
+
+fn decode_one_or_optional<T: Decode>(data: &mut &[u8]) -> Option<T> {
+	let mut copy = *data;
+	T::decode_all(&mut copy).ok().or_else(|| Option::<T>::decode_all(data).ok().flatten())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::scale_json::native_metadata;
+	use frame_system::{EventRecord, Phase};
+	use logion_node_runtime::RuntimeEvent;
+	use sp_keyring::Sr25519Keyring;
+
+	#[test]
+	fn decodes_events_with_metadata() {
+		let account = Sr25519Keyring::Alice.to_account_id();
+		let new_account =
+			RuntimeEvent::System(frame_system::Event::NewAccount { account: account.clone() });
+		let loc_created = RuntimeEvent::LogionLoc(pallet_logion_loc::Event::LocCreated(42));
+		let records: Vec<EventRecord<RuntimeEvent, Hash>> = [new_account.clone(), loc_created]
+			.into_iter()
+			.map(|event| EventRecord { phase: Phase::ApplyExtrinsic(1), event, topics: vec![] })
+			.collect();
+		let block_hash = Hash::repeat_byte(1);
+
+		let events = decode_events(&native_metadata(), 7, block_hash, &records.encode()).unwrap();
+
+		assert_eq!(events.len(), 2);
+		assert_eq!(
+			events[0],
+			DecodedEvent {
+				block_number: 7,
+				block_hash,
+				event_index: 0,
+				pallet: "System".into(),
+				variant: "NewAccount".into(),
+				accounts: vec![account],
+				loc_ids: vec![],
+				data: new_account.encode(),
+			}
+		);
+		assert_eq!(events[1].pallet, "LogionLoc");
+		assert_eq!(events[1].variant, "LocCreated");
+		assert_eq!(events[1].loc_ids, vec![42]);
+		assert!(decode_events(&native_metadata(), 7, block_hash, &[4, 0]).is_err());
+	}
+}
@@ -0,0 +1,271 @@
+//! Builds the chain spec of a local test chain from the state of an existing chain.
+//!
+//! The storage of the forked chain is kept, except:
+//! - the storage of the pallets handling consensus and sudo, and the one of `System` (except the
+//!   accounts), which come from a new genesis with the given authorities and sudo key;
+//! - the storage of the pruned pallets, reset to their genesis state.
+//!
+//! The sudo account is endowed so that it can pay the fees of its calls. The runtime code is the
+//! one of the forked chain. Child tries are not forked.
+
+use std::{
+	collections::BTreeMap,
+	fs,
+	path::{Path, PathBuf},
+	sync::Arc,
+};
+
+use codec::{Decode, Encode};
+use logion_node_runtime::{opaque::Block, AccountId, Balance, Nonce, Runtime};
+use sc_cli::{CliConfiguration, DatabaseParams, SharedParams};
+use sc_client_api::{Backend, StorageProvider, UsageProvider};
+use sc_service::ChainSpec as _;
+use sp_runtime::BuildStorage;
+
+use crate::chain_spec::{authority_keys_from_seed, fork_off_config, INITIAL_BALANCE};
+
+pub(crate) type StorageMap = BTreeMap<Vec<u8>, Vec<u8>>;
+
+type AccountInfo =
+	frame_system::AccountInfo<Nonce, <Runtime as frame_system::Config>::AccountData>;
+
+/// The pallets whose storage comes from the new genesis.
+const RESET_PALLETS: [&str; 5] = ["Aura", "Grandpa", "Session", "ValidatorSet", "Sudo"];
+
+/// The well-known key of the GRANDPA authorities, written by older runtimes.
+const GRANDPA_AUTHORITIES_KEY: &[u8] = b":grandpa_authorities";
+
+const CODE_KEY: &[u8] = b":code";
+
+fn pallet_prefix(pallet: &str) -> Vec<u8> {
+	sp_core::twox_128(pallet.as_bytes()).to_vec()
+}
+
+fn storage_value_key(pallet: &str, storage: &str) -> Vec<u8> {
+	[sp_core::twox_128(pallet.as_bytes()), sp_core::twox_128(storage.as_bytes())].concat()
+}
+
+/// The `fork-off` command.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct ForkOffCmd {
+	/// The forked state: a raw chain spec produced by `export-state`, or `db` to read the last
+	/// finalized state of the local database of `--chain`.
+	#[arg(long)]
+	pub from: String,
+
+	/// The development seed of an authority of the new chain (e.g. `Alice`). May be repeated.
+	#[arg(long = "authority", default_value = "Alice")]
+	pub authorities: Vec<String>,
+
+	/// The sudo account of the new chain, the account of the first authority by default.
+	#[arg(long)]
+	pub sudo: Option<AccountId>,
+
+	/// A pallet whose storage is reset to its genesis state (e.g. `Recovery`). May be repeated.
+	#[arg(long)]
+	pub prune: Vec<String>,
+
+	/// The file to write the chain spec to, standard output by default.
+	#[arg(long)]
+	pub output: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl ForkOffCmd {
+	/// Returns true if the forked state has to be read from the local database.
+	pub fn from_db(&self) -> bool {
+		self.from == "db"
+	}
+
+	/// Builds the chain spec of the fork, with the state read from `client` when forking off the
+	/// local database.
+	pub fn run<C, B>(&self, client: Option<Arc<C>>) -> sc_cli::Result<()>
+	where
+		C: StorageProvider<Block, B> + UsageProvider<Block>,
+		B: Backend<Block>,
+	{
+		let forked = match client {
+			Some(client) => {
+				let finalized_hash = client.usage_info().chain.finalized_hash;
+				sc_service::chain_ops::export_raw_state(client, finalized_hash)?.top
+			},
+			None => read_raw_state(Path::new(&self.from))?,
+		};
+
+		let authorities: Vec<_> =
+			self.authorities.iter().map(|seed| authority_keys_from_seed(seed)).collect();
+		let sudo = match &self.sudo {
+			Some(sudo) => sudo.clone(),
+			None => authorities.first().ok_or("At least one authority is required")?.0.clone(),
+		};
+		let spec = fork_off_config(authorities, sudo.clone())?;
+		let genesis = spec.as_storage_builder().build_storage()?.top;
+
+		let mut state = fork_off(forked, genesis, &self.prune);
+		endow(&mut state, &sudo, INITIAL_BALANCE);
+
+		let mut json: serde_json::Value = serde_json::from_str(&spec.as_json(true)?)
+			.map_err(|e| format!("Unable to build chain spec: {}", e))?;
+		json["genesis"]["raw"]["top"] = state
+			.iter()
+			.map(|(key, value)| {
+				let value = serde_json::Value::String(sp_core::bytes::to_hex(value, false));
+				(sp_core::bytes::to_hex(key, false), value)
+			})
+			.collect::<serde_json::Map<_, _>>()
+			.into();
+		let json = serde_json::to_string_pretty(&json)
+			.map_err(|e| format!("Unable to serialize: {}", e))?;
+		match &self.output {
+			Some(path) => fs::write(path, json)?,
+			None => println!("{}", json),
+		}
+		Ok(())
+	}
+}
+
+impl CliConfiguration for ForkOffCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+pub(crate) fn read_raw_state(path: &Path) -> Result<StorageMap, String> {
+	let content =
+		fs::read(path).map_err(|e| format!("Unable to read {}: {}", path.display(), e))?;
+	let spec: serde_json::Value = serde_json::from_slice(&content)
+		.map_err(|e| format!("Unable to parse {}: {}", path.display(), e))?;
+	let top = spec
+		.pointer("/genesis/raw/top")
+		.and_then(|top| top.as_object())
+		.ok_or_else(|| format!("{} is not a raw chain spec", path.display()))?;
+	top.iter()
+		.map(|(key, value)| {
+			let value = value.as_str().ok_or_else(|| format!("Invalid value for key {}", key))?;
+			let key = sp_core::bytes::from_hex(key).map_err(|e| format!("{}: {}", key, e))?;
+			let value = sp_core::bytes::from_hex(value).map_err(|e| e.to_string())?;
+			Ok((key, value))
+		})
+		.collect()
+}
+
+/// Merges the `forked` state with the `genesis` of the new chain.
+fn fork_off(forked: StorageMap, genesis: StorageMap, pruned_pallets: &[String]) -> StorageMap {
+	let accounts_prefix = storage_value_key("System", "Account");
+	let reset_prefixes: Vec<Vec<u8>> = RESET_PALLETS
+		.iter()
+		.copied()
+		.chain(pruned_pallets.iter().map(String::as_str))
+		.chain(["System"])
+		.map(pallet_prefix)
+		.collect();
+	let is_reset = |key: &Vec<u8>| {
+		key == GRANDPA_AUTHORITIES_KEY ||
+			(!key.starts_with(&accounts_prefix) &&
+				reset_prefixes.iter().any(|prefix| key.starts_with(prefix)))
+	};
+
+	let mut state: StorageMap = forked.into_iter().filter(|(key, _)| !is_reset(key)).collect();
+	for (key, value) in genesis {
+		if key.starts_with(&accounts_prefix) {
+			// Forked accounts are kept, genesis only adds the ones of new authorities.
+			state.entry(key).or_insert(value);
+		} else if is_reset(&key) && key != CODE_KEY {
+			state.insert(key, value);
+		}
+	}
+	state
+}
+
+/// Sets the free balance of `account` to at least `amount`, updating the total issuance.
+fn endow(state: &mut StorageMap, account: &AccountId, amount: Balance) {
+	let account_key = frame_system::Account::<Runtime>::hashed_key_for(account);
+	let mut info: AccountInfo = state
+		.get(&account_key)
+		.and_then(|value| AccountInfo::decode(&mut &value[..]).ok())
+		.unwrap_or_default();
+	let added = amount.saturating_sub(info.data.free);
+	info.data.free += added;
+	info.providers = info.providers.max(1);
+	state.insert(account_key, info.encode());
+
+	let issuance_key = storage_value_key("Balances", "TotalIssuance");
+	let issuance = state
+		.get(&issuance_key)
+		.and_then(|value| Balance::decode(&mut &value[..]).ok())
+		.unwrap_or_default();
+	state.insert(issuance_key, issuance.saturating_add(added).encode());
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn fork_off_resets_consensus_and_keeps_accounts() {
+		let account_key = [storage_value_key("System", "Account"), vec![1]].concat();
+		let new_account_key = [storage_value_key("System", "Account"), vec![2]].concat();
+		let number_key = storage_value_key("System", "Number");
+		let aura_key = storage_value_key("Aura", "Authorities");
+		let loc_key = storage_value_key("LogionLoc", "LocMap");
+		let recovery_key = storage_value_key("Recovery", "Recoverable");
+		let forked = StorageMap::from([
+			(CODE_KEY.to_vec(), b"forked code".to_vec()),
+			(account_key.clone(), b"forked account".to_vec()),
+			(number_key.clone(), b"forked number".to_vec()),
+			(aura_key.clone(), b"forked authorities".to_vec()),
+			(loc_key.clone(), b"forked loc".to_vec()),
+			(recovery_key.clone(), b"forked recovery".to_vec()),
+			(GRANDPA_AUTHORITIES_KEY.to_vec(), b"forked grandpa".to_vec()),
+		]);
+		let genesis = StorageMap::from([
+			(CODE_KEY.to_vec(), b"genesis code".to_vec()),
+			(account_key.clone(), b"genesis account".to_vec()),
+			(new_account_key.clone(), b"genesis new account".to_vec()),
+			(number_key.clone(), b"genesis number".to_vec()),
+			(aura_key.clone(), b"genesis authorities".to_vec()),
+		]);
+
+		let state = fork_off(forked, genesis, &["Recovery".to_string()]);
+
+		assert_eq!(
+			state,
+			StorageMap::from([
+				(CODE_KEY.to_vec(), b"forked code".to_vec()),
+				(account_key, b"forked account".to_vec()),
+				(new_account_key, b"genesis new account".to_vec()),
+				(number_key, b"genesis number".to_vec()),
+				(aura_key, b"genesis authorities".to_vec()),
+				(loc_key, b"forked loc".to_vec()),
+			])
+		);
+	}
+
+	#[test]
+	fn endow_updates_total_issuance() {
+		let account = AccountId::new([1; 32]);
+		let issuance_key = storage_value_key("Balances", "TotalIssuance");
+		let mut state = StorageMap::from([(issuance_key.clone(), 1_000u128.encode())]);
+
+		endow(&mut state, &account, 500);
+
+		let info = AccountInfo::decode(
+			&mut &state[&frame_system::Account::<Runtime>::hashed_key_for(&account)][..],
+		)
+		.unwrap();
+		assert_eq!(info.data.free, 500);
+		assert_eq!(info.providers, 1);
+		assert_eq!(Balance::decode(&mut &state[&issuance_key][..]).unwrap(), 1_500);
+	}
+}
@@ -0,0 +1,485 @@
+//! Indexes the Logion events of finalized blocks in an embedded database.
+//!
+//! Only finalized blocks are indexed, so that the index never has to be rolled back. The number
+//! of the last indexed block is committed with the events of the block: after a restart, or if
+//! finality notifications were skipped, indexing resumes with the next block.
+//!
+//! Indexing a block requires its state, a node indexing the whole history must run with
+//! `--state-pruning archive`. Otherwise, indexing starts with the first block whose state is
+//! available, or with the block given by `--logion-indexer-from`. Blocks whose events cannot be
+//! read or decoded are skipped and recorded, see [`EventPage::skipped_blocks`].
+
+use std::{path::Path, sync::Arc};
+
+use codec::{Decode, Encode};
+use futures::StreamExt;
+use logion_node_runtime::{opaque::Block, AccountId, BlockNumber, LocId};
+use sc_client_api::{Backend, BlockchainEvents, StorageProvider};
+use serde::{Deserialize, Serialize};
+use sp_blockchain::HeaderBackend;
+
+use crate::events::{events_key, DecodedEvent, EventDecoder};
+
+const LOG_TARGET: &str = "logion-indexer";
+
+/// The pallets whose events are indexed.
+pub const INDEXED_PALLETS: [&str; 6] =
+	["LogionLoc", "Vote", "Vault", "VerifiedRecovery", "LogionTreasury", "CommunityTreasury"];
+
+/// The default number of events in a page.
+pub const DEFAULT_PAGE_SIZE: u32 = 20;
+
+/// The maximum number of events in a page.
+pub const MAX_PAGE_SIZE: u32 = 100;
+
+/// The maximum number of events read by a query, whether they match it or not.
+pub const MAX_SCANNED_EVENTS: usize = 10_000;
+
+/// Events, by block number and event index.
+const EVENTS: u8 = 0;
+/// Event references, by account then block number and event index.
+const BY_ACCOUNT: u8 = 1;
+/// Event references, by LOC ID then block number and event index.
+const BY_LOC: u8 = 2;
+/// Indexer state.
+const META: u8 = 3;
+const COLUMNS: u8 = 4;
+
+const LAST_INDEXED_KEY: &[u8] = b"last_indexed";
+const SKIPPED_BLOCKS_KEY: &[u8] = b"skipped_blocks";
+
+/// Identifies an indexed event.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct EventId {
+	/// The number of the block which deposited the event.
+	pub block_number: BlockNumber,
+	/// The index of the event in the block.
+	pub event_index: u32,
+}
+
+impl EventId {
+	fn key(&self) -> [u8; 8] {
+		let mut key = [0u8; 8];
+		key[..4].copy_from_slice(&self.block_number.to_be_bytes());
+		key[4..].copy_from_slice(&self.event_index.to_be_bytes());
+		key
+	}
+
+	fn from_key(key: &[u8]) -> Option<Self> {
+		let key: [u8; 8] = key.try_into().ok()?;
+		Some(Self {
+			block_number: BlockNumber::from_be_bytes(key[..4].try_into().ok()?),
+			event_index: u32::from_be_bytes(key[4..].try_into().ok()?),
+		})
+	}
+}
+
+/// A query of indexed events. All criteria are optional and combined.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EventQuery {
+	/// Only events referring to this account.
+	pub account: Option<AccountId>,
+	/// Only events referring to this LOC.
+	pub loc_id: Option<LocId>,
+	/// Only events deposited by this pallet (e.g. `LogionLoc`).
+	pub pallet: Option<String>,
+	/// Only events with this name (e.g. `LocCreated`).
+	pub variant: Option<String>,
+	/// Only events deposited by this block or later.
+	pub from_block: Option<BlockNumber>,
+	/// Only events deposited by this block or earlier.
+	pub to_block: Option<BlockNumber>,
+	/// Only events after this one, i.e. the `next` cursor of the previous page.
+	pub after: Option<EventId>,
+	/// The maximum number of events to return, at most [`MAX_PAGE_SIZE`].
+	pub limit: Option<u32>,
+}
+
+impl EventQuery {
+	fn matches(&self, event: &DecodedEvent) -> bool {
+		self.pallet.as_ref().map_or(true, |pallet| *pallet == event.pallet) &&
+			self.variant.as_ref().map_or(true, |variant| *variant == event.variant) &&
+			self.account.as_ref().map_or(true, |account| event.accounts.contains(account)) &&
+			self.loc_id.as_ref().map_or(true, |loc_id| event.loc_ids.contains(loc_id))
+	}
+
+	fn first_event(&self) -> EventId {
+		let from = EventId { block_number: self.from_block.unwrap_or_default(), event_index: 0 };
+		match self.after {
+			Some(after) => {
+				let next = match after.event_index.checked_add(1) {
+					Some(event_index) => EventId { event_index, ..after },
+					None => EventId {
+						block_number: after.block_number.saturating_add(1),
+						event_index: 0,
+					},
+				};
+				if next.key() > from.key() {
+					next
+				} else {
+					from
+				}
+			},
+			None => from,
+		}
+	}
+}
+
+/// A page of indexed events.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct EventPage {
+	/// The events, in chain order.
+	pub events: Vec<DecodedEvent>,
+	/// The cursor to pass as `after` to get the next page, `null` if there are no more events. A
+	/// page may have fewer events than requested, or none, and still a next one: a query reads at
+	/// most [`MAX_SCANNED_EVENTS`] events.
+	pub next: Option<EventId>,
+	/// The number of the last indexed block.
+	pub last_indexed_block: Option<BlockNumber>,
+	/// The blocks of the queried range whose events could not be indexed.
+	pub skipped_blocks: Vec<BlockNumber>,
+}
+
+/// The embedded database of the indexer.
+pub struct IndexerDb {
+	db: parity_db::Db,
+}
+
+impl IndexerDb {
+	/// Opens the database at `path`, creating it if needed.
+	pub fn open(path: &Path) -> Result<Self, String> {
+		let mut options = parity_db::Options::with_columns(path, COLUMNS);
+		for column in [EVENTS, BY_ACCOUNT, BY_LOC] {
+			options.columns[column as usize].btree_index = true;
+		}
+		let db = parity_db::Db::open_or_create(&options)
+			.map_err(|e| format!("Unable to open indexer database: {}", e))?;
+		Ok(Self { db })
+	}
+
+	/// Returns the number of the last indexed block.
+	pub fn last_indexed(&self) -> Result<Option<BlockNumber>, String> {
+		self.db
+			.get(META, LAST_INDEXED_KEY)
+			.map_err(|e| e.to_string())?
+			.map(|value| BlockNumber::decode(&mut &value[..]).map_err(|e| e.to_string()))
+			.transpose()
+	}
+
+	/// Returns the blocks whose events could not be indexed.
+	pub fn skipped_blocks(&self) -> Result<Vec<BlockNumber>, String> {
+		self.db
+			.get(META, SKIPPED_BLOCKS_KEY)
+			.map_err(|e| e.to_string())?
+			.map(|value| Vec::<BlockNumber>::decode(&mut &value[..]).map_err(|e| e.to_string()))
+			.transpose()
+			.map(Option::unwrap_or_default)
+	}
+
+	/// Records block `block_number` as skipped and marks it as indexed, atomically.
+	pub fn skip_block(&self, block_number: BlockNumber) -> Result<(), String> {
+		let mut skipped_blocks = self.skipped_blocks()?;
+		skipped_blocks.push(block_number);
+		self.db
+			.commit([
+				(META, SKIPPED_BLOCKS_KEY.to_vec(), Some(skipped_blocks.encode())),
+				(META, LAST_INDEXED_KEY.to_vec(), Some(block_number.encode())),
+			])
+			.map_err(|e| e.to_string())
+	}
+
+	/// Stores the events of block `block_number` and marks it as indexed, atomically.
+	pub fn index_block(
+		&self,
+		block_number: BlockNumber,
+		events: &[DecodedEvent],
+	) -> Result<(), String> {
+		let mut changes = Vec::new();
+		let indexed = |event: &&DecodedEvent| INDEXED_PALLETS.contains(&event.pallet.as_str());
+		for event in events.iter().filter(indexed) {
+			let key = EventId { block_number, event_index: event.event_index }.key();
+			changes.push((EVENTS, key.to_vec(), Some(event.encode())));
+			for account in event.accounts.iter() {
+				let account: &[u8] = account.as_ref();
+				changes.push((BY_ACCOUNT, [account, &key[..]].concat(), Some(Vec::new())));
+			}
+			for loc_id in event.loc_ids.iter() {
+				let loc_id = loc_id.to_be_bytes();
+				changes.push((BY_LOC, [&loc_id[..], &key[..]].concat(), Some(Vec::new())));
+			}
+		}
+		changes.push((META, LAST_INDEXED_KEY.to_vec(), Some(block_number.encode())));
+		self.db.commit(changes).map_err(|e| e.to_string())
+	}
+
+	/// Returns a page of the events matching `query`.
+	pub fn query(&self, query: &EventQuery) -> Result<EventPage, String> {
+		let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE) as usize;
+		let (column, prefix) = match (&query.account, &query.loc_id) {
+			(Some(account), _) => (BY_ACCOUNT, AsRef::<[u8]>::as_ref(account).to_vec()),
+			(None, Some(loc_id)) => (BY_LOC, loc_id.to_be_bytes().to_vec()),
+			(None, None) => (EVENTS, Vec::new()),
+		};
+
+		let mut iter = self.db.iter(column).map_err(|e| e.to_string())?;
+		iter.seek(&[&prefix[..], &query.first_event().key()[..]].concat())
+			.map_err(|e| e.to_string())?;
+		let mut events = Vec::new();
+		let mut next = None;
+		let mut scanned = 0;
+		let mut last_scanned = None;
+		while let Some((key, value)) = iter.next().map_err(|e| e.to_string())? {
+			if !key.starts_with(&prefix) {
+				break
+			}
+			let event_key = &key[prefix.len()..];
+			let event_id = EventId::from_key(event_key).ok_or("Invalid index key")?;
+			if query.to_block.map_or(false, |to_block| event_id.block_number > to_block) {
+				break
+			}
+			if scanned == MAX_SCANNED_EVENTS {
+				next = last_scanned;
+				break
+			}
+			scanned += 1;
+			last_scanned = Some(event_id);
+			let value = if column == EVENTS {
+				value
+			} else {
+				self.db
+					.get(EVENTS, event_key)
+					.map_err(|e| e.to_string())?
+					.ok_or("Missing indexed event")?
+			};
+			let event = DecodedEvent::decode(&mut &value[..]).map_err(|e| e.to_string())?;
+			if !query.matches(&event) {
+				continue
+			}
+			if events.len() == limit {
+				next = events.last().map(|last: &DecodedEvent| EventId {
+					block_number: last.block_number,
+					event_index: last.event_index,
+				});
+				break
+			}
+			events.push(event);
+		}
+		let in_range = |block_number: &BlockNumber| {
+			query.from_block.map_or(true, |from_block| *block_number >= from_block) &&
+				query.to_block.map_or(true, |to_block| *block_number <= to_block)
+		};
+		Ok(EventPage {
+			events,
+			next,
+			last_indexed_block: self.last_indexed()?,
+			skipped_blocks: self.skipped_blocks()?.into_iter().filter(in_range).collect(),
+		})
+	}
+}
+
+/// Indexes the events of each newly finalized block. On first run, indexing starts with block
+/// `from` if given, otherwise with the first block whose state is available.
+pub async fn run_indexer<C, B>(client: Arc<C>, db: Arc<IndexerDb>, from: Option<BlockNumber>)
+where
+	C: StorageProvider<Block, B> + HeaderBackend<Block> + BlockchainEvents<Block>,
+	B: Backend<Block>,
+{
+	let decoder = EventDecoder::default();
+	let mut finality_notifications = client.finality_notification_stream();
+	let finalized_number = client.info().finalized_number;
+	index_finalized(client.as_ref(), &decoder, db.as_ref(), from, finalized_number);
+	while let Some(notification) = finality_notifications.next().await {
+		let finalized_number = *sp_runtime::traits::Header::number(&notification.header);
+		index_finalized(client.as_ref(), &decoder, db.as_ref(), from, finalized_number);
+	}
+}
+
+/// Indexes the blocks following the last indexed one, up to `finalized_number`. Blocks whose
+/// events cannot be read are skipped. Stops if the database cannot be written, the remaining
+/// blocks being indexed on next finality notification.
+fn index_finalized<C, B>(
+	client: &C,
+	decoder: &EventDecoder,
+	db: &IndexerDb,
+	from: Option<BlockNumber>,
+	finalized_number: BlockNumber,
+) where
+	C: StorageProvider<Block, B> + HeaderBackend<Block>,
+	B: Backend<Block>,
+{
+	let first = match db.last_indexed() {
+		Ok(Some(last_indexed)) => last_indexed + 1,
+		Ok(None) => from.unwrap_or_else(|| first_block_with_state(client, finalized_number)),
+		Err(e) => {
+			log::error!(target: LOG_TARGET, "Unable to read indexer state: {}", e);
+			return
+		},
+	};
+	for block_number in first..=finalized_number {
+		let result = match read_events(client, decoder, block_number) {
+			Ok(events) => db.index_block(block_number, &events),
+			Err(e) => {
+				log::warn!(target: LOG_TARGET, "Skipping block {}: {}", block_number, e);
+				db.skip_block(block_number)
+			},
+		};
+		if let Err(e) = result {
+			log::error!(target: LOG_TARGET, "Unable to index block {}: {}", block_number, e);
+			return
+		}
+		log::debug!(target: LOG_TARGET, "Indexed block {}", block_number);
+	}
+}
+
+fn read_events<C, B>(
+	client: &C,
+	decoder: &EventDecoder,
+	block_number: BlockNumber,
+) -> Result<Vec<DecodedEvent>, String>
+where
+	C: StorageProvider<Block, B> + HeaderBackend<Block>,
+	B: Backend<Block>,
+{
+	let hash = client
+		.hash(block_number)
+		.map_err(|e| e.to_string())?
+		.ok_or_else(|| format!("Block {} not found", block_number))?;
+	decoder.block_events(client, hash)
+}
+
+/// Returns the first block, up to `finalized_number`, whose state was not pruned. Finalized
+/// states are pruned from the oldest, so the blocks with a state form a range.
+fn first_block_with_state<C, B>(client: &C, finalized_number: BlockNumber) -> BlockNumber
+where
+	C: StorageProvider<Block, B> + HeaderBackend<Block>,
+	B: Backend<Block>,
+{
+	let has_state = |block_number| {
+		client
+			.hash(block_number)
+			.ok()
+			.flatten()
+			.map_or(false, |hash| client.storage(hash, &events_key()).is_ok())
+	};
+	let (mut low, mut high) = (0, finalized_number);
+	while low < high {
+		let middle = low + (high - low) / 2;
+		if has_state(middle) {
+			high = middle;
+		} else {
+			low = middle + 1;
+		}
+	}
+	low
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn event(block_number: BlockNumber, event_index: u32, variant: &str) -> DecodedEvent {
+		DecodedEvent {
+			block_number,
+			block_hash: Default::default(),
+			event_index,
+			pallet: "LogionLoc".into(),
+			variant: variant.into(),
+			accounts: vec![],
+			loc_ids: vec![block_number as LocId % 2],
+			data: vec![],
+		}
+	}
+
+	fn ids(page: &EventPage) -> Vec<(BlockNumber, u32)> {
+		page.events.iter().map(|event| (event.block_number, event.event_index)).collect()
+	}
+
+	fn open_db(dir: &tempfile::TempDir) -> IndexerDb {
+		let db = IndexerDb::open(dir.path()).unwrap();
+		for block_number in 1..=5 {
+			let events: Vec<_> =
+				(0..2).map(|index| event(block_number, index, "LocCreated")).collect();
+			db.index_block(block_number, &events).unwrap();
+		}
+		db
+	}
+
+	#[test]
+	fn pages_follow_each_other() {
+		let dir = tempfile::tempdir().unwrap();
+		let db = open_db(&dir);
+		let mut query = EventQuery { limit: Some(4), ..Default::default() };
+
+		let page = db.query(&query).unwrap();
+		assert_eq!(ids(&page), vec![(1, 0), (1, 1), (2, 0), (2, 1)]);
+		assert_eq!(page.next, Some(EventId { block_number: 2, event_index: 1 }));
+		assert_eq!(page.last_indexed_block, Some(5));
+
+		query.after = page.next;
+		let page = db.query(&query).unwrap();
+		assert_eq!(ids(&page), vec![(3, 0), (3, 1), (4, 0), (4, 1)]);
+
+		query.after = page.next;
+		let page = db.query(&query).unwrap();
+		assert_eq!(ids(&page), vec![(5, 0), (5, 1)]);
+		assert_eq!(page.next, None);
+	}
+
+	#[test]
+	fn pages_are_filtered_and_bounded_by_blocks() {
+		let dir = tempfile::tempdir().unwrap();
+		let db = open_db(&dir);
+		let query = EventQuery {
+			loc_id: Some(1),
+			from_block: Some(2),
+			to_block: Some(4),
+			limit: Some(1),
+			..Default::default()
+		};
+
+		let page = db.query(&query).unwrap();
+		assert_eq!(ids(&page), vec![(3, 0)]);
+		let page = db.query(&EventQuery { after: page.next, ..query }).unwrap();
+		assert_eq!(ids(&page), vec![(3, 1)]);
+		assert_eq!(page.next, None);
+	}
+
+	#[test]
+	fn scans_are_bounded() {
+		let dir = tempfile::tempdir().unwrap();
+		let db = IndexerDb::open(dir.path()).unwrap();
+		let events: Vec<_> = (0..MAX_SCANNED_EVENTS as u32 + 1)
+			.map(|index| event(1, index, "LocCreated"))
+			.chain([event(1, MAX_SCANNED_EVENTS as u32 + 1, "LocClosed")])
+			.collect();
+		db.index_block(1, &events).unwrap();
+		let query = EventQuery { variant: Some("LocClosed".into()), ..Default::default() };
+
+		let page = db.query(&query).unwrap();
+		assert_eq!(ids(&page), vec![]);
+		let next = EventId { block_number: 1, event_index: MAX_SCANNED_EVENTS as u32 - 1 };
+		assert_eq!(page.next, Some(next));
+
+		let page = db.query(&EventQuery { after: page.next, ..query }).unwrap();
+		assert_eq!(ids(&page), vec![(1, MAX_SCANNED_EVENTS as u32 + 1)]);
+		assert_eq!(page.next, None);
+	}
+
+	#[test]
+	fn skipped_blocks_are_recorded() {
+		let dir = tempfile::tempdir().unwrap();
+		let db = open_db(&dir);
+		db.skip_block(6).unwrap();
+		db.index_block(7, &[event(7, 0, "LocCreated")]).unwrap();
+
+		assert_eq!(db.last_indexed().unwrap(), Some(7));
+		let page = db.query(&EventQuery { from_block: Some(6), ..Default::default() }).unwrap();
+		assert_eq!(ids(&page), vec![(7, 0)]);
+		assert_eq!(page.skipped_blocks, vec![6]);
+		let page = db.query(&EventQuery { to_block: Some(5), ..Default::default() }).unwrap();
+		assert_eq!(page.skipped_blocks, vec![]);
+	}
+}
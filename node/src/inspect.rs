@@ -0,0 +1,389 @@
+//! Inspection of the blocks and extrinsics of the local database.
+//!
+//! Extrinsics are decoded with the metadata of the runtime which executed them (the runtime of
+//! the parent block's state), so that blocks produced before a runtime upgrade are decoded
+//! correctly. Calls are decoded with the metadata's type information, nested calls (e.g.
+//! `Utility::batch`, `Multisig::as_multi` or the calls of `Vault`) included.
+
+use std::{collections::BTreeMap, str::FromStr};
+
+use codec::{Compact, Decode, Encode};
+use frame_metadata::v14::RuntimeMetadataV14;
+use logion_node_runtime::{opaque::Block, Balance, BlockNumber, Hash};
+use pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi;
+use sc_cli::{CliConfiguration, DatabaseParams, SharedParams};
+use sc_client_api::{Backend, BlockBackend, StorageProvider};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{
+	traits::{Header as HeaderT, Zero},
+	OpaqueExtrinsic,
+};
+
+use crate::{
+	check_upgrade::{code_at, WasmRuntime},
+	events::events_key,
+	scale_json::{decode_value, extrinsic_param, storage_type},
+};
+
+/// The `inspect` command.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct InspectCmd {
+	#[allow(missing_docs)]
+	#[command(subcommand)]
+	pub command: InspectSubcommand,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+/// What to inspect.
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum InspectSubcommand {
+	/// Decode the extrinsics and events of a block.
+	Block {
+		/// The number or the hash of the block.
+		input: String,
+	},
+	/// Decode a hex-encoded extrinsic, with the runtime of the last finalized block.
+	Extrinsic {
+		/// The hex-encoded extrinsic, length prefix included.
+		input: String,
+	},
+}
+
+impl InspectCmd {
+	/// Prints the decoded block or extrinsic.
+	pub fn run<C, B>(&self, client: &C) -> sc_cli::Result<()>
+	where
+		C: HeaderBackend<Block>
+			+ BlockBackend<Block>
+			+ StorageProvider<Block, B>
+			+ ProvideRuntimeApi<Block>,
+		C::Api: TransactionPaymentRuntimeApi<Block, Balance>,
+		B: Backend<Block>,
+	{
+		let json = match &self.command {
+			InspectSubcommand::Block { input } => {
+				let hash = match input.strip_prefix("0x") {
+					Some(_) => Hash::from_str(input).map_err(|e| format!("{}: {}", input, e))?,
+					None => {
+						let number: BlockNumber =
+							input.parse().map_err(|e| format!("{}: {}", input, e))?;
+						client
+							.hash(number)
+							.map_err(|e| e.to_string())?
+							.ok_or_else(|| format!("Block {} not found", number))?
+					},
+				};
+				serde_json::to_string_pretty(&inspect_block(client, hash)?)
+			},
+			InspectSubcommand::Extrinsic { input } => {
+				let encoded = sp_core::bytes::from_hex(input).map_err(|e| e.to_string())?;
+				let extrinsic = OpaqueExtrinsic::decode(&mut &encoded[..])
+					.map_err(|e| format!("Invalid extrinsic: {}", e))?;
+				let hash = client.info().finalized_hash;
+				let runtime = WasmRuntime::load(&code_at(client, hash)?)?;
+				serde_json::to_string_pretty(&inspect_extrinsic(client, &runtime, hash, extrinsic)?)
+			},
+		}
+		.map_err(|e| format!("Unable to serialize: {}", e))?;
+		println!("{}", json);
+		Ok(())
+	}
+}
+
+impl CliConfiguration for InspectCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+/// A decoded block.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockInspection {
+	/// The number of the block.
+	pub number: BlockNumber,
+	/// The hash of the block.
+	pub hash: Hash,
+	/// The hash of the parent block.
+	pub parent_hash: Hash,
+	/// The name of the runtime which executed the block.
+	pub spec_name: String,
+	/// The version of the runtime which executed the block.
+	pub spec_version: u32,
+	/// The extrinsics of the block.
+	pub extrinsics: Vec<ExtrinsicInspection>,
+	/// The events deposited during the initialization or the finalization of the block.
+	pub events: Vec<Value>,
+}
+
+/// A decoded extrinsic.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtrinsicInspection {
+	/// The index of the extrinsic in its block.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub index: Option<u32>,
+	/// The Blake2-256 hash of the encoded extrinsic.
+	pub hash: Hash,
+	/// The address of the signer, none for unsigned extrinsics.
+	pub signer: Option<Value>,
+	/// The signed extensions by identifier, none for unsigned extrinsics.
+	pub signed_extensions: Option<Map<String, Value>>,
+	/// The call.
+	pub call: Value,
+	/// The events deposited by the extrinsic.
+	pub events: Vec<Value>,
+	/// The fees, none for unsigned extrinsics.
+	pub fees: Option<FeeBreakdown>,
+}
+
+/// The fees of a signed extrinsic. The actual fee, after the refund of unused weight, is given
+/// by event `TransactionPayment.TransactionFeePaid`.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeBreakdown {
+	/// The fee paid by any extrinsic.
+	pub base_fee: Balance,
+	/// The fee proportional to the length of the extrinsic.
+	pub len_fee: Balance,
+	/// The fee proportional to the weight of the extrinsic, adjusted to the chain's load.
+	pub adjusted_weight_fee: Balance,
+	/// The tip.
+	pub tip: Balance,
+}
+
+fn inspect_block<C, B>(client: &C, hash: Hash) -> Result<BlockInspection, String>
+where
+	C: HeaderBackend<Block>
+		+ BlockBackend<Block>
+		+ StorageProvider<Block, B>
+		+ ProvideRuntimeApi<Block>,
+	C::Api: TransactionPaymentRuntimeApi<Block, Balance>,
+	B: Backend<Block>,
+{
+	let header = client
+		.header(hash)
+		.map_err(|e| e.to_string())?
+		.ok_or_else(|| format!("Block {} not found", hash))?;
+	let body = client
+		.block_body(hash)
+		.map_err(|e| e.to_string())?
+		.ok_or_else(|| format!("Body of block {} not found", hash))?;
+	// A block is executed by the runtime of its parent's state.
+	let parent_hash = *header.parent_hash();
+	let state_hash = if header.number().is_zero() { hash } else { parent_hash };
+	let runtime = WasmRuntime::load(&code_at(client, state_hash)?)?;
+
+	let records = match client.storage(hash, &events_key()).map_err(|e| e.to_string())? {
+		Some(data) => {
+			let ty = storage_type(&runtime.metadata, "System", "Events")
+				.ok_or("System.Events not found in metadata")?;
+			decode_value(&runtime.metadata.types, ty, &mut &data.0[..])
+				.map_err(|e| format!("Unable to decode events: {}", e))?
+		},
+		None => Value::Array(Vec::new()),
+	};
+	let mut events_by_extrinsic: BTreeMap<u64, Vec<Value>> = BTreeMap::new();
+	let mut block_events = Vec::new();
+	for record in records.as_array().into_iter().flatten() {
+		match record["phase"]["ApplyExtrinsic"].as_u64() {
+			Some(index) =>
+				events_by_extrinsic.entry(index).or_default().push(record["event"].clone()),
+			None => block_events.push(record.clone()),
+		}
+	}
+
+	let extrinsics = body
+		.into_iter()
+		.enumerate()
+		.map(|(index, extrinsic)| {
+			let mut inspection = inspect_extrinsic(client, &runtime, state_hash, extrinsic)?;
+			inspection.index = Some(index as u32);
+			inspection.events = events_by_extrinsic.remove(&(index as u64)).unwrap_or_default();
+			Ok::<_, String>(inspection)
+		})
+		.collect::<Result<_, String>>()?;
+	Ok(BlockInspection {
+		number: *header.number(),
+		hash,
+		parent_hash,
+		spec_name: runtime.version.spec_name.to_string(),
+		spec_version: runtime.version.spec_version,
+		extrinsics,
+		events: block_events,
+	})
+}
+
+/// Decodes `extrinsic` with `runtime`, the fees being computed at block `at`.
+fn inspect_extrinsic<C>(
+	client: &C,
+	runtime: &WasmRuntime,
+	at: Hash,
+	extrinsic: OpaqueExtrinsic,
+) -> Result<ExtrinsicInspection, String>
+where
+	C: ProvideRuntimeApi<Block>,
+	C::Api: TransactionPaymentRuntimeApi<Block, Balance>,
+{
+	let encoded = extrinsic.encode();
+	let hash = Hash::from(sp_core::blake2_256(&encoded));
+	let decoded = decode_extrinsic(&runtime.metadata, &encoded)
+		.map_err(|e| format!("Unable to decode extrinsic {}: {}", hash, e))?;
+	let fees = match decoded.signer {
+		Some(_) => {
+			let details = client
+				.runtime_api()
+				.query_fee_details(at, extrinsic, encoded.len() as u32)
+				.map_err(|e| e.to_string())?;
+			let (base_fee, len_fee, adjusted_weight_fee) = details
+				.inclusion_fee
+				.map(|fee| (fee.base_fee, fee.len_fee, fee.adjusted_weight_fee))
+				.unwrap_or_default();
+			Some(FeeBreakdown { base_fee, len_fee, adjusted_weight_fee, tip: details.tip })
+		},
+		None => None,
+	};
+	Ok(ExtrinsicInspection {
+		index: None,
+		hash,
+		signer: decoded.signer,
+		signed_extensions: decoded.signed_extensions,
+		call: decoded.call,
+		events: Vec::new(),
+		fees,
+	})
+}
+
+struct DecodedExtrinsic {
+	signer: Option<Value>,
+	signed_extensions: Option<Map<String, Value>>,
+	call: Value,
+}
+
+/// The version of the extrinsic format, and the bit set in signed extrinsics.
+const EXTRINSIC_FORMAT_VERSION: u8 = 4;
+const SIGNED_BIT: u8 = 0b1000_0000;
+
+/// Decodes an encoded `UncheckedExtrinsic`, length prefix included.
+fn decode_extrinsic(
+	metadata: &RuntimeMetadataV14,
+	encoded: &[u8],
+) -> Result<DecodedExtrinsic, codec::Error> {
+	let types = &metadata.types;
+	let param = |name: &str| {
+		extrinsic_param(metadata, name).ok_or(codec::Error::from("Missing extrinsic parameter"))
+	};
+
+	let input = &mut &encoded[..];
+	Compact::<u32>::decode(input)?;
+	let version = u8::decode(input)?;
+	if version & !SIGNED_BIT != EXTRINSIC_FORMAT_VERSION {
+		return Err("Unsupported extrinsic format version".into())
+	}
+	let (signer, signed_extensions) = if version & SIGNED_BIT != 0 {
+		let signer = decode_value(types, param("Address")?, input)?;
+		decode_value(types, param("Signature")?, input)?;
+		let mut extensions = Map::new();
+		for extension in &metadata.extrinsic.signed_extensions {
+			let value = decode_value(types, extension.ty.id, input)?;
+			extensions.insert(extension.identifier.clone(), value);
+		}
+		(Some(signer), Some(extensions))
+	} else {
+		(None, None)
+	};
+	let call = decode_value(types, param("Call")?, input)?;
+	if !input.is_empty() {
+		return Err("Trailing bytes after call".into())
+	}
+	Ok(DecodedExtrinsic { signer, signed_extensions, call })
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::scale_json::native_metadata;
+	use logion_node_runtime::{BalancesCall, Runtime, RuntimeCall, SignedExtra, UncheckedExtrinsic};
+	use serde_json::json;
+	use sp_core::crypto::Ss58Codec;
+	use sp_keyring::Sr25519Keyring;
+	use sp_runtime::generic::Era;
+
+	fn transfer() -> RuntimeCall {
+		RuntimeCall::Balances(BalancesCall::transfer_keep_alive {
+			dest: Sr25519Keyring::Bob.to_account_id().into(),
+			value: 1_000,
+		})
+	}
+
+	#[test]
+	fn decodes_signed_extrinsic() {
+		let extra: SignedExtra = (
+			frame_system::CheckNonZeroSender::<Runtime>::new(),
+			frame_system::CheckSpecVersion::<Runtime>::new(),
+			frame_system::CheckTxVersion::<Runtime>::new(),
+			frame_system::CheckGenesis::<Runtime>::new(),
+			frame_system::CheckEra::<Runtime>::from(Era::Immortal),
+			frame_system::CheckNonce::<Runtime>::from(7),
+			frame_system::CheckWeight::<Runtime>::new(),
+			pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(10),
+		);
+		let signer = Sr25519Keyring::Alice;
+		let call = transfer();
+		let signature = signer.sign(&call.encode()).into();
+		let extrinsic =
+			UncheckedExtrinsic::new_signed(call, signer.to_account_id().into(), signature, extra);
+
+		let decoded = decode_extrinsic(&native_metadata(), &extrinsic.encode()).unwrap();
+
+		assert_eq!(decoded.signer, Some(json!({ "Id": signer.to_account_id().to_ss58check() })));
+		let extensions = decoded.signed_extensions.unwrap();
+		assert_eq!(extensions["CheckNonce"], json!(7));
+		assert_eq!(extensions["ChargeTransactionPayment"], json!(10));
+		assert_eq!(extensions["CheckMortality"], json!("Immortal"));
+		assert_eq!(
+			decoded.call,
+			json!({
+				"Balances": {
+					"transfer_keep_alive": {
+						"dest": { "Id": Sr25519Keyring::Bob.to_account_id().to_ss58check() },
+						"value": 1_000,
+					}
+				}
+			})
+		);
+	}
+
+	#[test]
+	fn decodes_unsigned_extrinsic() {
+		let extrinsic = UncheckedExtrinsic::new_unsigned(transfer());
+
+		let decoded = decode_extrinsic(&native_metadata(), &extrinsic.encode()).unwrap();
+
+		assert_eq!(decoded.signer, None);
+		assert_eq!(decoded.signed_extensions, None);
+		assert_eq!(decoded.call["Balances"]["transfer_keep_alive"]["value"], json!(1_000));
+	}
+
+	#[test]
+	fn trailing_bytes_are_rejected() {
+		let mut encoded = UncheckedExtrinsic::new_unsigned(transfer()).encode();
+		encoded.push(0);
+
+		assert!(decode_extrinsic(&native_metadata(), &encoded).is_err());
+	}
+}
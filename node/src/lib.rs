@@ -1,3 +1,24 @@
+mod benchmarking;
 pub mod chain_spec;
+pub mod check_upgrade;
+pub mod cli;
+pub mod command;
+pub mod consensus;
+pub mod double_sign;
+pub mod events;
+pub mod fork_off;
+pub mod indexer;
+pub mod inspect;
+pub mod loc_index;
+pub mod logion_data;
+pub mod peering;
+pub mod proof;
+pub mod remote_signer;
 pub mod rpc;
+pub mod scale_json;
+pub mod sealing;
 pub mod service;
+pub mod session_keys;
+pub mod tx;
+pub mod validator_check;
+pub mod webhooks;
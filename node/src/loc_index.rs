@@ -0,0 +1,235 @@
+//! Merges the journals written by [`pallet_loc_index`] into the LOC index.
+//!
+//! The runtime writes the index changes made by a block to a journal in the offchain database,
+//! keyed by the block's hash. Journals are merged in the order of finalized blocks, so that the
+//! index never has to be rolled back, and removed once merged. The number of the last merged
+//! block is kept with the index: after a restart, or if finality notifications were skipped,
+//! merging resumes with the next block.
+
+use std::sync::Arc;
+
+use codec::{Decode, Encode};
+use futures::StreamExt;
+use logion_node_runtime::{opaque::Block, BlockNumber, Hash, LocId};
+use pallet_loc_index::{IndexChange, IndexKey, IndexedItem};
+use sc_client_api::BlockchainEvents;
+use sp_blockchain::HeaderBackend;
+use sp_core::offchain::{OffchainStorage, STORAGE_PREFIX};
+use sp_runtime::traits::Header;
+
+const LOG_TARGET: &str = "logion-loc-index";
+
+type Key = IndexKey<Hash, Hash, LocId>;
+
+/// An item a hash was found in.
+pub type Item = IndexedItem<LocId, Hash>;
+
+/// Merges the journals of finalized blocks, forever.
+pub async fn run_loc_index<C, S>(client: Arc<C>, mut offchain_db: S)
+where
+	C: HeaderBackend<Block> + BlockchainEvents<Block>,
+	S: OffchainStorage,
+{
+	let mut finality_notifications = client.finality_notification_stream();
+	merge_finalized(client.as_ref(), &mut offchain_db, client.info().finalized_number);
+	while let Some(notification) = finality_notifications.next().await {
+		let finalized_number = *notification.header.number();
+		merge_finalized(client.as_ref(), &mut offchain_db, finalized_number);
+	}
+}
+
+/// Returns the items `hash` was found in, in all LOCs.
+pub fn find_by_hash<S: OffchainStorage>(offchain_db: &S, hash: Hash) -> Result<Vec<Item>, String> {
+	let mut items = Vec::new();
+	for loc_id in read::<_, Vec<LocId>>(offchain_db, &Key::Locs(hash))?.unwrap_or_default() {
+		let entry = read::<_, Vec<Item>>(offchain_db, &Key::Entry(hash, loc_id))?;
+		items.extend(entry.unwrap_or_default());
+	}
+	Ok(items)
+}
+
+/// Merges the journals of the blocks following the last merged one, up to `finalized_number`.
+/// Stops if a block cannot be read, the remaining blocks being merged on next finality
+/// notification.
+fn merge_finalized<C, S>(client: &C, offchain_db: &mut S, finalized_number: BlockNumber)
+where
+	C: HeaderBackend<Block>,
+	S: OffchainStorage,
+{
+	let first = match read::<_, BlockNumber>(offchain_db, &Key::LastMerged) {
+		Ok(last_merged) => last_merged.map_or(1, |number| number + 1),
+		Err(e) => {
+			log::error!(target: LOG_TARGET, "Unable to read LOC index state: {}", e);
+			return
+		},
+	};
+	for block_number in first..=finalized_number {
+		if let Err(e) = merge_block(client, offchain_db, block_number) {
+			log::error!(target: LOG_TARGET, "Unable to read block {}: {}", block_number, e);
+			return
+		}
+	}
+}
+
+/// Merges the journal of the parent of block `block_number`, written while importing the block.
+fn merge_block<C, S>(
+	client: &C,
+	offchain_db: &mut S,
+	block_number: BlockNumber,
+) -> Result<(), String>
+where
+	C: HeaderBackend<Block>,
+	S: OffchainStorage,
+{
+	let hash = client
+		.hash(block_number)
+		.map_err(|e| e.to_string())?
+		.ok_or_else(|| format!("Block {} not found", block_number))?;
+	let header = client
+		.header(hash)
+		.map_err(|e| e.to_string())?
+		.ok_or_else(|| format!("Header of block {} not found", block_number))?;
+	let journal = Key::Journal(*header.parent_hash());
+	match read::<_, Vec<IndexChange<Hash, LocId, Hash>>>(offchain_db, &journal) {
+		Ok(Some(changes)) => {
+			apply_changes(offchain_db, changes)?;
+			offchain_db.remove(STORAGE_PREFIX, &journal.offchain_key());
+		},
+		Ok(None) => {},
+		Err(e) => {
+			log::warn!(target: LOG_TARGET, "Skipping journal of block {}: {}", block_number - 1, e);
+			offchain_db.remove(STORAGE_PREFIX, &journal.offchain_key());
+		},
+	}
+	write(offchain_db, &Key::LastMerged, &block_number);
+	Ok(())
+}
+
+/// Applies index `changes`, in order.
+fn apply_changes<S: OffchainStorage>(
+	offchain_db: &mut S,
+	changes: Vec<IndexChange<Hash, LocId, Hash>>,
+) -> Result<(), String> {
+	for change in changes {
+		match change {
+			IndexChange::Add(hash, item) => {
+				let loc_id = *item.loc_id();
+				if offchain_db.get(STORAGE_PREFIX, &Key::Void(loc_id).offchain_key()).is_some() {
+					continue
+				}
+				insert(offchain_db, &Key::Entry(hash, loc_id), item)?;
+				insert(offchain_db, &Key::Locs(hash), loc_id)?;
+				insert(offchain_db, &Key::Hashes(loc_id), hash)?;
+			},
+			IndexChange::Void(loc_id) => {
+				let hashes = read::<_, Vec<Hash>>(offchain_db, &Key::Hashes(loc_id))?;
+				for hash in hashes.unwrap_or_default() {
+					offchain_db.remove(STORAGE_PREFIX, &Key::Entry(hash, loc_id).offchain_key());
+					remove(offchain_db, &Key::Locs(hash), &loc_id)?;
+				}
+				offchain_db.remove(STORAGE_PREFIX, &Key::Hashes(loc_id).offchain_key());
+				offchain_db.set(STORAGE_PREFIX, &Key::Void(loc_id).offchain_key(), &[]);
+			},
+		}
+	}
+	Ok(())
+}
+
+fn read<S: OffchainStorage, T: Decode>(offchain_db: &S, key: &Key) -> Result<Option<T>, String> {
+	offchain_db
+		.get(STORAGE_PREFIX, &key.offchain_key())
+		.map(|value| T::decode(&mut &value[..]))
+		.transpose()
+		.map_err(|e| format!("Unable to decode {:?}: {}", key, e))
+}
+
+fn write<S: OffchainStorage, T: Encode>(offchain_db: &mut S, key: &Key, value: &T) {
+	offchain_db.set(STORAGE_PREFIX, &key.offchain_key(), &value.encode());
+}
+
+/// Adds `value` to the list at `key`, if not already there.
+fn insert<S, T>(offchain_db: &mut S, key: &Key, value: T) -> Result<(), String>
+where
+	S: OffchainStorage,
+	T: Encode + Decode + PartialEq,
+{
+	let mut values = read::<_, Vec<T>>(offchain_db, key)?.unwrap_or_default();
+	if !values.contains(&value) {
+		values.push(value);
+		write(offchain_db, key, &values);
+	}
+	Ok(())
+}
+
+/// Removes `value` from the list at `key`, and the list if empty.
+fn remove<S, T>(offchain_db: &mut S, key: &Key, value: &T) -> Result<(), String>
+where
+	S: OffchainStorage,
+	T: Encode + Decode + PartialEq,
+{
+	let mut values = read::<_, Vec<T>>(offchain_db, key)?.unwrap_or_default();
+	values.retain(|other| other != value);
+	if values.is_empty() {
+		offchain_db.remove(STORAGE_PREFIX, &key.offchain_key());
+	} else {
+		write(offchain_db, key, &values);
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::offchain::storage::InMemOffchainStorage;
+
+	#[test]
+	fn indexes_a_hash_in_several_locs() {
+		let mut offchain_db = InMemOffchainStorage::default();
+		let hash = Hash::repeat_byte(1);
+		let item_id = Hash::repeat_byte(2);
+		apply_changes(
+			&mut offchain_db,
+			vec![
+				IndexChange::Add(hash, Item::File { loc_id: 1 }),
+				IndexChange::Add(hash, Item::Metadata { loc_id: 1 }),
+				IndexChange::Add(hash, Item::CollectionItemFile { loc_id: 2, item_id }),
+			],
+		)
+		.unwrap();
+		// Merging the same journal twice changes nothing.
+		apply_changes(&mut offchain_db, vec![IndexChange::Add(hash, Item::File { loc_id: 1 })])
+			.unwrap();
+
+		assert_eq!(
+			find_by_hash(&offchain_db, hash).unwrap(),
+			vec![
+				Item::File { loc_id: 1 },
+				Item::Metadata { loc_id: 1 },
+				Item::CollectionItemFile { loc_id: 2, item_id },
+			]
+		);
+		assert_eq!(find_by_hash(&offchain_db, item_id).unwrap(), vec![]);
+	}
+
+	#[test]
+	fn removes_void_locs() {
+		let mut offchain_db = InMemOffchainStorage::default();
+		let hash = Hash::repeat_byte(1);
+		let other_hash = Hash::repeat_byte(2);
+		apply_changes(
+			&mut offchain_db,
+			vec![
+				IndexChange::Add(hash, Item::File { loc_id: 1 }),
+				IndexChange::Add(other_hash, Item::Metadata { loc_id: 1 }),
+				IndexChange::Add(hash, Item::File { loc_id: 2 }),
+				IndexChange::Void(1),
+				IndexChange::Add(other_hash, Item::File { loc_id: 1 }),
+			],
+		)
+		.unwrap();
+
+		assert_eq!(find_by_hash(&offchain_db, hash).unwrap(), vec![Item::File { loc_id: 2 }]);
+		assert_eq!(find_by_hash(&offchain_db, other_hash).unwrap(), vec![]);
+		assert_eq!(offchain_db.get(STORAGE_PREFIX, &Key::Locs(other_hash).offchain_key()), None);
+	}
+}
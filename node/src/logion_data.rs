@@ -0,0 +1,676 @@
+//! Export of the Logion data as `import_*` calls, for the migration to another chain.
+//!
+//! The legal officers, LOCs, collection items, tokens records, verified issuers, invited
+//! contributors and sponsorships of a block's state are turned into the `import_*` calls of
+//! `LoAuthorityList` and `LogionLoc`. Calls are ordered so that the data they refer to is
+//! imported first (e.g. legal officers before LOCs, LOCs before their items), and packed into
+//! `Utility::batch_all` calls fitting the normal dispatch limits of `BlockWeights` and
+//! `BlockLength`, minus a tenth left to the batch and sudo overhead. The export fails if a single
+//! call exceeds those limits. Each batch comes with a checksum, the export with a checksum of all
+//! batches.
+//!
+//! Pallet indices may differ on the target chain: they are given with `--target-pallet-index`.
+//! Import calls require the root origin, the batches are meant to be submitted through sudo.
+//!
+//! `verify-logion-data` rebuilds the calls from the target state after replay and compares them
+//! with the exported ones.
+
+use std::{
+	collections::BTreeMap,
+	fs,
+	path::{Path, PathBuf},
+	sync::Arc,
+};
+
+use codec::{Compact, Encode};
+use frame_support::{
+	dispatch::{DispatchClass, GetDispatchInfo},
+	traits::PalletInfoAccess,
+	weights::Weight,
+};
+use logion_node_runtime::{
+	opaque::Block, BlockLength, BlockNumber, BlockWeights, Hash, LoAuthorityList, LogionLoc,
+	Runtime, RuntimeCall, Utility,
+};
+use pallet_lo_authority_list::{LegalOfficerData, LegalOfficerSet};
+use sc_cli::{CliConfiguration, DatabaseParams, SharedParams};
+use sc_client_api::{Backend, StorageProvider, UsageProvider};
+use serde::{Deserialize, Serialize};
+use sp_blockchain::HeaderBackend;
+use sp_core::{storage::Storage, Bytes};
+use sp_state_machine::BasicExternalities;
+
+use crate::fork_off::{read_raw_state, StorageMap};
+
+/// The index of `batch_all` in `pallet_utility`'s calls.
+const BATCH_ALL_CALL_INDEX: u8 = 2;
+
+/// A batch of import calls.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportBatch {
+	/// The SCALE-encoded import calls.
+	pub calls: Vec<Bytes>,
+	/// The SCALE-encoded `Utility::batch_all` call of the import calls.
+	pub batch_call: Bytes,
+	/// The estimated weight of the import calls (ref time, proof size).
+	pub weight: (u64, u64),
+	/// The Blake2-256 hash of `batch_call`.
+	pub checksum: Hash,
+}
+
+/// The Logion data of a block, as batches of import calls.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LogionDataExport {
+	/// The number of the exported block.
+	pub block_number: BlockNumber,
+	/// The hash of the exported block.
+	pub block_hash: Hash,
+	/// The indices of the pallets on the target chain.
+	pub pallet_indices: BTreeMap<String, u8>,
+	/// The number of exported items, by kind.
+	pub counts: BTreeMap<String, u32>,
+	/// The batches, in submission order.
+	pub batches: Vec<ImportBatch>,
+	/// The Blake2-256 hash of the concatenated batch checksums.
+	pub checksum: Hash,
+}
+
+impl LogionDataExport {
+	fn expected_checksum(&self) -> Hash {
+		let checksums: Vec<u8> =
+			self.batches.iter().flat_map(|batch| batch.checksum.0.to_vec()).collect();
+		sp_core::blake2_256(&checksums).into()
+	}
+}
+
+/// The indices of the pallets involved in the migration, on the target chain.
+#[derive(Clone, Debug)]
+struct PalletIndices(BTreeMap<String, u8>);
+
+impl PalletIndices {
+	fn new(overrides: &[(String, u8)]) -> Result<Self, String> {
+		let mut indices = BTreeMap::from([
+			("LoAuthorityList".to_string(), LoAuthorityList::index() as u8),
+			("LogionLoc".to_string(), LogionLoc::index() as u8),
+			("Utility".to_string(), Utility::index() as u8),
+		]);
+		for (pallet, index) in overrides {
+			*indices.get_mut(pallet).ok_or_else(|| format!("Unknown pallet {}", pallet))? = *index;
+		}
+		Ok(Self(indices))
+	}
+
+	/// Encodes `call` with the index of its pallet on the target chain.
+	fn encode(&self, call: &RuntimeCall) -> Vec<u8> {
+		let pallet = match call {
+			RuntimeCall::LoAuthorityList(_) => "LoAuthorityList",
+			_ => "LogionLoc",
+		};
+		let mut encoded = call.encode();
+		encoded[0] = self.0[pallet];
+		encoded
+	}
+
+	fn batch_all(&self, calls: &[Bytes]) -> Vec<u8> {
+		let mut encoded = vec![self.0["Utility"], BATCH_ALL_CALL_INDEX];
+		Compact(calls.len() as u32).encode_to(&mut encoded);
+		calls.iter().for_each(|call| encoded.extend_from_slice(call));
+		encoded
+	}
+}
+
+/// Returns the import calls of the Logion data in `state`, with the kind of each call.
+fn import_calls(state: StorageMap) -> Vec<(&'static str, RuntimeCall)> {
+	use pallet_lo_authority_list::Call as AuthorityCall;
+	use pallet_logion_loc::Call as LocCall;
+
+	let storage = Storage { top: state, children_default: Default::default() };
+	BasicExternalities::new(storage).execute_with(|| {
+		let mut calls = Vec::new();
+
+		// Guests refer to their host.
+		let (hosts, guests): (Vec<_>, Vec<_>) = LegalOfficerSet::<Runtime>::iter()
+			.partition(|(_, data)| matches!(data, LegalOfficerData::Host(_)));
+		for (legal_officer_id, data) in hosts.into_iter().chain(guests) {
+			let call = match data {
+				LegalOfficerData::Host(data) =>
+					AuthorityCall::import_host_legal_officer { legal_officer_id, data },
+				LegalOfficerData::Guest(host_id) =>
+					AuthorityCall::import_guest_legal_officer { legal_officer_id, host_id },
+			};
+			calls.push(("legalOfficers", RuntimeCall::LoAuthorityList(call)));
+		}
+
+		// LOCs refer to their sponsorship.
+		for (sponsorship_id, sponsorship) in pallet_logion_loc::SponsorshipMap::<Runtime>::iter() {
+			let call = LocCall::import_sponsorship {
+				sponsorship_id,
+				sponsor: sponsorship.sponsor,
+				sponsored_account: sponsorship.sponsored_account,
+				legal_officer: sponsorship.legal_officer,
+				loc_id: sponsorship.loc_id,
+			};
+			calls.push(("sponsorships", RuntimeCall::LogionLoc(call)));
+		}
+
+		for (loc_id, loc) in pallet_logion_loc::LocMap::<Runtime>::iter() {
+			let call = LocCall::import_loc {
+				loc_id,
+				requester: loc.requester,
+				legal_officer: loc.owner,
+				loc_type: loc.loc_type,
+				items: pallet_logion_loc::Items {
+					metadata: loc.metadata,
+					files: loc.files,
+					links: loc.links,
+				},
+				collection_last_block_submission: loc.collection_last_block_submission,
+				collection_max_size: loc.collection_max_size,
+				collection_can_upload: loc.collection_can_upload,
+				value_fee: loc.value_fee,
+				legal_fee: loc.legal_fee,
+				collection_item_fee: loc.collection_item_fee,
+				tokens_record_fee: loc.tokens_record_fee,
+				sponsorship_id: loc.sponsorship_id,
+				seal: loc.seal,
+				void_info: loc.void_info,
+				replacer_of: loc.replacer_of,
+				closed: loc.closed,
+			};
+			calls.push(("locs", RuntimeCall::LogionLoc(call)));
+		}
+
+		// Verified issuers refer to their identity LOC.
+		for (legal_officer, issuer, verified_issuer) in
+			pallet_logion_loc::VerifiedIssuersMap::<Runtime>::iter()
+		{
+			let call = LocCall::import_verified_issuer {
+				legal_officer,
+				issuer,
+				identity_loc_id: verified_issuer.identity_loc,
+			};
+			calls.push(("verifiedIssuers", RuntimeCall::LogionLoc(call)));
+		}
+
+		// The remaining data refers to collection LOCs.
+		for (collection_loc_id, item_id, item) in
+			pallet_logion_loc::CollectionItemsMap::<Runtime>::iter()
+		{
+			let call = LocCall::import_collection_item {
+				collection_loc_id,
+				item_id,
+				item_description: item.description,
+				item_files: item.files,
+				item_token: item.token,
+				restricted_delivery: item.restricted_delivery,
+				terms_and_conditions: item.terms_and_conditions,
+				token_issuance: item.token_issuance,
+			};
+			calls.push(("collectionItems", RuntimeCall::LogionLoc(call)));
+		}
+		for (collection_loc_id, record_id, record) in
+			pallet_logion_loc::TokensRecordsMap::<Runtime>::iter()
+		{
+			let call = LocCall::import_tokens_record {
+				collection_loc_id,
+				record_id,
+				description: record.description,
+				files: record.files,
+				submitter: record.submitter,
+			};
+			calls.push(("tokensRecords", RuntimeCall::LogionLoc(call)));
+		}
+		for (collection_loc_id, invited_contributor, _) in
+			pallet_logion_loc::InvitedContributorsByLocMap::<Runtime>::iter()
+		{
+			let call = LocCall::import_invited_contributor_selection {
+				collection_loc_id,
+				invited_contributor,
+			};
+			calls.push(("invitedContributors", RuntimeCall::LogionLoc(call)));
+		}
+		for (collection_loc_id, issuer, _) in
+			pallet_logion_loc::VerifiedIssuersByLocMap::<Runtime>::iter()
+		{
+			let call = LocCall::import_verified_issuer_selection { collection_loc_id, issuer };
+			calls.push(("issuerSelections", RuntimeCall::LogionLoc(call)));
+		}
+
+		calls
+	})
+}
+
+/// The maximum weight of the calls of a batch. Leaves room for the batch and sudo overhead.
+fn max_batch_weight() -> Weight {
+	let block_weights = BlockWeights::get();
+	let normal = block_weights.get(DispatchClass::Normal);
+	let max_weight = normal.max_extrinsic.unwrap_or(block_weights.max_block);
+	Weight::from_parts(max_weight.ref_time() / 10 * 9, max_weight.proof_size() / 10 * 9)
+}
+
+/// The maximum length of the calls of a batch. Leaves room for the batch, sudo and extrinsic
+/// envelopes.
+fn max_batch_length() -> usize {
+	*BlockLength::get().max.get(DispatchClass::Normal) as usize / 10 * 9
+}
+
+/// Packs `calls` into batches fitting the normal dispatch limits. Fails if a call does not fit
+/// in a batch by itself.
+fn pack(
+	calls: Vec<(&'static str, RuntimeCall)>,
+	indices: &PalletIndices,
+) -> Result<Vec<ImportBatch>, String> {
+	let max_weight = max_batch_weight();
+	let max_length = max_batch_length();
+
+	let mut batches = Vec::new();
+	let mut calls_of_batch: Vec<Bytes> = Vec::new();
+	let mut weight = Weight::zero();
+	let mut length = 0;
+	for (kind, call) in calls {
+		let call_weight = call.get_dispatch_info().weight;
+		let encoded = indices.encode(&call);
+		if !call_weight.all_lte(max_weight) || encoded.len() > max_length {
+			return Err(format!(
+				"Call of {} does not fit in a batch (weight {:?}, length {})",
+				kind,
+				call_weight,
+				encoded.len()
+			))
+		}
+		let fits = (weight + call_weight).all_lte(max_weight) &&
+			length + encoded.len() <= max_length;
+		if !fits && !calls_of_batch.is_empty() {
+			batches.push(batch(std::mem::take(&mut calls_of_batch), weight, indices));
+			weight = Weight::zero();
+			length = 0;
+		}
+		weight += call_weight;
+		length += encoded.len();
+		calls_of_batch.push(encoded.into());
+	}
+	if !calls_of_batch.is_empty() {
+		batches.push(batch(calls_of_batch, weight, indices));
+	}
+	Ok(batches)
+}
+
+fn batch(calls: Vec<Bytes>, weight: Weight, indices: &PalletIndices) -> ImportBatch {
+	let batch_call = indices.batch_all(&calls);
+	ImportBatch {
+		checksum: sp_core::blake2_256(&batch_call).into(),
+		batch_call: batch_call.into(),
+		calls,
+		weight: (weight.ref_time(), weight.proof_size()),
+	}
+}
+
+fn count(calls: &[(&'static str, RuntimeCall)]) -> BTreeMap<String, u32> {
+	let mut counts = BTreeMap::new();
+	for (kind, _) in calls {
+		*counts.entry(kind.to_string()).or_default() += 1;
+	}
+	counts
+}
+
+fn parse_pallet_index(s: &str) -> Result<(String, u8), String> {
+	let (pallet, index) =
+		s.split_once('=').ok_or_else(|| format!("Expected <pallet>=<index>, got {}", s))?;
+	let index = index.parse().map_err(|_| format!("Invalid pallet index {}", index))?;
+	Ok((pallet.to_string(), index))
+}
+
+/// The `export-logion-data` command.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct ExportLogionDataCmd {
+	/// The hash of the exported block, the last finalized block by default.
+	#[arg(long)]
+	pub at: Option<Hash>,
+
+	/// The index of a pallet on the target chain, as `<pallet>=<index>` (pallets:
+	/// `LoAuthorityList`, `LogionLoc`, `Utility`). Defaults to the index in this chain.
+	#[arg(long = "target-pallet-index", value_parser = parse_pallet_index)]
+	pub target_pallet_indices: Vec<(String, u8)>,
+
+	/// The file to write the export to, standard output by default.
+	#[arg(long)]
+	pub output: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl ExportLogionDataCmd {
+	/// Exports the Logion data of the block's state read from `client`.
+	pub fn run<C, B>(&self, client: Arc<C>) -> sc_cli::Result<()>
+	where
+		C: StorageProvider<Block, B> + UsageProvider<Block> + HeaderBackend<Block>,
+		B: Backend<Block>,
+	{
+		let block_hash = self.at.unwrap_or_else(|| client.usage_info().chain.finalized_hash);
+		let block_number = client
+			.number(block_hash)
+			.map_err(|e| e.to_string())?
+			.ok_or_else(|| format!("Block {} not found", block_hash))?;
+		let state = sc_service::chain_ops::export_raw_state(client, block_hash)?.top;
+
+		let indices = PalletIndices::new(&self.target_pallet_indices)?;
+		let calls = import_calls(state);
+		let counts = count(&calls);
+		let mut export = LogionDataExport {
+			block_number,
+			block_hash,
+			pallet_indices: indices.0.clone(),
+			counts,
+			batches: pack(calls, &indices)?,
+			checksum: Default::default(),
+		};
+		export.checksum = export.expected_checksum();
+		write_json(&export, self.output.as_deref())
+	}
+}
+
+impl CliConfiguration for ExportLogionDataCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+/// The result of `verify-logion-data`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationReport {
+	/// True if all batch checksums and the export checksum are valid.
+	pub checksums_valid: bool,
+	/// The number of items in the export, by kind.
+	pub exported: BTreeMap<String, u32>,
+	/// The number of items in the target state, by kind.
+	pub found: BTreeMap<String, u32>,
+	/// The exported calls whose data is not found as is in the target state.
+	pub missing: Vec<Bytes>,
+	/// The calls rebuilt from the target state which are not part of the export.
+	pub unexpected: Vec<Bytes>,
+}
+
+/// The `verify-logion-data` command.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct VerifyLogionDataCmd {
+	/// The file produced by `export-logion-data`.
+	pub export: PathBuf,
+
+	/// The state of the target chain after replay: a raw chain spec produced by `export-state`,
+	/// or `db` to read the last finalized state of the local database of `--chain`.
+	#[arg(long)]
+	pub target: String,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl VerifyLogionDataCmd {
+	/// Returns true if the target state has to be read from the local database.
+	pub fn from_db(&self) -> bool {
+		self.target == "db"
+	}
+
+	/// Compares the export with the target state, read from `client` if given.
+	pub fn run<C, B>(&self, client: Option<Arc<C>>) -> sc_cli::Result<()>
+	where
+		C: StorageProvider<Block, B> + UsageProvider<Block>,
+		B: Backend<Block>,
+	{
+		let content = fs::read(&self.export)?;
+		let export: LogionDataExport = serde_json::from_slice(&content)
+			.map_err(|e| format!("Unable to parse {}: {}", self.export.display(), e))?;
+		let state = match client {
+			Some(client) => {
+				let finalized_hash = client.usage_info().chain.finalized_hash;
+				sc_service::chain_ops::export_raw_state(client, finalized_hash)?.top
+			},
+			None => read_raw_state(Path::new(&self.target))?,
+		};
+
+		let indices = PalletIndices(export.pallet_indices.clone());
+		let calls = import_calls(state);
+		let found = count(&calls);
+		let mut rebuilt: Vec<Bytes> =
+			calls.iter().map(|(_, call)| indices.encode(call).into()).collect();
+		let mut exported: Vec<Bytes> =
+			export.batches.iter().flat_map(|batch| batch.calls.iter().cloned()).collect();
+		rebuilt.sort();
+		exported.sort();
+
+		let checksums_valid = export.checksum == export.expected_checksum() &&
+			export.batches.iter().all(|batch| {
+				batch.batch_call == Bytes::from(indices.batch_all(&batch.calls)) &&
+					batch.checksum == Hash::from(sp_core::blake2_256(&batch.batch_call))
+			});
+		let report = VerificationReport {
+			checksums_valid,
+			exported: export.counts,
+			found,
+			missing: difference(&exported, &rebuilt),
+			unexpected: difference(&rebuilt, &exported),
+		};
+		let valid =
+			report.checksums_valid && report.missing.is_empty() && report.unexpected.is_empty();
+		write_json(&report, None)?;
+		if valid {
+			Ok(())
+		} else {
+			Err("Target state does not match the export".into())
+		}
+	}
+}
+
+impl CliConfiguration for VerifyLogionDataCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+/// Returns the items of sorted `a` which are not in sorted `b`, duplicates included.
+fn difference(a: &[Bytes], b: &[Bytes]) -> Vec<Bytes> {
+	let mut result = Vec::new();
+	let mut b = b.iter().peekable();
+	for item in a {
+		while b.next_if(|other| *other < item).is_some() {}
+		if b.next_if(|other| *other == item).is_none() {
+			result.push(item.clone());
+		}
+	}
+	result
+}
+
+fn write_json<T: Serialize>(value: &T, output: Option<&Path>) -> sc_cli::Result<()> {
+	let json = serde_json::to_string_pretty(value)
+		.map_err(|e| format!("Unable to serialize: {}", e))?;
+	match output {
+		Some(path) => fs::write(path, json)?,
+		None => println!("{}", json),
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn difference_keeps_duplicates() {
+		let a: Vec<Bytes> = vec![vec![1].into(), vec![2].into(), vec![2].into(), vec![4].into()];
+		let b: Vec<Bytes> = vec![vec![2].into(), vec![3].into()];
+		assert_eq!(difference(&a, &b), vec![vec![1].into(), vec![2].into(), vec![4].into()]);
+	}
+
+	#[test]
+	fn batch_all_uses_target_indices() {
+		let indices = PalletIndices::new(&[("Utility".to_string(), 40)]).unwrap();
+		let calls: Vec<Bytes> = vec![vec![15, 1, 2].into(), vec![15, 3].into()];
+		assert_eq!(
+			indices.batch_all(&calls),
+			vec![40, BATCH_ALL_CALL_INDEX, 8, 15, 1, 2, 15, 3]
+		);
+	}
+
+	#[test]
+	fn unknown_pallet_index_is_rejected() {
+		assert!(PalletIndices::new(&[("Balances".to_string(), 1)]).is_err());
+	}
+
+	/// Dispatches an import call with the root origin, as the batches are on the target chain.
+	fn import(call: RuntimeCall) {
+		use sp_runtime::traits::Dispatchable;
+
+		call.dispatch(logion_node_runtime::RuntimeOrigin::root())
+			.expect("Test data is consistent; qed");
+	}
+
+	#[test]
+	fn import_calls_are_ordered_by_dependency() {
+		use frame_support::traits::BuildGenesisConfig;
+		use pallet_lo_authority_list::{Call as AuthorityCall, GenesisHostData};
+		use pallet_logion_loc::{Call as LocCall, Items, LocType, Requester, SupportedAccountId};
+		use sp_keyring::Sr25519Keyring;
+
+		let hosts = [Sr25519Keyring::Alice, Sr25519Keyring::Bob];
+		let guests = [Sr25519Keyring::Charlie, Sr25519Keyring::Dave, Sr25519Keyring::Eve];
+		let requester = Sr25519Keyring::Ferdie.to_account_id();
+
+		let mut ext = BasicExternalities::default();
+		ext.execute_with(|| {
+			pallet_lo_authority_list::GenesisConfig::<Runtime> {
+				legal_officers: hosts
+					.iter()
+					.map(|host| {
+						let data = GenesisHostData {
+							node_id: None,
+							base_url: None,
+							region: "Europe".into(),
+						};
+						(host.to_account_id(), data)
+					})
+					.collect(),
+			}
+			.build();
+			for guest in guests {
+				import(RuntimeCall::LoAuthorityList(AuthorityCall::import_guest_legal_officer {
+					legal_officer_id: guest.to_account_id(),
+					host_id: hosts[0].to_account_id(),
+				}));
+			}
+			for id in 1..=3 {
+				let loc_id: logion_node_runtime::LocId = id;
+				let sponsorship_id: logion_node_runtime::SponsorshipId = id;
+				import(RuntimeCall::LogionLoc(LocCall::import_sponsorship {
+					sponsorship_id,
+					sponsor: hosts[1].to_account_id(),
+					sponsored_account: SupportedAccountId::Polkadot(requester.clone()),
+					legal_officer: hosts[0].to_account_id(),
+					loc_id: None,
+				}));
+				import(RuntimeCall::LogionLoc(LocCall::import_loc {
+					loc_id,
+					requester: Requester::Account(requester.clone()),
+					legal_officer: hosts[0].to_account_id(),
+					loc_type: LocType::Collection,
+					items: Items {
+						metadata: Default::default(),
+						files: Default::default(),
+						links: Default::default(),
+					},
+					collection_last_block_submission: None,
+					collection_max_size: None,
+					collection_can_upload: false,
+					value_fee: 0,
+					legal_fee: 0,
+					collection_item_fee: 0,
+					tokens_record_fee: 0,
+					sponsorship_id: None,
+					seal: None,
+					void_info: None,
+					replacer_of: None,
+					closed: true,
+				}));
+				import(RuntimeCall::LogionLoc(LocCall::import_collection_item {
+					collection_loc_id: loc_id,
+					item_id: Hash::repeat_byte(id as u8),
+					item_description: Hash::repeat_byte(id as u8),
+					item_files: Default::default(),
+					item_token: None,
+					restricted_delivery: false,
+					terms_and_conditions: Default::default(),
+					token_issuance: 0,
+				}));
+			}
+		});
+
+		let kinds: Vec<_> = import_calls(ext.into_storages().top)
+			.into_iter()
+			.map(|(kind, call)| match call {
+				RuntimeCall::LoAuthorityList(AuthorityCall::import_host_legal_officer {
+					..
+				}) => "hosts",
+				RuntimeCall::LoAuthorityList(AuthorityCall::import_guest_legal_officer {
+					..
+				}) => "guests",
+				_ => kind,
+			})
+			.collect();
+		assert_eq!(
+			kinds,
+			[
+				vec!["hosts"; 2],
+				vec!["guests"; 3],
+				vec!["sponsorships"; 3],
+				vec!["locs"; 3],
+				vec!["collectionItems"; 3],
+			]
+			.concat()
+		);
+	}
+
+	#[test]
+	fn batches_leave_room_for_the_batch_overhead() {
+		let indices = PalletIndices::new(&[]).unwrap();
+		let calls: Vec<_> = (0..100_000u128)
+			.map(|collection_loc_id| {
+				let call = pallet_logion_loc::Call::import_verified_issuer_selection {
+					collection_loc_id,
+					issuer: sp_keyring::Sr25519Keyring::Alice.to_account_id(),
+				};
+				("issuerSelections", RuntimeCall::LogionLoc(call))
+			})
+			.collect();
+
+		let batches = pack(calls, &indices).unwrap();
+
+		assert!(batches.len() > 1);
+		assert_eq!(batches.iter().map(|batch| batch.calls.len()).sum::<usize>(), 100_000);
+		for batch in batches {
+			assert!(Weight::from_parts(batch.weight.0, batch.weight.1).all_lte(max_batch_weight()));
+			let length: usize = batch.calls.iter().map(|call| call.len()).sum();
+			assert!(length <= max_batch_length());
+		}
+	}
+}
@@ -0,0 +1,133 @@
+//! Keeps the nodes of the legal officers registered in `LoAuthorityList` as reserved peers.
+//!
+//! The set of nodes is read from the state of the last finalized block and updated on each
+//! finality notification, so that legal officers joining or leaving the authority list are taken
+//! into account without restarting the node.
+//!
+//! Only the peer IDs of the nodes are known on chain: their addresses are taken from the boot
+//! nodes and reserved nodes of the configuration. A legal officer node without a known address
+//! is not reserved, a warning tells to add it to `--bootnodes` or `--reserved-nodes`.
+
+use std::{
+	collections::{HashMap, HashSet},
+	sync::Arc,
+};
+
+use futures::StreamExt;
+use logion_node_runtime::{apis::LegalOfficerNodesApi, opaque::Block};
+use sc_client_api::BlockchainEvents;
+use sc_network::{config::MultiaddrWithPeerId, Multiaddr, NetworkPeers, PeerId};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::traits::Block as BlockT;
+
+const LOG_TARGET: &str = "logion-peering";
+
+/// Adds the nodes of the legal officers to the reserved peers, and removes the nodes that are no
+/// longer registered, each time a block is finalized. The addresses of the nodes are taken from
+/// `known_nodes`, e.g. the boot nodes.
+pub async fn keep_legal_officer_nodes_reserved<C, N>(
+	client: Arc<C>,
+	network: Arc<N>,
+	known_nodes: Vec<MultiaddrWithPeerId>,
+) where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + BlockchainEvents<Block>,
+	C::Api: LegalOfficerNodesApi<Block>,
+	N: NetworkPeers,
+{
+	let mut addresses: HashMap<PeerId, Vec<Multiaddr>> = HashMap::new();
+	for node in known_nodes {
+		addresses.entry(node.peer_id).or_default().push(node.multiaddr);
+	}
+	let mut finality_notifications = client.finality_notification_stream();
+	let mut reserved = HashSet::new();
+	let finalized_hash = client.info().finalized_hash;
+	update_reserved_peers(
+		client.as_ref(),
+		network.as_ref(),
+		&addresses,
+		finalized_hash,
+		&mut reserved,
+	);
+	while let Some(notification) = finality_notifications.next().await {
+		update_reserved_peers(
+			client.as_ref(),
+			network.as_ref(),
+			&addresses,
+			notification.hash,
+			&mut reserved,
+		);
+	}
+}
+
+fn update_reserved_peers<C, N>(
+	client: &C,
+	network: &N,
+	addresses: &HashMap<PeerId, Vec<Multiaddr>>,
+	at: <Block as BlockT>::Hash,
+	reserved: &mut HashSet<PeerId>,
+) where
+	C: ProvideRuntimeApi<Block>,
+	C::Api: LegalOfficerNodesApi<Block>,
+	N: NetworkPeers,
+{
+	let legal_officer_nodes = match legal_officer_nodes(client, at) {
+		Ok(nodes) => nodes,
+		Err(e) => {
+			log::warn!(target: LOG_TARGET, "Unable to read legal officer nodes: {}", e);
+			return
+		},
+	};
+
+	for peer_id in legal_officer_nodes.difference(reserved) {
+		let Some(node_addresses) = addresses.get(peer_id) else {
+			log::warn!(
+				target: LOG_TARGET,
+				"No address known for legal officer node {}, add it to --bootnodes or \
+				 --reserved-nodes",
+				peer_id
+			);
+			continue
+		};
+		for multiaddr in node_addresses {
+			let peer = MultiaddrWithPeerId { multiaddr: multiaddr.clone(), peer_id: *peer_id };
+			match network.add_reserved_peer(peer) {
+				Ok(()) => log::info!(
+					target: LOG_TARGET,
+					"Reserved legal officer node {} at {}",
+					peer_id,
+					multiaddr
+				),
+				Err(e) => log::debug!(target: LOG_TARGET, "Node {} not reserved: {}", peer_id, e),
+			}
+		}
+	}
+	for peer_id in reserved.difference(&legal_officer_nodes) {
+		network.remove_reserved_peer(*peer_id);
+		log::info!(target: LOG_TARGET, "Removed legal officer node {} from reserved peers", peer_id);
+	}
+	*reserved = legal_officer_nodes;
+}
+
+/// Returns the peer IDs of the legal officer nodes registered at block `at`. Invalid peer IDs are
+/// ignored.
+pub fn legal_officer_nodes<C>(
+	client: &C,
+	at: <Block as BlockT>::Hash,
+) -> Result<HashSet<PeerId>, sp_api::ApiError>
+where
+	C: ProvideRuntimeApi<Block>,
+	C::Api: LegalOfficerNodesApi<Block>,
+{
+	let nodes = client.runtime_api().legal_officer_nodes(at)?;
+	Ok(nodes
+		.into_iter()
+		.filter_map(|node| match PeerId::from_bytes(&node.0) {
+			Ok(peer_id) => Some(peer_id),
+			Err(_) => {
+				log::warn!(target: LOG_TARGET, "Ignoring invalid legal officer node ID {:?}", node);
+				None
+			},
+		})
+		.collect())
+}
@@ -0,0 +1,645 @@
+//! Offline-verifiable proofs of existence of LOCs and collection items.
+//!
+//! A [`ProofBundle`] contains everything needed to check, without network access, that a closed
+//! LOC or a collection item was part of the state of a block finalized by GRANDPA:
+//! - the header of the block;
+//! - a GRANDPA justification of the block, and the authority set which signed it;
+//! - the authority set changes from a trusted set to the one which signed the justification,
+//!   each with the justification of the block enacting it;
+//! - a storage read proof of the item and of the block's timestamp against the header's state
+//!   root.
+//!
+//! The verifier has to trust a root authority set: the genesis one of a chain spec (`--chain`),
+//! or a set known from other sources (`--trusted-authorities`). Bundles which do not chain to it
+//! are rejected.
+
+use std::{fs, path::PathBuf};
+
+use codec::Decode;
+use logion_node_runtime::{opaque::Block, BlockNumber, Grandpa, Hash, LocId, Runtime};
+use sc_cli::{CliConfiguration, SharedParams};
+use sc_client_api::{BlockBackend, ProofProvider};
+use sc_service::ChainSpec;
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_consensus_grandpa::{
+	AuthorityId as GrandpaId, AuthorityList, AuthorityWeight, ConsensusLog, GrandpaApi, SetId,
+	GRANDPA_ENGINE_ID,
+};
+use sp_core::Bytes;
+use sp_runtime::{
+	traits::{Block as BlockT, HashingFor, Header as HeaderT},
+	BuildStorage,
+};
+use sp_state_machine::{read_proof_check, BasicExternalities, StorageProof};
+
+type Header = <Block as BlockT>::Header;
+
+/// The maximum number of blocks searched, back from the last finalized one, for a justification.
+/// Justifications are kept at least every justification period (512 blocks by default).
+const MAX_JUSTIFICATION_SEARCH_DEPTH: BlockNumber = 4096;
+
+/// The LOC or collection item a proof is about.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum ProofTarget {
+	/// A closed LOC, including its files.
+	Loc {
+		/// The ID of the LOC.
+		loc_id: LocId,
+	},
+	/// An item of a collection LOC, including its files.
+	CollectionItem {
+		/// The ID of the collection LOC.
+		loc_id: LocId,
+		/// The ID of the item.
+		item_id: Hash,
+	},
+}
+
+impl ProofTarget {
+	fn storage_key(&self) -> Vec<u8> {
+		match self {
+			Self::Loc { loc_id } => pallet_logion_loc::LocMap::<Runtime>::hashed_key_for(loc_id),
+			Self::CollectionItem { loc_id, item_id } =>
+				pallet_logion_loc::CollectionItemsMap::<Runtime>::hashed_key_for(loc_id, item_id),
+		}
+	}
+}
+
+fn timestamp_key() -> Vec<u8> {
+	pallet_timestamp::Now::<Runtime>::hashed_key().to_vec()
+}
+
+/// A GRANDPA authority set.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthoritySet {
+	/// The ID of the set.
+	pub set_id: SetId,
+	/// The authorities and their weights.
+	pub authorities: Vec<(GrandpaId, AuthorityWeight)>,
+}
+
+impl AuthoritySet {
+	/// The genesis authority set of `chain_spec`.
+	pub fn genesis(chain_spec: &dyn ChainSpec) -> Result<Self, String> {
+		let storage = chain_spec.build_storage()?;
+		let authorities =
+			BasicExternalities::new(storage).execute_with(Grandpa::grandpa_authorities);
+		Ok(Self { set_id: 0, authorities })
+	}
+}
+
+/// The enactment of a new authority set, signed by the previous one.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthoritySetChange {
+	/// The header of the block scheduling the change, with no delay.
+	pub header: Header,
+	/// The SCALE-encoded GRANDPA justification of the block, by the previous set.
+	pub justification: Bytes,
+}
+
+/// A self-contained proof that a LOC or collection item exists in a finalized block.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ProofBundle {
+	/// What is proven.
+	pub target: ProofTarget,
+	/// The header of the finalized block.
+	pub header: Header,
+	/// The SCALE-encoded GRANDPA justification of the block.
+	pub justification: Bytes,
+	/// The authority set which signed the justification.
+	pub authority_set: AuthoritySet,
+	/// The authority set changes from the trusted set to `authority_set`, in order.
+	pub set_changes: Vec<AuthoritySetChange>,
+	/// The trie nodes proving the target and the block's timestamp against the state root.
+	pub storage_proof: Vec<Bytes>,
+}
+
+/// The facts established by a valid [`ProofBundle`].
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifiedProof {
+	/// What is proven.
+	pub target: ProofTarget,
+	/// The number of the finalized block.
+	pub block_number: BlockNumber,
+	/// The hash of the finalized block.
+	pub block_hash: Hash,
+	/// The timestamp of the block, in milliseconds since Unix epoch.
+	pub timestamp: u64,
+	/// The ID of the authority set which finalized the block.
+	pub set_id: SetId,
+	/// The ID of the trusted authority set the bundle chains to.
+	pub trusted_set_id: SetId,
+	/// The hashes of the files of the LOC or collection item.
+	pub file_hashes: Vec<Hash>,
+}
+
+/// Builds a proof bundle for `target` at the last block with a GRANDPA justification, chaining to
+/// authority set `trusted_set_id`.
+///
+/// Justifications are only kept every `grandpaJustificationPeriod` blocks (see `consensus`) and on
+/// authority set changes, so a LOC closed very recently may not be provable yet. The authority
+/// set changes are found with the state of past blocks, which must not be pruned.
+pub fn build_proof_bundle<C>(
+	client: &C,
+	target: ProofTarget,
+	trusted_set_id: SetId,
+) -> Result<ProofBundle, String>
+where
+	C: HeaderBackend<Block>
+		+ BlockBackend<Block>
+		+ ProofProvider<Block>
+		+ ProvideRuntimeApi<Block>,
+	C::Api: GrandpaApi<Block>,
+{
+	let (hash, header, justification) = last_justified_block(client)?;
+
+	// With a zero delay, authority set changes are enacted by the runtime in the block signed by
+	// the previous set, so the signing set is the one of the parent state.
+	let parent_hash = *header.parent_hash();
+	let runtime_api = client.runtime_api();
+	let authority_set = AuthoritySet {
+		set_id: runtime_api.current_set_id(parent_hash).map_err(|e| e.to_string())?,
+		authorities: runtime_api.grandpa_authorities(parent_hash).map_err(|e| e.to_string())?,
+	};
+
+	let set_changes =
+		authority_set_changes(client, trusted_set_id, authority_set.set_id, *header.number())?;
+
+	let keys = [target.storage_key(), timestamp_key()];
+	let storage_proof = client
+		.read_proof(hash, &mut keys.iter().map(|key| key.as_slice()))
+		.map_err(|e| format!("Unable to build storage proof: {}", e))?;
+
+	let bundle = ProofBundle {
+		target,
+		header,
+		justification: justification.into(),
+		authority_set,
+		set_changes,
+		storage_proof: storage_proof.into_iter_nodes().map(Into::into).collect(),
+	};
+	Ok(bundle)
+}
+
+/// Returns the enactments of sets `from_set_id + 1` to `to_set_id`, found before block `before`.
+fn authority_set_changes<C>(
+	client: &C,
+	from_set_id: SetId,
+	to_set_id: SetId,
+	before: BlockNumber,
+) -> Result<Vec<AuthoritySetChange>, String>
+where
+	C: HeaderBackend<Block> + BlockBackend<Block> + ProvideRuntimeApi<Block>,
+	C::Api: GrandpaApi<Block>,
+{
+	if from_set_id > to_set_id {
+		return Err(format!(
+			"Unknown authority set {}, the current one is {}",
+			from_set_id, to_set_id
+		))
+	}
+	let mut changes = Vec::new();
+	let mut low = 0;
+	for set_id in from_set_id + 1..=to_set_id {
+		// The set ID only grows: the enacting block is the first one whose state has the new ID.
+		let mut high = before;
+		while low < high {
+			let middle = low + (high - low) / 2;
+			if set_id_at(client, middle)? < set_id {
+				low = middle + 1;
+			} else {
+				high = middle;
+			}
+		}
+		let hash = block_hash(client, low)?;
+		let header = client
+			.header(hash)
+			.map_err(|e| e.to_string())?
+			.ok_or_else(|| format!("Header of block {} not found", low))?;
+		let justification = justification(client, hash)?
+			.ok_or_else(|| format!("Justification of authority set change {} not found", low))?;
+		changes.push(AuthoritySetChange { header, justification: justification.into() });
+	}
+	Ok(changes)
+}
+
+fn block_hash<C: HeaderBackend<Block>>(client: &C, number: BlockNumber) -> Result<Hash, String> {
+	client
+		.hash(number)
+		.map_err(|e| e.to_string())?
+		.ok_or_else(|| format!("Block {} not found", number))
+}
+
+fn set_id_at<C>(client: &C, number: BlockNumber) -> Result<SetId, String>
+where
+	C: HeaderBackend<Block> + ProvideRuntimeApi<Block>,
+	C::Api: GrandpaApi<Block>,
+{
+	let hash = block_hash(client, number)?;
+	client
+		.runtime_api()
+		.current_set_id(hash)
+		.map_err(|e| format!("Unable to read the authority set of block {}: {}", number, e))
+}
+
+fn justification<C: BlockBackend<Block>>(
+	client: &C,
+	hash: Hash,
+) -> Result<Option<Vec<u8>>, String> {
+	Ok(client
+		.justifications(hash)
+		.map_err(|e| e.to_string())?
+		.and_then(|justifications| justifications.into_justification(GRANDPA_ENGINE_ID)))
+}
+
+fn last_justified_block<C>(client: &C) -> Result<(Hash, Header, Vec<u8>), String>
+where
+	C: HeaderBackend<Block> + BlockBackend<Block>,
+{
+	let info = client.info();
+	let mut hash = info.finalized_hash;
+	let lowest = info.finalized_number.saturating_sub(MAX_JUSTIFICATION_SEARCH_DEPTH);
+	loop {
+		let header = client
+			.header(hash)
+			.map_err(|e| e.to_string())?
+			.ok_or_else(|| format!("Header of block {} not found", hash))?;
+		if let Some(justification) = justification(client, hash)? {
+			return Ok((hash, header, justification))
+		}
+		if *header.number() <= lowest {
+			return Err(format!(
+				"No block with a GRANDPA justification found after block {}",
+				lowest
+			))
+		}
+		hash = *header.parent_hash();
+	}
+}
+
+/// Checks that `header` is finalized by `justification`, signed by `set`.
+fn verify_justification(
+	header: &Header,
+	justification: &[u8],
+	set: &AuthoritySet,
+) -> Result<(), String> {
+	let voters = finality_grandpa::voter_set::VoterSet::new(set.authorities.iter().cloned())
+		.ok_or("Invalid authority set")?;
+	sc_consensus_grandpa::GrandpaJustification::<Block>::decode_and_verify_finalizes(
+		justification,
+		(header.hash(), *header.number()),
+		set.set_id,
+		&voters,
+	)
+	.map(|_| ())
+	.map_err(|e| format!("Invalid justification of block {}: {}", header.number(), e))
+}
+
+/// The authorities of the change scheduled by `header`.
+fn scheduled_authorities(header: &Header) -> Result<AuthorityList, String> {
+	let change = header
+		.digest()
+		.logs()
+		.iter()
+		.find_map(|log| match log.consensus_try_to(&GRANDPA_ENGINE_ID) {
+			Some(ConsensusLog::<BlockNumber>::ScheduledChange(change)) => Some(change),
+			_ => None,
+		})
+		.ok_or_else(|| format!("Block {} does not change the authority set", header.number()))?;
+	if change.delay != 0 {
+		return Err(format!("Delayed authority set change in block {}", header.number()))
+	}
+	Ok(change.next_authorities)
+}
+
+/// Checks that the authority set of `bundle` chains to `trusted`, and that the bundle's block is
+/// finalized by it.
+fn verify_finality(bundle: &ProofBundle, trusted: &AuthoritySet) -> Result<(), String> {
+	if bundle.authority_set.set_id < trusted.set_id {
+		return Err(format!(
+			"Authority set {} precedes the trusted set {}",
+			bundle.authority_set.set_id, trusted.set_id
+		))
+	}
+	let mut set = trusted.clone();
+	let mut previous_number = 0;
+	for change in bundle.set_changes.iter() {
+		let number = *change.header.number();
+		if number < previous_number || number > *bundle.header.number() {
+			return Err(format!("Authority set change in block {} is out of order", number))
+		}
+		verify_justification(&change.header, &change.justification, &set)?;
+		let authorities = scheduled_authorities(&change.header)?;
+		set = AuthoritySet { set_id: set.set_id + 1, authorities };
+		previous_number = number;
+	}
+	if set != bundle.authority_set {
+		return Err(format!(
+			"Authority set {} does not chain to the trusted set {}",
+			bundle.authority_set.set_id, trusted.set_id
+		))
+	}
+	verify_justification(&bundle.header, &bundle.justification, &set)
+}
+
+/// Checks `bundle` without any access to the chain. Its authority set must chain to `trusted`.
+pub fn verify_proof_bundle(
+	bundle: &ProofBundle,
+	trusted: &AuthoritySet,
+) -> Result<VerifiedProof, String> {
+	verify_finality(bundle, trusted)?;
+
+	let block_hash = bundle.header.hash();
+	let block_number = *bundle.header.number();
+
+	let target_key = bundle.target.storage_key();
+	let timestamp_key = timestamp_key();
+	let proof = StorageProof::new(bundle.storage_proof.iter().map(|node| node.to_vec()));
+	let mut values = read_proof_check::<HashingFor<Block>, _>(
+		*bundle.header.state_root(),
+		proof,
+		[&target_key, &timestamp_key],
+	)
+	.map_err(|e| format!("Invalid storage proof: {}", e))?;
+
+	let timestamp = values
+		.remove(&timestamp_key)
+		.flatten()
+		.and_then(|value| u64::decode(&mut &value[..]).ok())
+		.ok_or("Block timestamp not proven")?;
+	let value = values.remove(&target_key).flatten().ok_or("Target not found in block state")?;
+	let file_hashes = match bundle.target {
+		ProofTarget::Loc { .. } => {
+			let loc = pallet_logion_loc::LegalOfficerCaseOf::<Runtime>::decode(&mut &value[..])
+				.map_err(|e| format!("Unable to decode LOC: {}", e))?;
+			if !loc.closed || loc.void_info.is_some() {
+				return Err("LOC is not closed or was voided".into())
+			}
+			loc.files.iter().map(|file| file.hash).collect()
+		},
+		ProofTarget::CollectionItem { .. } => {
+			let item = pallet_logion_loc::CollectionItemOf::<Runtime>::decode(&mut &value[..])
+				.map_err(|e| format!("Unable to decode collection item: {}", e))?;
+			item.files.iter().map(|file| file.hash).collect()
+		},
+	};
+
+	Ok(VerifiedProof {
+		target: bundle.target.clone(),
+		block_number,
+		block_hash,
+		timestamp,
+		set_id: bundle.authority_set.set_id,
+		trusted_set_id: trusted.set_id,
+		file_hashes,
+	})
+}
+
+/// The `export-proof` command.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct ExportProofCmd {
+	/// The ID of the LOC.
+	#[arg(long)]
+	pub loc_id: LocId,
+
+	/// The ID of the collection item, if the proof is about an item of a collection LOC.
+	#[arg(long)]
+	pub item_id: Option<Hash>,
+
+	/// The ID of the authority set the bundle chains to, the genesis one by default.
+	#[arg(long, default_value_t = 0)]
+	pub trusted_set_id: SetId,
+
+	/// The file to write the bundle to, standard output by default.
+	#[arg(long)]
+	pub output: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl ExportProofCmd {
+	/// Builds the bundle and writes it as JSON.
+	pub fn run<C>(&self, client: &C) -> sc_cli::Result<()>
+	where
+		C: HeaderBackend<Block>
+			+ BlockBackend<Block>
+			+ ProofProvider<Block>
+			+ ProvideRuntimeApi<Block>,
+		C::Api: GrandpaApi<Block>,
+	{
+		let target = match self.item_id {
+			Some(item_id) => ProofTarget::CollectionItem { loc_id: self.loc_id, item_id },
+			None => ProofTarget::Loc { loc_id: self.loc_id },
+		};
+		let bundle = build_proof_bundle(client, target, self.trusted_set_id)?;
+		write_json(&bundle, self.output.as_ref())
+	}
+}
+
+impl CliConfiguration for ExportProofCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+}
+
+/// The `verify-proof` command.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct VerifyProofCmd {
+	/// The bundle produced by `export-proof` or `logion_exportProof`.
+	pub bundle: PathBuf,
+
+	/// A JSON file with a trusted authority set (ID and authorities) the bundle must chain to.
+	#[arg(long, required_unless_present = "chain", conflicts_with = "chain")]
+	pub trusted_authorities: Option<PathBuf>,
+
+	/// The chain spec whose genesis authority set the bundle must chain to, e.g. `mvp`.
+	#[arg(long)]
+	pub chain: Option<String>,
+
+	/// A file hash which must be part of the LOC or collection item.
+	#[arg(long)]
+	pub hash: Option<Hash>,
+}
+
+impl VerifyProofCmd {
+	/// Verifies the bundle and prints the established facts as JSON. `chain_spec` is the spec
+	/// given with `--chain`.
+	pub fn run(&self, chain_spec: Option<&dyn ChainSpec>) -> sc_cli::Result<()> {
+		let bundle: ProofBundle = read_json(&self.bundle)?;
+		let trusted: AuthoritySet = match (&self.trusted_authorities, chain_spec) {
+			(Some(path), _) => read_json(path)?,
+			(None, Some(chain_spec)) => AuthoritySet::genesis(chain_spec)?,
+			(None, None) =>
+				return Err("Either --trusted-authorities or --chain is required".into()),
+		};
+		let verified = verify_proof_bundle(&bundle, &trusted)?;
+		if let Some(hash) = self.hash {
+			if !verified.file_hashes.contains(&hash) {
+				return Err(format!("File {:?} not found in proven item", hash).into())
+			}
+		}
+		write_json(&verified, None)
+	}
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &PathBuf) -> sc_cli::Result<T> {
+	let content = fs::read(path)?;
+	serde_json::from_slice(&content)
+		.map_err(|e| format!("Unable to parse {}: {}", path.display(), e).into())
+}
+
+fn write_json<T: Serialize>(value: &T, output: Option<&PathBuf>) -> sc_cli::Result<()> {
+	let json = serde_json::to_string_pretty(value)
+		.map_err(|e| format!("Unable to serialize: {}", e))?;
+	match output {
+		Some(path) => fs::write(path, json)?,
+		None => println!("{}", json),
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Encode;
+	use finality_grandpa::{Commit, Message, Precommit, SignedPrecommit};
+	use sp_consensus_grandpa::ScheduledChange;
+	use sp_core::storage::{StateVersion, Storage};
+	use sp_keyring::Ed25519Keyring;
+	use sp_runtime::{generic::DigestItem, traits::BlakeTwo256, Digest};
+	use sp_state_machine::{prove_read, InMemoryBackend};
+
+	fn authority_set(set_id: SetId, keys: &[Ed25519Keyring]) -> AuthoritySet {
+		let authorities = keys.iter().map(|key| (key.public().into(), 1)).collect();
+		AuthoritySet { set_id, authorities }
+	}
+
+	fn first_set() -> AuthoritySet {
+		authority_set(0, &[Ed25519Keyring::Alice, Ed25519Keyring::Bob, Ed25519Keyring::Charlie])
+	}
+
+	fn second_set() -> AuthoritySet {
+		authority_set(1, &[Ed25519Keyring::Dave, Ed25519Keyring::Eve, Ed25519Keyring::Ferdie])
+	}
+
+	fn header(number: BlockNumber, state_root: Hash, logs: Vec<DigestItem>) -> Header {
+		Header::new(number, Hash::zero(), state_root, Hash::repeat_byte(1), Digest { logs })
+	}
+
+	fn change_header(number: BlockNumber, next: &AuthoritySet) -> Header {
+		let change = ConsensusLog::<BlockNumber>::ScheduledChange(ScheduledChange {
+			next_authorities: next.authorities.clone(),
+			delay: 0,
+		});
+		let log = DigestItem::Consensus(GRANDPA_ENGINE_ID, change.encode());
+		header(number, Hash::zero(), vec![log])
+	}
+
+	fn justify(header: &Header, set_id: SetId, keys: &[Ed25519Keyring]) -> Bytes {
+		let round = 1;
+		let precommit = Precommit { target_hash: header.hash(), target_number: *header.number() };
+		let payload = sp_consensus_grandpa::localized_payload(
+			round,
+			set_id,
+			&Message::<Hash, BlockNumber>::Precommit(precommit.clone()),
+		);
+		let precommits = keys
+			.iter()
+			.map(|key| SignedPrecommit {
+				precommit: precommit.clone(),
+				signature: key.pair().sign(&payload).into(),
+				id: key.public().into(),
+			})
+			.collect();
+		sp_consensus_grandpa::GrandpaJustification::<Header> {
+			round,
+			commit: Commit {
+				target_hash: header.hash(),
+				target_number: *header.number(),
+				precommits,
+			},
+			votes_ancestries: vec![],
+		}
+		.encode()
+		.into()
+	}
+
+	fn bundle(state_root: Hash, storage_proof: Vec<Bytes>) -> ProofBundle {
+		let change = change_header(10, &second_set());
+		let header = header(20, state_root, vec![]);
+		ProofBundle {
+			target: ProofTarget::Loc { loc_id: 1 },
+			justification: justify(
+				&header,
+				1,
+				&[Ed25519Keyring::Dave, Ed25519Keyring::Eve, Ed25519Keyring::Ferdie],
+			),
+			header,
+			authority_set: second_set(),
+			set_changes: vec![AuthoritySetChange {
+				justification: justify(
+					&change,
+					0,
+					&[Ed25519Keyring::Alice, Ed25519Keyring::Bob, Ed25519Keyring::Charlie],
+				),
+				header: change,
+			}],
+			storage_proof,
+		}
+	}
+
+	#[test]
+	fn chains_authority_sets() {
+		let bundle = bundle(Hash::zero(), vec![]);
+
+		assert_eq!(verify_finality(&bundle, &first_set()), Ok(()));
+		assert_eq!(verify_finality(&bundle, &second_set()).map_err(|_| ()), Err(()));
+		let trusting_second = ProofBundle { set_changes: vec![], ..bundle.clone() };
+		assert_eq!(verify_finality(&trusting_second, &second_set()), Ok(()));
+	}
+
+	#[test]
+	fn rejects_wrong_authorities_and_unknown_set() {
+		let bundle = bundle(Hash::zero(), vec![]);
+
+		let wrong = authority_set(0, &[Ed25519Keyring::Dave, Ed25519Keyring::Eve]);
+		assert!(verify_finality(&bundle, &wrong).unwrap_err().starts_with("Invalid justification"));
+		let self_certified = ProofBundle { set_changes: vec![], ..bundle.clone() };
+		assert!(verify_finality(&self_certified, &first_set())
+			.unwrap_err()
+			.contains("does not chain"));
+		let unknown = authority_set(2, &[Ed25519Keyring::Alice]);
+		assert!(verify_finality(&bundle, &unknown).unwrap_err().contains("precedes"));
+	}
+
+	#[test]
+	fn rejects_tampered_storage_proof() {
+		let storage = Storage {
+			top: [(timestamp_key(), 1_700_000_000_000u64.encode())].into_iter().collect(),
+			children_default: Default::default(),
+		};
+		let backend = InMemoryBackend::<BlakeTwo256>::from((storage, StateVersion::V1));
+		let state_root = *backend.root();
+		let proof: Vec<Bytes> = prove_read(backend, [timestamp_key()])
+			.unwrap()
+			.into_iter_nodes()
+			.map(Into::into)
+			.collect();
+
+		let error = verify_proof_bundle(&bundle(state_root, proof.clone()), &first_set());
+		assert_eq!(error, Err("Target not found in block state".into()));
+		let mut tampered = proof;
+		tampered[0].0[4] ^= 1;
+		let error = verify_proof_bundle(&bundle(state_root, tampered), &first_set());
+		assert!(error.unwrap_err().starts_with("Invalid storage proof"));
+	}
+}
@@ -8,7 +8,16 @@
 use std::sync::Arc;
 
 use jsonrpsee::RpcModule;
-use logion_node_runtime::{opaque::Block, AccountId, Balance, Nonce};
+use logion_node_runtime::{
+	opaque::Block, AccountId, Balance, BlockNumber, Hash, LocId, Nonce, RuntimeCall,
+	RuntimeEvent,
+};
+use sc_client_api::{Backend, BlockBackend, BlockchainEvents, ProofProvider, StorageProvider};
+use sc_consensus_grandpa::{
+	FinalityProofProvider, GrandpaJustificationStream, SharedAuthoritySet, SharedVoterState,
+};
+use sc_consensus_manual_seal::EngineCommand;
+use sc_rpc::SubscriptionTaskExecutor;
 use sc_transaction_pool_api::TransactionPool;
 use sp_api::ProvideRuntimeApi;
 use sp_block_builder::BlockBuilder;
@@ -16,37 +25,132 @@ use sp_blockchain::{Error as BlockChainError, HeaderBackend, HeaderMetadata};
 
 pub use sc_rpc_api::DenyUnsafe;
 
+pub mod config;
+pub mod dry_run;
+pub mod error_decoder;
+pub mod events;
+pub mod hash_anchoring;
+pub mod indexer;
+pub mod loc_index;
+pub mod proof;
+pub mod reward_accounting;
+
+/// Extra dependencies for GRANDPA
+pub struct GrandpaDeps<B> {
+	/// Voting round info.
+	pub shared_voter_state: SharedVoterState,
+	/// Authority set info.
+	pub shared_authority_set: SharedAuthoritySet<Hash, BlockNumber>,
+	/// Receives notifications about justification events from Grandpa.
+	pub justification_stream: GrandpaJustificationStream<Block>,
+	/// Executor to drive the subscription manager in the Grandpa RPC handler.
+	pub subscription_executor: SubscriptionTaskExecutor,
+	/// Finality proof provider.
+	pub finality_provider: Arc<FinalityProofProvider<B, Block>>,
+}
+
 /// Full client dependencies.
-pub struct FullDeps<C, P> {
+pub struct FullDeps<C, P, B> {
 	/// The client instance to use.
 	pub client: Arc<C>,
+	/// The backend instance to use.
+	pub backend: Arc<B>,
 	/// Transaction pool instance.
 	pub pool: Arc<P>,
 	/// Whether to deny unsafe calls
 	pub deny_unsafe: DenyUnsafe,
+	/// GRANDPA specific dependencies.
+	pub grandpa: GrandpaDeps<B>,
+	/// The database of the event indexer, if enabled.
+	pub indexer: Option<Arc<crate::indexer::IndexerDb>>,
+	/// The channel of the `engine_*` RPCs, if blocks are sealed manually.
+	pub manual_seal: Option<futures::channel::mpsc::Sender<EngineCommand<Hash>>>,
 }
 
 /// Instantiate all full RPC extensions.
-pub fn create_full<C, P>(
-	deps: FullDeps<C, P>,
+pub fn create_full<C, P, B>(
+	deps: FullDeps<C, P, B>,
 ) -> Result<RpcModule<()>, Box<dyn std::error::Error + Send + Sync>>
 where
 	C: ProvideRuntimeApi<Block>,
 	C: HeaderBackend<Block> + HeaderMetadata<Block, Error = BlockChainError> + 'static,
+	C: BlockBackend<Block> + ProofProvider<Block>,
+	C: BlockchainEvents<Block> + StorageProvider<Block, B>,
 	C: Send + Sync + 'static,
 	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Nonce>,
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
 	C::Api: BlockBuilder<Block>,
+	C::Api: logion_node_runtime::apis::DryRunApi<Block, AccountId, RuntimeCall, RuntimeEvent, Balance>,
+	C::Api: logion_node_runtime::apis::ErrorDecoderApi<Block>,
+	C::Api: logion_node_runtime::apis::LogionConfigApi<Block, Balance, BlockNumber>,
+	C::Api: sp_consensus_grandpa::GrandpaApi<Block>,
+	C::Api: sp_mmr_primitives::MmrApi<Block, Hash, BlockNumber>,
+	C::Api: pallet_hash_anchoring::runtime_api::HashAnchoringApi<
+		Block,
+		AccountId,
+		LocId,
+		Hash,
+		BlockNumber,
+	>,
+	C::Api: pallet_reward_accounting::runtime_api::RewardAccountingApi<Block, AccountId, Balance>,
 	P: TransactionPool + 'static,
+	B: sc_client_api::Backend<Block> + Send + Sync + 'static,
+	B::State: sc_client_api::StateBackend<sp_runtime::traits::HashingFor<Block>>,
 {
+	use config::{Config, LogionConfigApiServer};
+	use dry_run::{DryRun, DryRunApiServer};
+	use error_decoder::{ErrorDecoder, ErrorDecoderApiServer};
+	use events::{Events, EventsApiServer};
+	use hash_anchoring::{HashAnchoring, HashAnchoringApiServer};
+	use indexer::{Indexer, IndexerApiServer};
+	use loc_index::{LocIndex, LocIndexApiServer};
+	use mmr_rpc::{Mmr, MmrApiServer};
+	use sc_consensus_manual_seal::rpc::{ManualSeal, ManualSealApiServer};
+	use sc_consensus_grandpa_rpc::{Grandpa, GrandpaApiServer};
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApiServer};
+	use proof::{Proof, ProofApiServer};
+	use reward_accounting::{RewardAccounting, RewardAccountingApiServer};
 	use substrate_frame_rpc_system::{System, SystemApiServer};
 
 	let mut module = RpcModule::new(());
-	let FullDeps { client, pool, deny_unsafe } = deps;
+	let FullDeps { client, backend, pool, deny_unsafe, grandpa, indexer, manual_seal } = deps;
+	let GrandpaDeps {
+		shared_voter_state,
+		shared_authority_set,
+		justification_stream,
+		subscription_executor,
+		finality_provider,
+	} = grandpa;
 
 	module.merge(System::new(client.clone(), pool.clone(), deny_unsafe).into_rpc())?;
-	module.merge(TransactionPayment::new(client).into_rpc())?;
+	module.merge(TransactionPayment::new(client.clone()).into_rpc())?;
+	module.merge(DryRun::new(client.clone(), deny_unsafe).into_rpc())?;
+	module.merge(ErrorDecoder::new(client.clone()).into_rpc())?;
+	module.merge(Config::new(client.clone()).into_rpc())?;
+	module.merge(Proof::new(client.clone()).into_rpc())?;
+	module.merge(HashAnchoring::new(client.clone()).into_rpc())?;
+	let offchain_storage =
+		backend.offchain_storage().ok_or("Backend doesn't provide an offchain storage")?;
+	module.merge(Mmr::new(client.clone(), offchain_storage.clone()).into_rpc())?;
+	module.merge(LocIndex::new(offchain_storage).into_rpc())?;
+	module.merge(RewardAccounting::new(client.clone()).into_rpc())?;
+	module.merge(Events::<_, B>::new(client, subscription_executor.clone()).into_rpc())?;
+	if let Some(indexer) = indexer {
+		module.merge(Indexer::new(indexer).into_rpc())?;
+	}
+	if let Some(command_sink) = manual_seal {
+		module.merge(ManualSeal::new(command_sink).into_rpc())?;
+	}
+	module.merge(
+		Grandpa::new(
+			subscription_executor,
+			shared_authority_set,
+			shared_voter_state,
+			justification_stream,
+			finality_provider,
+		)
+		.into_rpc(),
+	)?;
 
 	// Extend this RPC with a custom API by using the following syntax.
 	// `YourRpcStruct` should have a reference to a client, which is needed
@@ -0,0 +1,71 @@
+//! RPC wrapper of the runtime's [`LogionConfigApi`](logion_node_runtime::apis::LogionConfigApi).
+
+use std::sync::Arc;
+
+use jsonrpsee::{core::RpcResult, proc_macros::rpc, types::error::ErrorObject};
+use logion_node_runtime::{
+	apis::{LogionConfig, LogionConfigApi as LogionConfigRuntimeApi},
+	opaque::Block,
+	Balance, BlockNumber,
+};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_rpc::number::NumberOrHex;
+use sp_runtime::traits::Block as BlockT;
+
+/// Runtime configuration RPC methods.
+#[rpc(server)]
+pub trait LogionConfigApi<BlockHash> {
+	/// Returns the limits, fees and periods configured in the runtime of block `at` (best
+	/// block by default).
+	#[method(name = "logion_config")]
+	fn logion_config(&self, at: Option<BlockHash>)
+		-> RpcResult<LogionConfig<NumberOrHex, BlockNumber>>;
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+	/// The runtime API call failed.
+	RuntimeError,
+}
+
+impl From<Error> for i32 {
+	fn from(e: Error) -> i32 {
+		match e {
+			Error::RuntimeError => 1,
+		}
+	}
+}
+
+/// Provides RPC methods to query the runtime's configuration.
+pub struct Config<C> {
+	client: Arc<C>,
+}
+
+impl<C> Config<C> {
+	/// Creates a new instance of the Config RPC helper.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client }
+	}
+}
+
+impl<C> LogionConfigApiServer<<Block as BlockT>::Hash> for Config<C>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: LogionConfigRuntimeApi<Block, Balance, BlockNumber>,
+{
+	fn logion_config(
+		&self,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<LogionConfig<NumberOrHex, BlockNumber>> {
+		let at_hash = at.unwrap_or_else(|| self.client.info().best_hash);
+		let config = self.client.runtime_api().logion_config(at_hash).map_err(|e| {
+			ErrorObject::owned(
+				Error::RuntimeError.into(),
+				"Unable to query configuration.",
+				Some(e.to_string()),
+			)
+		})?;
+		Ok(config.map_balances(|balance| NumberOrHex::Hex(balance.into())))
+	}
+}
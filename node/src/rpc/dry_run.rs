@@ -0,0 +1,211 @@
+//! RPC wrapper of the runtime's [`DryRunApi`](logion_node_runtime::apis::DryRunApi).
+
+use std::sync::Arc;
+
+use codec::{Decode, Encode};
+use jsonrpsee::{
+	core::RpcResult,
+	proc_macros::rpc,
+	types::error::ErrorObject,
+};
+use logion_node_runtime::{
+	apis::{DryRunApi as DryRunRuntimeApi, DryRunOrigin, ErrorDecoderApi},
+	opaque::Block,
+	AccountId, Balance, RuntimeCall, RuntimeEvent, Weight,
+};
+use pallet_transaction_payment::{FeeDetails, InclusionFee};
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sc_rpc_api::DenyUnsafe;
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use sp_rpc::number::NumberOrHex;
+use sp_runtime::{traits::Block as BlockT, DispatchError};
+
+use super::error_decoder::{describe_error, ErrorDescription};
+
+/// The outcome of a dry-run, as returned to RPC clients.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DryRunOutcome {
+	/// The error returned by the call, if any.
+	pub error: Option<DispatchError>,
+	/// The human-readable description of `error`, if known to the runtime.
+	pub decoded_error: Option<ErrorDescription>,
+	/// The SCALE-encoded events deposited by the call.
+	pub events: Vec<Bytes>,
+	/// The weight actually used by the call.
+	pub weight: Weight,
+	/// The inclusion fee that a signed extrinsic wrapping the call would pay.
+	pub fee: FeeDetails<NumberOrHex>,
+}
+
+/// The origin a call is dry-run with, e.g. `{"signed": "5GrwvaEF..."}` or `"root"`.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum Origin {
+	/// Root origin, only available on the unsafe RPC interface.
+	Root,
+	/// Signed by given account.
+	Signed(AccountId),
+}
+
+/// Dry-run RPC methods.
+#[rpc(server)]
+pub trait DryRunApi<BlockHash> {
+	/// Executes the SCALE-encoded `call` against the state of block `at` (best block by
+	/// default) and rolls back all changes. Root origin is only available on the unsafe RPC
+	/// interface. Calls weighing more than the maximum extrinsic weight are not dispatched and
+	/// fail with `Exhausted`.
+	#[method(name = "logion_dryRun")]
+	fn dry_run(
+		&self,
+		origin: Origin,
+		call: Bytes,
+		at: Option<BlockHash>,
+	) -> RpcResult<DryRunOutcome>;
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+	/// The call was not a valid `RuntimeCall`.
+	DecodeError,
+	/// The runtime API call failed.
+	RuntimeError,
+	/// Root origin was requested on the safe RPC interface.
+	UnsafeOrigin,
+}
+
+impl From<Error> for i32 {
+	fn from(e: Error) -> i32 {
+		match e {
+			Error::RuntimeError => 1,
+			Error::DecodeError => 2,
+			Error::UnsafeOrigin => 3,
+		}
+	}
+}
+
+/// Provides RPC methods to dry-run calls.
+pub struct DryRun<C> {
+	client: Arc<C>,
+	deny_unsafe: DenyUnsafe,
+}
+
+impl<C> DryRun<C> {
+	/// Creates a new instance of the DryRun RPC helper.
+	pub fn new(client: Arc<C>, deny_unsafe: DenyUnsafe) -> Self {
+		Self { client, deny_unsafe }
+	}
+
+	/// The runtime origin of `origin`, root being denied on the safe RPC interface.
+	fn runtime_origin(&self, origin: Origin) -> RpcResult<DryRunOrigin<AccountId>> {
+		match origin {
+			Origin::Signed(account) => Ok(DryRunOrigin::Signed(account)),
+			Origin::Root => {
+				self.deny_unsafe.check_if_safe().map_err(|e| {
+					ErrorObject::owned(
+						Error::UnsafeOrigin.into(),
+						"Root origin is not available.",
+						Some(e.to_string()),
+					)
+				})?;
+				Ok(DryRunOrigin::Root)
+			},
+		}
+	}
+}
+
+impl<C> DryRunApiServer<<Block as BlockT>::Hash> for DryRun<C>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: DryRunRuntimeApi<Block, AccountId, RuntimeCall, RuntimeEvent, Balance>,
+	C::Api: ErrorDecoderApi<Block>,
+{
+	fn dry_run(
+		&self,
+		origin: Origin,
+		call: Bytes,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<DryRunOutcome> {
+		let origin = self.runtime_origin(origin)?;
+		let api = self.client.runtime_api();
+		let at_hash = at.unwrap_or_else(|| self.client.info().best_hash);
+
+		let call = RuntimeCall::decode(&mut &*call).map_err(|e| {
+			ErrorObject::owned(
+				Error::DecodeError.into(),
+				"Unable to decode call.",
+				Some(format!("{:?}", e)),
+			)
+		})?;
+		let result = api.dry_run(at_hash, origin, call).map_err(|e| {
+			ErrorObject::owned(
+				Error::RuntimeError.into(),
+				"Unable to dry-run call.",
+				Some(e.to_string()),
+			)
+		})?;
+
+		let error = result.result.err().map(|e| e.error);
+		let decoded_error = match error {
+			Some(error) => describe_error(self.client.as_ref(), Some(at_hash), error)?,
+			None => None,
+		};
+
+		Ok(DryRunOutcome {
+			error,
+			decoded_error,
+			events: result.events.iter().map(|event| event.encode().into()).collect(),
+			weight: result.weight,
+			fee: into_rpc_fee_details(result.fee),
+		})
+	}
+}
+
+fn into_rpc_fee_details(fee: FeeDetails<Balance>) -> FeeDetails<NumberOrHex> {
+	FeeDetails {
+		inclusion_fee: fee.inclusion_fee.map(|fee| InclusionFee {
+			base_fee: into_rpc_balance(fee.base_fee),
+			len_fee: into_rpc_balance(fee.len_fee),
+			adjusted_weight_fee: into_rpc_balance(fee.adjusted_weight_fee),
+		}),
+		tip: into_rpc_balance(fee.tip),
+	}
+}
+
+fn into_rpc_balance(value: Balance) -> NumberOrHex {
+	NumberOrHex::Hex(value.into())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_keyring::Sr25519Keyring;
+
+	#[test]
+	fn root_origin_is_denied_on_safe_interface() {
+		let alice = Sr25519Keyring::Alice.to_account_id();
+		let safe = DryRun::new(Arc::new(()), DenyUnsafe::Yes);
+		let unsafe_ = DryRun::new(Arc::new(()), DenyUnsafe::No);
+
+		assert!(safe.runtime_origin(Origin::Root).is_err());
+		assert_eq!(
+			safe.runtime_origin(Origin::Signed(alice.clone())).unwrap(),
+			DryRunOrigin::Signed(alice)
+		);
+		assert_eq!(unsafe_.runtime_origin(Origin::Root).unwrap(), DryRunOrigin::Root);
+	}
+
+	#[test]
+	fn origin_is_explicit() {
+		let alice = Sr25519Keyring::Alice.to_account_id();
+
+		assert_eq!(serde_json::from_str::<Origin>("\"root\"").unwrap(), Origin::Root);
+		assert_eq!(
+			serde_json::from_value::<Origin>(serde_json::json!({ "signed": alice })).unwrap(),
+			Origin::Signed(alice)
+		);
+		assert!(serde_json::from_str::<Origin>("null").is_err());
+	}
+}
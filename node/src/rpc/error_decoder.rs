@@ -0,0 +1,127 @@
+//! RPC wrapper of the runtime's [`ErrorDecoderApi`](logion_node_runtime::apis::ErrorDecoderApi).
+
+use std::sync::Arc;
+
+use codec::Decode;
+use jsonrpsee::{core::RpcResult, proc_macros::rpc, types::error::ErrorObject};
+use logion_node_runtime::{
+	apis::{DecodedError, ErrorDecoderApi as ErrorDecoderRuntimeApi},
+	opaque::Block,
+};
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use sp_runtime::{traits::Block as BlockT, DispatchError};
+
+/// A human-readable description of a `DispatchError`.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorDescription {
+	/// The name of the pallet that returned the error, `None` if not a module error.
+	pub pallet: Option<String>,
+	/// The index of the pallet that returned the error, `None` if not a module error.
+	pub pallet_index: Option<u8>,
+	/// The name of the error variant.
+	pub error: String,
+	/// The index of the error variant.
+	pub error_index: u8,
+	/// The doc comment of the error variant.
+	pub docs: String,
+}
+
+impl From<DecodedError> for ErrorDescription {
+	fn from(decoded: DecodedError) -> Self {
+		Self {
+			pallet: decoded.pallet.as_ref().map(|pallet| lossy_string(&pallet.name)),
+			pallet_index: decoded.pallet.as_ref().map(|pallet| pallet.index),
+			error: lossy_string(&decoded.error),
+			error_index: decoded.error_index,
+			docs: lossy_string(&decoded.docs),
+		}
+	}
+}
+
+fn lossy_string(bytes: &[u8]) -> String {
+	String::from_utf8_lossy(bytes).into_owned()
+}
+
+/// Error decoding RPC methods.
+#[rpc(server)]
+pub trait ErrorDecoderApi<BlockHash> {
+	/// Describes the SCALE-encoded `DispatchError` using the runtime of block `at` (best block
+	/// by default). Returns `null` if the error is unknown to the runtime.
+	#[method(name = "logion_decodeError")]
+	fn decode_error(
+		&self,
+		error: Bytes,
+		at: Option<BlockHash>,
+	) -> RpcResult<Option<ErrorDescription>>;
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+	/// The error was not a valid `DispatchError`.
+	DecodeError,
+	/// The runtime API call failed.
+	RuntimeError,
+}
+
+impl From<Error> for i32 {
+	fn from(e: Error) -> i32 {
+		match e {
+			Error::RuntimeError => 1,
+			Error::DecodeError => 2,
+		}
+	}
+}
+
+/// Provides RPC methods to describe dispatch errors.
+pub struct ErrorDecoder<C> {
+	client: Arc<C>,
+}
+
+impl<C> ErrorDecoder<C> {
+	/// Creates a new instance of the ErrorDecoder RPC helper.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client }
+	}
+}
+
+impl<C> ErrorDecoderApiServer<<Block as BlockT>::Hash> for ErrorDecoder<C>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: ErrorDecoderRuntimeApi<Block>,
+{
+	fn decode_error(
+		&self,
+		error: Bytes,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Option<ErrorDescription>> {
+		let error = DispatchError::decode(&mut &*error).map_err(|e| {
+			ErrorObject::owned(
+				Error::DecodeError.into(),
+				"Unable to decode error.",
+				Some(format!("{:?}", e)),
+			)
+		})?;
+		describe_error(self.client.as_ref(), at, error)
+	}
+}
+
+/// Describes `error` using the runtime of block `at` (best block by default).
+pub fn describe_error<C>(
+	client: &C,
+	at: Option<<Block as BlockT>::Hash>,
+	error: DispatchError,
+) -> RpcResult<Option<ErrorDescription>>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: ErrorDecoderRuntimeApi<Block>,
+{
+	let at_hash = at.unwrap_or_else(|| client.info().best_hash);
+	let decoded = client.runtime_api().decode_error(at_hash, error).map_err(|e| {
+		ErrorObject::owned(Error::RuntimeError.into(), "Unable to decode error.", Some(e.to_string()))
+	})?;
+	Ok(decoded.map(Into::into))
+}
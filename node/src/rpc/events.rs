@@ -0,0 +1,143 @@
+//! Subscriptions to the events concerning a LOC or an account.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use futures::{future, stream, stream::BoxStream, StreamExt};
+use jsonrpsee::{proc_macros::rpc, PendingSubscriptionSink};
+use logion_node_runtime::{opaque::Block, AccountId, Hash, LocId};
+use sc_client_api::{Backend, BlockchainEvents, StorageProvider};
+use sc_rpc::{
+	utils::{pipe_from_stream, spawn_subscription_task},
+	SubscriptionTaskExecutor,
+};
+use serde::{Deserialize, Serialize};
+use sp_blockchain::HeaderBackend;
+
+use crate::events::{DecodedEvent, EventDecoder};
+
+/// The blocks the events of a subscription are taken from.
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum EventSource {
+	/// New best blocks. Events are pushed earlier, but may belong to a block which is later
+	/// retracted.
+	Best,
+	/// Finalized blocks.
+	#[default]
+	Finalized,
+}
+
+/// Event subscription RPC methods.
+#[rpc(server)]
+pub trait EventsApi {
+	/// Pushes the events referring to LOC `loc_id`, taken from `source` blocks (finalized by
+	/// default).
+	#[subscription(
+		name = "logion_subscribeLocEvents" => "logion_locEvent",
+		unsubscribe = "logion_unsubscribeLocEvents",
+		item = DecodedEvent,
+	)]
+	fn subscribe_loc_events(&self, loc_id: LocId, source: Option<EventSource>);
+
+	/// Pushes the events referring to `account`, taken from `source` blocks (finalized by
+	/// default).
+	#[subscription(
+		name = "logion_subscribeAccountEvents" => "logion_accountEvent",
+		unsubscribe = "logion_unsubscribeAccountEvents",
+		item = DecodedEvent,
+	)]
+	fn subscribe_account_events(&self, account: AccountId, source: Option<EventSource>);
+}
+
+/// Provides RPC subscriptions to filtered events.
+pub struct Events<C, B> {
+	client: Arc<C>,
+	executor: SubscriptionTaskExecutor,
+	decoder: Arc<EventDecoder>,
+	_backend: PhantomData<B>,
+}
+
+impl<C, B> Events<C, B> {
+	/// Creates a new instance of the Events RPC helper.
+	pub fn new(client: Arc<C>, executor: SubscriptionTaskExecutor) -> Self {
+		Self { client, executor, decoder: Default::default(), _backend: PhantomData }
+	}
+}
+
+impl<C, B> Events<C, B>
+where
+	C: BlockchainEvents<Block> + StorageProvider<Block, B> + HeaderBackend<Block>,
+	C: Send + Sync + 'static,
+	B: Backend<Block> + Send + Sync + 'static,
+{
+	fn subscribe<F>(&self, pending: PendingSubscriptionSink, source: EventSource, filter: F)
+	where
+		F: Fn(&DecodedEvent) -> bool + Send + 'static,
+	{
+		let blocks: BoxStream<'static, Vec<Hash>> = match source {
+			EventSource::Best => self
+				.client
+				.import_notification_stream()
+				.filter(|notification| future::ready(notification.is_new_best))
+				.map(|notification| vec![notification.hash])
+				.boxed(),
+			// A notification may finalize several blocks at once.
+			EventSource::Finalized => self
+				.client
+				.finality_notification_stream()
+				.map(|notification| {
+					let mut hashes = notification.tree_route.to_vec();
+					hashes.push(notification.hash);
+					hashes
+				})
+				.boxed(),
+		};
+		let client = self.client.clone();
+		let decoder = self.decoder.clone();
+		let events = blocks
+			.flat_map(move |hashes| {
+				let events: Vec<DecodedEvent> = hashes
+					.into_iter()
+					.flat_map(|hash| {
+						decoder.block_events(client.as_ref(), hash).unwrap_or_else(|e| {
+							log::warn!(target: "logion-rpc", "Skipping events of {}: {}", hash, e);
+							Vec::new()
+						})
+					})
+					.filter(|event| filter(event))
+					.collect();
+				stream::iter(events)
+			})
+			.boxed();
+		spawn_subscription_task(&self.executor, pipe_from_stream(pending, events));
+	}
+}
+
+impl<C, B> EventsApiServer for Events<C, B>
+where
+	C: BlockchainEvents<Block> + StorageProvider<Block, B> + HeaderBackend<Block>,
+	C: Send + Sync + 'static,
+	B: Backend<Block> + Send + Sync + 'static,
+{
+	fn subscribe_loc_events(
+		&self,
+		pending: PendingSubscriptionSink,
+		loc_id: LocId,
+		source: Option<EventSource>,
+	) {
+		self.subscribe(pending, source.unwrap_or_default(), move |event| {
+			event.loc_ids.contains(&loc_id)
+		});
+	}
+
+	fn subscribe_account_events(
+		&self,
+		pending: PendingSubscriptionSink,
+		account: AccountId,
+		source: Option<EventSource>,
+	) {
+		self.subscribe(pending, source.unwrap_or_default(), move |event| {
+			event.accounts.contains(&account)
+		});
+	}
+}
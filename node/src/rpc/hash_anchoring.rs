@@ -0,0 +1,82 @@
+//! RPC wrapper of the runtime's
+//! [`HashAnchoringApi`](pallet_hash_anchoring::runtime_api::HashAnchoringApi).
+
+use std::sync::Arc;
+
+use jsonrpsee::{core::RpcResult, proc_macros::rpc, types::error::ErrorObject};
+use logion_node_runtime::{opaque::Block, AccountId, BlockNumber, Hash, LocId};
+use pallet_hash_anchoring::{runtime_api::HashAnchoringApi as HashAnchoringRuntimeApi, Anchor};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::traits::Block as BlockT;
+
+/// Hash anchoring RPC methods.
+#[rpc(server)]
+pub trait HashAnchoringApi<BlockHash> {
+	/// Checks that `proof` proves the inclusion of `document` in the Merkle tree with given
+	/// `root`, anchored in LOC `loc_id`, using the state of block `at` (best block by default).
+	/// Returns the anchor (LOC, block and timestamp) of the root, `null` if the root is not
+	/// anchored in the LOC or the proof invalid.
+	#[method(name = "logion_verifyAnchoredHash")]
+	fn verify_anchored_hash(
+		&self,
+		loc_id: LocId,
+		root: Hash,
+		document: Hash,
+		proof: Vec<Hash>,
+		at: Option<BlockHash>,
+	) -> RpcResult<Option<Anchor<AccountId, LocId, BlockNumber>>>;
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+	/// The runtime API call failed.
+	RuntimeError,
+}
+
+impl From<Error> for i32 {
+	fn from(e: Error) -> i32 {
+		match e {
+			Error::RuntimeError => 1,
+		}
+	}
+}
+
+/// Provides RPC methods to verify anchored document hashes.
+pub struct HashAnchoring<C> {
+	client: Arc<C>,
+}
+
+impl<C> HashAnchoring<C> {
+	/// Creates a new instance of the HashAnchoring RPC helper.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client }
+	}
+}
+
+impl<C> HashAnchoringApiServer<<Block as BlockT>::Hash> for HashAnchoring<C>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: HashAnchoringRuntimeApi<Block, AccountId, LocId, Hash, BlockNumber>,
+{
+	fn verify_anchored_hash(
+		&self,
+		loc_id: LocId,
+		root: Hash,
+		document: Hash,
+		proof: Vec<Hash>,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Option<Anchor<AccountId, LocId, BlockNumber>>> {
+		let at_hash = at.unwrap_or_else(|| self.client.info().best_hash);
+		self.client
+			.runtime_api()
+			.verify_inclusion(at_hash, loc_id, root, document, proof)
+			.map_err(|e| {
+				ErrorObject::owned(
+					Error::RuntimeError.into(),
+					"Unable to verify inclusion.",
+					Some(e.to_string()),
+				)
+			})
+	}
+}
@@ -0,0 +1,49 @@
+//! RPC access to the events indexed with `--logion-indexer`.
+
+use std::sync::Arc;
+
+use jsonrpsee::{core::RpcResult, proc_macros::rpc, types::error::ErrorObject};
+
+use crate::indexer::{EventPage, EventQuery, IndexerDb};
+
+/// Indexer RPC methods.
+#[rpc(server)]
+pub trait IndexerApi {
+	/// Returns a page of the indexed events matching `query`, in chain order.
+	#[method(name = "logion_queryEvents")]
+	fn query_events(&self, query: EventQuery) -> RpcResult<EventPage>;
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+	/// The indexer database could not be read.
+	DatabaseError,
+}
+
+impl From<Error> for i32 {
+	fn from(e: Error) -> i32 {
+		match e {
+			Error::DatabaseError => 1,
+		}
+	}
+}
+
+/// Provides RPC methods to query the indexed events.
+pub struct Indexer {
+	db: Arc<IndexerDb>,
+}
+
+impl Indexer {
+	/// Creates a new instance of the Indexer RPC helper.
+	pub fn new(db: Arc<IndexerDb>) -> Self {
+		Self { db }
+	}
+}
+
+impl IndexerApiServer for Indexer {
+	fn query_events(&self, query: EventQuery) -> RpcResult<EventPage> {
+		self.db.query(&query).map_err(|e| {
+			ErrorObject::owned(Error::DatabaseError.into(), "Unable to query events.", Some(e))
+		})
+	}
+}
@@ -0,0 +1,53 @@
+//! RPC access to the LOC index, see [`crate::loc_index`].
+
+use jsonrpsee::{core::RpcResult, proc_macros::rpc, types::error::ErrorObject};
+use logion_node_runtime::Hash;
+use sp_core::offchain::OffchainStorage;
+
+use crate::loc_index::Item;
+
+/// LOC index RPC methods.
+#[rpc(server)]
+pub trait LocIndexApi {
+	/// Returns the items of closed, non-void LOCs `hash` was found in, an empty list if the hash
+	/// is not indexed. Requires the node to run with `--enable-offchain-indexing true`.
+	#[method(name = "logion_findByHash")]
+	fn find_by_hash(&self, hash: Hash) -> RpcResult<Vec<Item>>;
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+	/// An index entry could not be decoded.
+	DecodeError,
+}
+
+impl From<Error> for i32 {
+	fn from(e: Error) -> i32 {
+		match e {
+			Error::DecodeError => 1,
+		}
+	}
+}
+
+/// Provides RPC methods to query the LOC index.
+pub struct LocIndex<S> {
+	offchain_db: S,
+}
+
+impl<S> LocIndex<S> {
+	/// Creates a new instance of the LocIndex RPC helper.
+	pub fn new(offchain_db: S) -> Self {
+		Self { offchain_db }
+	}
+}
+
+impl<S> LocIndexApiServer for LocIndex<S>
+where
+	S: OffchainStorage + 'static,
+{
+	fn find_by_hash(&self, hash: Hash) -> RpcResult<Vec<Item>> {
+		crate::loc_index::find_by_hash(&self.offchain_db, hash).map_err(|e| {
+			ErrorObject::owned(Error::DecodeError.into(), "Unable to decode index entry.", Some(e))
+		})
+	}
+}
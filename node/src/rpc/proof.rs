@@ -0,0 +1,75 @@
+//! RPC access to the [proof bundles](crate::proof::ProofBundle) of LOCs and collection items.
+
+use std::sync::Arc;
+
+use jsonrpsee::{core::RpcResult, proc_macros::rpc, types::error::ErrorObject};
+use logion_node_runtime::opaque::Block;
+use sc_client_api::{BlockBackend, ProofProvider};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_consensus_grandpa::{GrandpaApi, SetId};
+
+use crate::proof::{build_proof_bundle, ProofBundle, ProofTarget};
+
+/// Proof of existence RPC methods.
+#[rpc(server)]
+pub trait ProofApi {
+	/// Returns a bundle proving that `target` is part of the state of the last block with a
+	/// GRANDPA justification. The bundle chains to authority set `trusted_set_id`, the genesis one
+	/// by default, and can be checked offline with `logion-node verify-proof`.
+	#[method(name = "logion_exportProof")]
+	fn export_proof(
+		&self,
+		target: ProofTarget,
+		trusted_set_id: Option<SetId>,
+	) -> RpcResult<ProofBundle>;
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+	/// The proof could not be built.
+	ProofError,
+}
+
+impl From<Error> for i32 {
+	fn from(e: Error) -> i32 {
+		match e {
+			Error::ProofError => 1,
+		}
+	}
+}
+
+/// Provides RPC methods to export proof bundles.
+pub struct Proof<C> {
+	client: Arc<C>,
+}
+
+impl<C> Proof<C> {
+	/// Creates a new instance of the Proof RPC helper.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client }
+	}
+}
+
+impl<C> ProofApiServer for Proof<C>
+where
+	C: HeaderBackend<Block>
+		+ BlockBackend<Block>
+		+ ProofProvider<Block>
+		+ ProvideRuntimeApi<Block>
+		+ Send
+		+ Sync
+		+ 'static,
+	C::Api: GrandpaApi<Block>,
+{
+	fn export_proof(
+		&self,
+		target: ProofTarget,
+		trusted_set_id: Option<SetId>,
+	) -> RpcResult<ProofBundle> {
+		build_proof_bundle(self.client.as_ref(), target, trusted_set_id.unwrap_or_default())
+			.map_err(|e| {
+				ErrorObject::owned(Error::ProofError.into(), "Unable to build proof.", Some(e))
+			})
+	}
+}
@@ -0,0 +1,110 @@
+//! RPC wrapper of the runtime's
+//! [`RewardAccountingApi`](pallet_reward_accounting::runtime_api::RewardAccountingApi).
+
+use std::sync::Arc;
+
+use jsonrpsee::{core::RpcResult, proc_macros::rpc, types::error::ErrorObject};
+use logion_node_runtime::{opaque::Block, AccountId, Balance, BlockNumber};
+use pallet_reward_accounting::{
+	runtime_api::RewardAccountingApi as RewardAccountingRuntimeApi, Inflows,
+};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_rpc::number::NumberOrHex;
+
+/// Reward accounting RPC methods.
+#[rpc(server)]
+pub trait RewardAccountingApi {
+	/// Returns the amounts received by the treasuries and legal officers, per category, from
+	/// block `from` to block `to` (both included). The state of block `from - 1` and `to` must
+	/// be available.
+	#[method(name = "logion_rewardInflows")]
+	fn inflows(
+		&self,
+		from: BlockNumber,
+		to: BlockNumber,
+	) -> RpcResult<Inflows<AccountId, NumberOrHex>>;
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+	/// The block range is invalid or a block is unknown.
+	InvalidRange,
+	/// The runtime API call failed.
+	RuntimeError,
+}
+
+impl From<Error> for i32 {
+	fn from(e: Error) -> i32 {
+		match e {
+			Error::RuntimeError => 1,
+			Error::InvalidRange => 2,
+		}
+	}
+}
+
+/// Provides RPC methods to query reward inflows.
+pub struct RewardAccounting<C> {
+	client: Arc<C>,
+}
+
+impl<C> RewardAccounting<C> {
+	/// Creates a new instance of the RewardAccounting RPC helper.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client }
+	}
+}
+
+impl<C> RewardAccounting<C>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: RewardAccountingRuntimeApi<Block, AccountId, Balance>,
+{
+	fn cumulative_inflows_at(&self, number: BlockNumber) -> RpcResult<Inflows<AccountId, Balance>> {
+		let hash = self
+			.client
+			.hash(number)
+			.ok()
+			.flatten()
+			.ok_or_else(|| {
+				ErrorObject::owned(
+					Error::InvalidRange.into(),
+					format!("Unknown block {}", number),
+					None::<()>,
+				)
+			})?;
+		self.client.runtime_api().inflows(hash).map_err(|e| {
+			ErrorObject::owned(
+				Error::RuntimeError.into(),
+				"Unable to query inflows.",
+				Some(e.to_string()),
+			)
+		})
+	}
+}
+
+impl<C> RewardAccountingApiServer for RewardAccounting<C>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: RewardAccountingRuntimeApi<Block, AccountId, Balance>,
+{
+	fn inflows(
+		&self,
+		from: BlockNumber,
+		to: BlockNumber,
+	) -> RpcResult<Inflows<AccountId, NumberOrHex>> {
+		if from > to {
+			return Err(ErrorObject::owned(
+				Error::InvalidRange.into(),
+				"`from` must not be greater than `to`",
+				None::<()>,
+			))
+		}
+		let later = self.cumulative_inflows_at(to)?;
+		let earlier = match from {
+			0 => Inflows::default(),
+			from => self.cumulative_inflows_at(from - 1)?,
+		};
+		Ok(later.since(&earlier).map_balances(|balance| NumberOrHex::Hex(balance.into())))
+	}
+}
@@ -0,0 +1,434 @@
+//! Conversion between SCALE-encoded values and JSON, using the type information of a runtime's
+//! metadata.
+//!
+//! Structs are represented as objects (or arrays if their fields are not named), enums as their
+//! variant's name or as an object with the variant's name as single key, byte sequences as hex
+//! strings and accounts as SS58 addresses. Numbers may also be given as strings when encoding.
+
+use std::str::FromStr;
+
+use codec::{Compact, Decode, Encode};
+use frame_metadata::{v14::{RuntimeMetadataV14, StorageEntryType}, RuntimeMetadata};
+use logion_node_runtime::{AccountId, Runtime};
+use scale_info::{form::PortableForm, Field, PortableRegistry, TypeDef, TypeDefPrimitive};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use sp_core::crypto::Ss58Codec;
+
+/// Returns the metadata of the node's runtime.
+pub(crate) fn native_metadata() -> RuntimeMetadataV14 {
+	match Runtime::metadata().1 {
+		RuntimeMetadata::V14(metadata) => metadata,
+		_ => unreachable!("The runtime provides V14 metadata"),
+	}
+}
+
+/// Returns the type of parameter `name` (`Address`, `Call`, `Signature` or `Extra`) of the
+/// runtime's `UncheckedExtrinsic`.
+pub(crate) fn extrinsic_param(metadata: &RuntimeMetadataV14, name: &str) -> Option<u32> {
+	metadata
+		.types
+		.resolve(metadata.extrinsic.ty.id)?
+		.type_params
+		.iter()
+		.find(|param| param.name == name)?
+		.ty
+		.as_ref()
+		.map(|ty| ty.id)
+}
+
+/// Returns the type of plain storage item `pallet.item`.
+pub(crate) fn storage_type(metadata: &RuntimeMetadataV14, pallet: &str, item: &str) -> Option<u32> {
+	let storage = metadata.pallets.iter().find(|p| p.name == pallet)?.storage.as_ref()?;
+	match &storage.entries.iter().find(|entry| entry.name == item)?.ty {
+		StorageEntryType::Plain(ty) => Some(ty.id),
+		StorageEntryType::Map { .. } => None,
+	}
+}
+
+fn is_account(types: &PortableRegistry, id: u32) -> bool {
+	types
+		.resolve(id)
+		.map_or(false, |ty| ty.path.segments.last().map(String::as_str) == Some("AccountId32"))
+}
+
+fn is_byte(types: &PortableRegistry, id: u32) -> bool {
+	matches!(
+		types.resolve(id).map(|ty| &ty.type_def),
+		Some(TypeDef::Primitive(TypeDefPrimitive::U8))
+	)
+}
+
+/// Decodes a value of type `id` as JSON.
+pub(crate) fn decode_value(
+	types: &PortableRegistry,
+	id: u32,
+	input: &mut &[u8],
+) -> Result<Value, codec::Error> {
+	let ty = types.resolve(id).ok_or("Unknown type")?;
+	if is_account(types, id) {
+		let account = AccountId::new(Decode::decode(input)?);
+		return Ok(Value::String(account.to_ss58check()))
+	}
+	match &ty.type_def {
+		TypeDef::Composite(composite) => decode_fields(types, &composite.fields, input),
+		TypeDef::Variant(variant) => {
+			let index = u8::decode(input)?;
+			let variant = variant
+				.variants
+				.iter()
+				.find(|variant| variant.index == index)
+				.ok_or("Unknown variant")?;
+			if variant.fields.is_empty() {
+				Ok(Value::String(variant.name.clone()))
+			} else {
+				let fields = decode_fields(types, &variant.fields, input)?;
+				Ok(Value::Object(Map::from_iter([(variant.name.clone(), fields)])))
+			}
+		},
+		TypeDef::Sequence(sequence) => {
+			let len = Compact::<u32>::decode(input)?.0;
+			decode_items(types, sequence.type_param.id, len, input)
+		},
+		TypeDef::Array(array) => decode_items(types, array.type_param.id, array.len, input),
+		TypeDef::Tuple(tuple) => tuple
+			.fields
+			.iter()
+			.map(|field| decode_value(types, field.id, input))
+			.collect::<Result<_, _>>()
+			.map(Value::Array),
+		TypeDef::Primitive(primitive) => decode_primitive(primitive, input),
+		TypeDef::Compact(_) => Ok(number(Compact::<u128>::decode(input)?.0)),
+		TypeDef::BitSequence(_) => Err("Bit sequences are not supported".into()),
+	}
+}
+
+fn decode_fields(
+	types: &PortableRegistry,
+	fields: &[Field<PortableForm>],
+	input: &mut &[u8],
+) -> Result<Value, codec::Error> {
+	match fields {
+		[] => Ok(Value::Null),
+		[field] if field.name.is_none() => decode_value(types, field.ty.id, input),
+		_ if fields.iter().all(|field| field.name.is_some()) => fields
+			.iter()
+			.map(|field| {
+				let name = field.name.clone().unwrap_or_default();
+				Ok::<_, codec::Error>((name, decode_value(types, field.ty.id, input)?))
+			})
+			.collect::<Result<_, _>>()
+			.map(Value::Object),
+		_ => fields
+			.iter()
+			.map(|field| decode_value(types, field.ty.id, input))
+			.collect::<Result<_, _>>()
+			.map(Value::Array),
+	}
+}
+
+fn decode_items(
+	types: &PortableRegistry,
+	item: u32,
+	len: u32,
+	input: &mut &[u8],
+) -> Result<Value, codec::Error> {
+	if is_byte(types, item) {
+		let len = len as usize;
+		if input.len() < len {
+			return Err("Not enough data to fill buffer".into())
+		}
+		let (bytes, rest) = input.split_at(len);
+		*input = rest;
+		Ok(Value::String(sp_core::bytes::to_hex(bytes, false)))
+	} else {
+		(0..len)
+			.map(|_| decode_value(types, item, input))
+			.collect::<Result<_, _>>()
+			.map(Value::Array)
+	}
+}
+
+fn decode_primitive(
+	primitive: &TypeDefPrimitive,
+	input: &mut &[u8],
+) -> Result<Value, codec::Error> {
+	Ok(match primitive {
+		TypeDefPrimitive::Bool => Value::Bool(bool::decode(input)?),
+		TypeDefPrimitive::Char => Value::String(
+			char::from_u32(u32::decode(input)?).ok_or("Invalid char")?.to_string(),
+		),
+		TypeDefPrimitive::Str => Value::String(String::decode(input)?),
+		TypeDefPrimitive::U8 => number(u8::decode(input)?),
+		TypeDefPrimitive::U16 => number(u16::decode(input)?),
+		TypeDefPrimitive::U32 => number(u32::decode(input)?),
+		TypeDefPrimitive::U64 => number(u64::decode(input)?),
+		TypeDefPrimitive::U128 => number(u128::decode(input)?),
+		TypeDefPrimitive::I8 => number(i8::decode(input)?),
+		TypeDefPrimitive::I16 => number(i16::decode(input)?),
+		TypeDefPrimitive::I32 => number(i32::decode(input)?),
+		TypeDefPrimitive::I64 => number(i64::decode(input)?),
+		TypeDefPrimitive::I128 => number(i128::decode(input)?),
+		TypeDefPrimitive::U256 | TypeDefPrimitive::I256 =>
+			Value::String(sp_core::bytes::to_hex(&<[u8; 32]>::decode(input)?, false)),
+	})
+}
+
+fn number<T: Serialize + ToString>(n: T) -> Value {
+	serde_json::to_value(&n).unwrap_or_else(|_| Value::String(n.to_string()))
+}
+
+/// Encodes the JSON `value` of type `id`.
+pub(crate) fn encode_value(
+	types: &PortableRegistry,
+	id: u32,
+	value: &Value,
+	output: &mut Vec<u8>,
+) -> Result<(), String> {
+	let ty = types.resolve(id).ok_or_else(|| format!("Unknown type {}", id))?;
+	if is_account(types, id) {
+		let account = value
+			.as_str()
+			.and_then(|account| AccountId::from_str(account).ok())
+			.ok_or_else(|| format!("Invalid account {}", value))?;
+		account.encode_to(output);
+		return Ok(())
+	}
+	match &ty.type_def {
+		TypeDef::Composite(composite) => encode_fields(types, &composite.fields, value, output),
+		TypeDef::Variant(variant) => {
+			let (name, fields) = match value {
+				Value::String(name) => (name, None),
+				Value::Object(object) if object.len() == 1 => {
+					let (name, fields) = object.iter().next().expect("Object has one entry; qed");
+					(name, Some(fields))
+				},
+				_ => return Err(format!("Expected a variant name or object, got {}", value)),
+			};
+			let variant = variant
+				.variants
+				.iter()
+				.find(|variant| &variant.name == name)
+				.ok_or_else(|| format!("Unknown variant {}", name))?;
+			output.push(variant.index);
+			encode_fields(types, &variant.fields, fields.unwrap_or(&Value::Null), output)
+				.map_err(|e| format!("{}: {}", name, e))
+		},
+		TypeDef::Sequence(sequence) => {
+			let item = sequence.type_param.id;
+			let len = match value {
+				Value::String(_) if is_byte(types, item) => hex(value)?.len(),
+				_ => value.as_array().map_or(0, Vec::len),
+			};
+			Compact(len as u32).encode_to(output);
+			encode_items(types, item, value, output)
+		},
+		TypeDef::Array(array) => {
+			let start = output.len();
+			encode_items(types, array.type_param.id, value, output)?;
+			let item_len = if is_byte(types, array.type_param.id) {
+				output.len() - start
+			} else {
+				value.as_array().map_or(0, Vec::len)
+			};
+			if item_len == array.len as usize {
+				Ok(())
+			} else {
+				Err(format!("Expected {} items, got {}", array.len, item_len))
+			}
+		},
+		TypeDef::Tuple(tuple) => {
+			let items = match value {
+				Value::Null if tuple.fields.is_empty() => &[][..],
+				Value::Array(items) if items.len() == tuple.fields.len() => &items[..],
+				_ => return Err(format!("Expected {} items, got {}", tuple.fields.len(), value)),
+			};
+			tuple
+				.fields
+				.iter()
+				.zip(items)
+				.try_for_each(|(field, item)| encode_value(types, field.id, item, output))
+		},
+		TypeDef::Primitive(primitive) => encode_primitive(primitive, value, output),
+		TypeDef::Compact(_) => {
+			Compact(parse_number::<u128>(value)?).encode_to(output);
+			Ok(())
+		},
+		TypeDef::BitSequence(_) => Err("Bit sequences are not supported".into()),
+	}
+}
+
+fn encode_fields(
+	types: &PortableRegistry,
+	fields: &[Field<PortableForm>],
+	value: &Value,
+	output: &mut Vec<u8>,
+) -> Result<(), String> {
+	match fields {
+		[] => Ok(()),
+		[field] if field.name.is_none() => encode_value(types, field.ty.id, value, output),
+		_ if fields.iter().all(|field| field.name.is_some()) => {
+			let object =
+				value.as_object().ok_or_else(|| format!("Expected an object, got {}", value))?;
+			fields.iter().try_for_each(|field| {
+				let name = field.name.as_deref().unwrap_or_default();
+				let value = object.get(name).ok_or_else(|| format!("Missing field {}", name))?;
+				encode_value(types, field.ty.id, value, output)
+					.map_err(|e| format!("{}: {}", name, e))
+			})
+		},
+		_ => {
+			let items = value
+				.as_array()
+				.filter(|items| items.len() == fields.len())
+				.ok_or_else(|| format!("Expected {} items, got {}", fields.len(), value))?;
+			fields
+				.iter()
+				.zip(items)
+				.try_for_each(|(field, item)| encode_value(types, field.ty.id, item, output))
+		},
+	}
+}
+
+/// Encodes the items of a sequence or array, without length.
+fn encode_items(
+	types: &PortableRegistry,
+	item: u32,
+	value: &Value,
+	output: &mut Vec<u8>,
+) -> Result<(), String> {
+	match value {
+		Value::String(_) if is_byte(types, item) => {
+			output.extend(hex(value)?);
+			Ok(())
+		},
+		Value::Array(items) =>
+			items.iter().try_for_each(|value| encode_value(types, item, value, output)),
+		_ => Err(format!("Expected an array, got {}", value)),
+	}
+}
+
+fn encode_primitive(
+	primitive: &TypeDefPrimitive,
+	value: &Value,
+	output: &mut Vec<u8>,
+) -> Result<(), String> {
+	match primitive {
+		TypeDefPrimitive::Bool => value
+			.as_bool()
+			.ok_or_else(|| format!("Expected a boolean, got {}", value))?
+			.encode_to(output),
+		TypeDefPrimitive::Char => value
+			.as_str()
+			.and_then(|s| s.chars().next())
+			.map(|c| c as u32)
+			.ok_or_else(|| format!("Expected a character, got {}", value))?
+			.encode_to(output),
+		TypeDefPrimitive::Str => value
+			.as_str()
+			.ok_or_else(|| format!("Expected a string, got {}", value))?
+			.encode_to(output),
+		TypeDefPrimitive::U8 => parse_number::<u8>(value)?.encode_to(output),
+		TypeDefPrimitive::U16 => parse_number::<u16>(value)?.encode_to(output),
+		TypeDefPrimitive::U32 => parse_number::<u32>(value)?.encode_to(output),
+		TypeDefPrimitive::U64 => parse_number::<u64>(value)?.encode_to(output),
+		TypeDefPrimitive::U128 => parse_number::<u128>(value)?.encode_to(output),
+		TypeDefPrimitive::I8 => parse_number::<i8>(value)?.encode_to(output),
+		TypeDefPrimitive::I16 => parse_number::<i16>(value)?.encode_to(output),
+		TypeDefPrimitive::I32 => parse_number::<i32>(value)?.encode_to(output),
+		TypeDefPrimitive::I64 => parse_number::<i64>(value)?.encode_to(output),
+		TypeDefPrimitive::I128 => parse_number::<i128>(value)?.encode_to(output),
+		TypeDefPrimitive::U256 | TypeDefPrimitive::I256 => {
+			let bytes = hex(value)?;
+			if bytes.len() != 32 {
+				return Err(format!("Expected 32 bytes, got {}", value))
+			}
+			output.extend(bytes);
+		},
+	}
+	Ok(())
+}
+
+fn parse_number<T: FromStr>(value: &Value) -> Result<T, String> {
+	let number = match value {
+		Value::Number(number) => number.to_string(),
+		Value::String(number) => number.clone(),
+		_ => String::new(),
+	};
+	number.parse().map_err(|_| format!("Invalid number {}", value))
+}
+
+fn hex(value: &Value) -> Result<Vec<u8>, String> {
+	value
+		.as_str()
+		.and_then(|hex| sp_core::bytes::from_hex(hex).ok())
+		.ok_or_else(|| format!("Expected a hex string, got {}", value))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use logion_node_runtime::{BalancesCall, RuntimeCall, SystemCall};
+	use serde_json::json;
+	use sp_keyring::Sr25519Keyring;
+
+	fn call_type(metadata: &RuntimeMetadataV14) -> u32 {
+		extrinsic_param(metadata, "Call").unwrap()
+	}
+
+	#[test]
+	fn calls_round_trip() {
+		let metadata = native_metadata();
+		let ty = call_type(&metadata);
+		let calls = [
+			RuntimeCall::Balances(BalancesCall::transfer_keep_alive {
+				dest: Sr25519Keyring::Bob.to_account_id().into(),
+				value: 1_000_000_000_000_000_000_000,
+			}),
+			RuntimeCall::System(SystemCall::remark { remark: vec![1, 2, 3] }),
+		];
+
+		for call in calls {
+			let encoded = call.encode();
+			let json = decode_value(&metadata.types, ty, &mut &encoded[..]).unwrap();
+			let mut reencoded = Vec::new();
+			encode_value(&metadata.types, ty, &json, &mut reencoded).unwrap();
+			assert_eq!(reencoded, encoded, "{}", json);
+		}
+	}
+
+	#[test]
+	fn numbers_may_be_strings() {
+		let metadata = native_metadata();
+		let call = json!({
+			"Balances": {
+				"transfer_keep_alive": {
+					"dest": { "Id": Sr25519Keyring::Bob.to_account_id().to_ss58check() },
+					"value": "1000",
+				}
+			}
+		});
+
+		let mut encoded = Vec::new();
+		encode_value(&metadata.types, call_type(&metadata), &call, &mut encoded).unwrap();
+
+		assert_eq!(
+			RuntimeCall::decode(&mut &encoded[..]).unwrap(),
+			RuntimeCall::Balances(BalancesCall::transfer_keep_alive {
+				dest: Sr25519Keyring::Bob.to_account_id().into(),
+				value: 1_000,
+			})
+		);
+	}
+
+	#[test]
+	fn unknown_variant_is_rejected() {
+		let metadata = native_metadata();
+		let call = json!({ "LogionLoc": { "unknown_call": {} } });
+
+		let error = encode_value(&metadata.types, call_type(&metadata), &call, &mut Vec::new())
+			.unwrap_err();
+
+		assert_eq!(error, "LogionLoc: Unknown variant unknown_call");
+	}
+}
@@ -0,0 +1,160 @@
+//! Development block production, replacing Aura and GRANDPA with manual sealing.
+//!
+//! Blocks still carry an Aura pre-digest and a timestamp, so that the runtime is unchanged. Each
+//! block's timestamp is the current time, or one slot after its parent's if later: several blocks
+//! may be produced within a slot's duration, the chain's clock then running ahead of the wall
+//! clock. The timestamp being derived from the parent block, this survives node restarts.
+//!
+//! Sealing is only available on development chains.
+
+use std::{str::FromStr, sync::Arc, time::Duration};
+
+use codec::Decode;
+use futures::{channel::mpsc, stream, stream::BoxStream, StreamExt};
+use logion_node_runtime::{opaque::Block, Hash, Runtime};
+use sc_client_api::{Backend, StorageProvider};
+use sc_consensus_manual_seal::EngineCommand;
+use sc_transaction_pool_api::TransactionPool;
+use sp_consensus_aura::{inherents::InherentDataProvider as SlotProvider, SlotDuration};
+use sp_core::storage::StorageKey;
+use sp_timestamp::{InherentDataProvider as TimestampProvider, Timestamp};
+
+/// How blocks are sealed in development mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sealing {
+	/// A block is sealed, and finalized, as soon as a transaction enters the pool.
+	Instant,
+	/// Blocks are only sealed with the `engine_createBlock` RPC.
+	Manual,
+	/// A block is sealed, and finalized, every given number of milliseconds.
+	Interval(u64),
+}
+
+impl FromStr for Sealing {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"instant" => Ok(Self::Instant),
+			"manual" => Ok(Self::Manual),
+			_ => match s.strip_prefix("interval=") {
+				Some(millis) => millis
+					.parse()
+					.ok()
+					.filter(|millis| *millis > 0)
+					.map(Self::Interval)
+					.ok_or_else(|| format!("Invalid sealing interval: {}", millis)),
+				None => Err(format!(
+					"Unknown sealing mode {}, expected instant, manual or interval=<ms>",
+					s
+				)),
+			},
+		}
+	}
+}
+
+fn seal_new_block(create_empty: bool) -> EngineCommand<Hash> {
+	EngineCommand::SealNewBlock { create_empty, finalize: true, parent_hash: None, sender: None }
+}
+
+/// Returns the stream of commands driving the sealing task: the commands received through the
+/// `engine_*` RPCs, plus the ones triggered by `sealing`.
+pub fn commands_stream<P>(
+	sealing: Sealing,
+	rpc_commands: mpsc::Receiver<EngineCommand<Hash>>,
+	pool: Arc<P>,
+) -> BoxStream<'static, EngineCommand<Hash>>
+where
+	P: TransactionPool + 'static,
+{
+	match sealing {
+		Sealing::Manual => rpc_commands.boxed(),
+		Sealing::Instant => {
+			let transactions = pool.import_notification_stream().map(|_| seal_new_block(false));
+			stream::select(rpc_commands, transactions).boxed()
+		},
+		Sealing::Interval(millis) => {
+			let ticks = stream::unfold((), move |()| async move {
+				tokio::time::sleep(Duration::from_millis(millis)).await;
+				Some((seal_new_block(true), ()))
+			});
+			stream::select(rpc_commands, ticks).boxed()
+		},
+	}
+}
+
+/// Returns the inherent data providers of the block built on `parent_hash`.
+pub fn next_block<C, B>(
+	client: &C,
+	parent_hash: Hash,
+	slot_duration: SlotDuration,
+) -> Result<(SlotProvider, TimestampProvider), String>
+where
+	C: StorageProvider<Block, B>,
+	B: Backend<Block>,
+{
+	let key = StorageKey(pallet_timestamp::Now::<Runtime>::hashed_key().to_vec());
+	let parent_timestamp = client
+		.storage(parent_hash, &key)
+		.map_err(|e| format!("Unable to read the timestamp of {}: {}", parent_hash, e))?
+		.map(|data| u64::decode(&mut &data.0[..]))
+		.transpose()
+		.map_err(|e| format!("Invalid timestamp in {}: {}", parent_hash, e))?
+		.unwrap_or_default();
+	let timestamp =
+		next_timestamp(parent_timestamp, Timestamp::current().as_millis(), slot_duration);
+	let timestamp = TimestampProvider::new(Timestamp::new(timestamp));
+	let slot = SlotProvider::from_timestamp_and_slot_duration(*timestamp, slot_duration);
+	Ok((slot, timestamp))
+}
+
+/// The timestamp of the block following one with `parent_timestamp` (zero at genesis), at
+/// least one slot later so that the block's slot is after its parent's.
+fn next_timestamp(parent_timestamp: u64, now: u64, slot_duration: SlotDuration) -> u64 {
+	if parent_timestamp == 0 {
+		now
+	} else {
+		now.max(parent_timestamp.saturating_add(slot_duration.as_millis()))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const SLOT_DURATION: u64 = 6_000;
+
+	fn slot_duration() -> SlotDuration {
+		SlotDuration::from_millis(SLOT_DURATION)
+	}
+
+	#[test]
+	fn parses_sealing_modes() {
+		assert_eq!("instant".parse(), Ok(Sealing::Instant));
+		assert_eq!("interval=500".parse(), Ok(Sealing::Interval(500)));
+		assert!("interval=0".parse::<Sealing>().is_err());
+		assert!("slow".parse::<Sealing>().is_err());
+	}
+
+	#[test]
+	fn follows_wall_clock_unless_ahead() {
+		let now = 1_700_000_000_000;
+
+		assert_eq!(next_timestamp(0, now, slot_duration()), now);
+		assert_eq!(next_timestamp(now - 60_000, now, slot_duration()), now);
+		assert_eq!(next_timestamp(now, now, slot_duration()), now + SLOT_DURATION);
+	}
+
+	#[test]
+	fn keeps_slots_increasing_after_restart() {
+		// Blocks sealed in a burst ran the chain's clock ten slots ahead, then the node restarted.
+		let now = 1_700_000_000_000;
+		let parent_timestamp = now + 10 * SLOT_DURATION;
+		let parent_slot = parent_timestamp / SLOT_DURATION;
+
+		let timestamp = next_timestamp(parent_timestamp, now, slot_duration());
+
+		assert_eq!(timestamp, parent_timestamp + SLOT_DURATION);
+		assert_eq!(timestamp / SLOT_DURATION, parent_slot + 1);
+	}
+}
@@ -1,52 +1,53 @@
 //! Service and ServiceFactory implementation. Specialized wrapper over substrate service.
 
+use crate::{cli::NodeOptions, consensus::ConsensusParams};
 use futures::FutureExt;
 use logion_node_runtime::{opaque::Block, RuntimeApi};
 use sc_client_api::{Backend, BlockBackend};
-use sc_consensus_aura::{ImportQueueParams, SlotProportion, StartAuraParams, CompatibilityMode};
+use sc_consensus_aura::{ImportQueueParams, StartAuraParams, CompatibilityMode};
 use sc_consensus_grandpa::SharedVoterState;
+use sc_network::NetworkStateInfo;
 use sc_service::{error::Error as ServiceError, Configuration, TaskManager, WarpSyncParams};
 use sc_telemetry::{Telemetry, TelemetryWorker};
 use sc_transaction_pool_api::OffchainTransactionPoolFactory;
 use sp_consensus_aura::sr25519::AuthorityPair as AuraPair;
+use sp_keystore::KeystorePtr;
 use sp_runtime::traits::NumberFor;
-use std::{sync::Arc, time::Duration};
+use std::sync::Arc;
 
 pub(crate) type FullClient = sc_service::TFullClient<
 	Block,
 	RuntimeApi,
 	sc_executor::WasmExecutor<sp_io::SubstrateHostFunctions>,
 >;
-type FullBackend = sc_service::TFullBackend<Block>;
+pub(crate) type FullBackend = sc_service::TFullBackend<Block>;
 type FullSelectChain = sc_consensus::LongestChain<FullBackend, Block>;
 
-/// The minimum period of blocks on which justifications will be
-/// imported and generated.
-const GRANDPA_JUSTIFICATION_PERIOD: u32 = 512;
+/// The components built by [`new_partial`].
+pub type Service = sc_service::PartialComponents<
+	FullClient,
+	FullBackend,
+	FullSelectChain,
+	sc_consensus::DefaultImportQueue<Block>,
+	sc_transaction_pool::FullPool<Block, FullClient>,
+	(
+		sc_consensus_grandpa::GrandpaBlockImport<FullBackend, Block, FullClient, FullSelectChain>,
+		sc_consensus_grandpa::LinkHalf<Block, FullClient, FullSelectChain>,
+		Option<Telemetry>,
+	),
+>;
+
+/// Builds the components shared by the node and the subcommands, with the GRANDPA justification
+/// period of the chain specification.
+pub fn new_partial(config: &Configuration) -> Result<Service, ServiceError> {
+	let consensus = ConsensusParams::from_chain_spec(&*config.chain_spec);
+	new_partial_with_justification_period(config, consensus.grandpa_justification_period)
+}
 
-#[allow(clippy::type_complexity)]
-pub fn new_partial(
+fn new_partial_with_justification_period(
 	config: &Configuration,
-) -> Result<
-	sc_service::PartialComponents<
-		FullClient,
-		FullBackend,
-		FullSelectChain,
-		sc_consensus::DefaultImportQueue<Block>,
-		sc_transaction_pool::FullPool<Block, FullClient>,
-		(
-			sc_consensus_grandpa::GrandpaBlockImport<
-				FullBackend,
-				Block,
-				FullClient,
-				FullSelectChain,
-			>,
-			sc_consensus_grandpa::LinkHalf<Block, FullClient, FullSelectChain>,
-			Option<Telemetry>,
-		),
-	>,
-	ServiceError,
-> {
+	justification_period: u32,
+) -> Result<Service, ServiceError> {
 	let telemetry = config
 		.telemetry_endpoints
 		.clone()
@@ -84,7 +85,7 @@ pub fn new_partial(
 
 	let (grandpa_block_import, grandpa_link) = sc_consensus_grandpa::block_import(
 		client.clone(),
-		GRANDPA_JUSTIFICATION_PERIOD,
+		justification_period,
 		&client,
 		select_chain.clone(),
 		telemetry.as_ref().map(|x| x.handle()),
@@ -142,7 +143,41 @@ fn compatibility_mode(config: &Configuration) -> CompatibilityMode<NumberFor<Blo
 }
 
 /// Builds a new service for a full client.
-pub fn new_full(config: Configuration) -> Result<TaskManager, ServiceError> {
+pub fn new_full(
+	mut config: Configuration,
+	options: NodeOptions,
+) -> Result<TaskManager, ServiceError> {
+	if options.permissioned {
+		config.network.default_peers_set.non_reserved_mode =
+			sc_network::config::NonReservedPeerMode::Deny;
+		// Until the legal officer nodes are read from a synced state, the boot nodes are the only
+		// peers a fresh node may sync from.
+		let boot_nodes = config.network.boot_nodes.clone();
+		let reserved_nodes = &mut config.network.default_peers_set.reserved_nodes;
+		for boot_node in boot_nodes {
+			if !reserved_nodes.contains(&boot_node) {
+				reserved_nodes.push(boot_node);
+			}
+		}
+	}
+	// The addresses of the legal officer nodes, only their peer IDs being known on chain.
+	let known_nodes: Vec<_> = config
+		.network
+		.boot_nodes
+		.iter()
+		.chain(&config.network.default_peers_set.reserved_nodes)
+		.cloned()
+		.collect();
+
+	let consensus =
+		ConsensusParams::from_chain_spec(&*config.chain_spec).with_overrides(&options.consensus);
+	consensus.validate().map_err(ServiceError::Other)?;
+	if options.sealing.is_some() &&
+		config.chain_spec.chain_type() != sc_service::ChainType::Development
+	{
+		return Err(ServiceError::Other("--sealing is only available on development chains".into()))
+	}
+
 	let sc_service::PartialComponents {
 		client,
 		backend,
@@ -152,7 +187,7 @@ pub fn new_full(config: Configuration) -> Result<TaskManager, ServiceError> {
 		select_chain,
 		transaction_pool,
 		other: (block_import, grandpa_link, mut telemetry),
-	} = new_partial(&config)?;
+	} = new_partial_with_justification_period(&config, consensus.grandpa_justification_period)?;
 
 	let mut net_config = sc_network::config::FullNetworkConfiguration::new(&config.network);
 
@@ -204,20 +239,155 @@ pub fn new_full(config: Configuration) -> Result<TaskManager, ServiceError> {
 		);
 	}
 
+	// Aura and GRANDPA sign with the remote signer if any, the other services keep using the
+	// local keystore.
+	let consensus_keystore: KeystorePtr =
+		match (&options.remote_signer, &options.remote_signer_token_file) {
+			(Some(address), Some(token_file)) => {
+				let token = crate::remote_signer::read_token(token_file)
+					.map_err(ServiceError::Other)?;
+				Arc::new(crate::remote_signer::RemoteKeystore::new(address.clone(), token))
+			},
+			_ => keystore_container.keystore(),
+		};
+
+	// Authorities record the slots and rounds they signed for, refusing to sign twice.
+	let signing_record = if config.role.is_authority() && options.sealing.is_none() {
+		let path = crate::double_sign::record_path(&config);
+		let db = crate::double_sign::SigningRecordDb::open(path, client.chain_info().genesis_hash)
+			.map_err(ServiceError::Other)?;
+		Some(Arc::new(db))
+	} else {
+		None
+	};
+	let consensus_keystore: KeystorePtr = match &signing_record {
+		Some(db) => Arc::new(crate::double_sign::ProtectedKeystore::new(
+			consensus_keystore,
+			db.clone(),
+		)),
+		None => consensus_keystore,
+	};
+
+	if config.role.is_authority() {
+		task_manager.spawn_essential_handle().spawn(
+			"validator-check",
+			None,
+			crate::validator_check::check_validator_setup(
+				client.clone(),
+				consensus_keystore.clone(),
+				network.local_peer_id(),
+				sync_service.clone(),
+				options.strict,
+			),
+		);
+	}
+
+	task_manager.spawn_handle().spawn(
+		"legal-officer-peering",
+		None,
+		crate::peering::keep_legal_officer_nodes_reserved(
+			client.clone(),
+			network.clone(),
+			known_nodes,
+		),
+	);
+
+	if config.offchain_worker.indexing_enabled {
+		// MMR nodes are written to the offchain database by the runtime, the gadget
+		// canonicalizes them on finality and prunes the ones of stale forks.
+		task_manager.spawn_essential_handle().spawn_blocking(
+			"mmr-gadget",
+			Some("mmr-gadget"),
+			mmr_gadget::MmrGadget::start(
+				client.clone(),
+				backend.clone(),
+				sp_mmr_primitives::INDEXING_PREFIX.to_vec(),
+			),
+		);
+		// LOC index journals are merged on finality.
+		if let Some(offchain_db) = backend.offchain_storage() {
+			task_manager.spawn_handle().spawn_blocking(
+				"logion-loc-index",
+				None,
+				crate::loc_index::run_loc_index(client.clone(), offchain_db),
+			);
+		}
+	}
+
+	let indexer = if options.logion_indexer {
+		let path = config.base_path.config_dir(config.chain_spec.id()).join("logion-indexer");
+		let db = Arc::new(crate::indexer::IndexerDb::open(&path).map_err(ServiceError::Other)?);
+		task_manager.spawn_handle().spawn_blocking(
+			"logion-indexer",
+			None,
+			crate::indexer::run_indexer(client.clone(), db.clone(), options.logion_indexer_from),
+		);
+		Some(db)
+	} else {
+		None
+	};
+
+	if let Some(path) = &options.webhooks {
+		let webhooks = crate::webhooks::WebhooksConfig::load(path).map_err(ServiceError::Other)?;
+		let cursors_path =
+			config.base_path.config_dir(config.chain_spec.id()).join("logion-webhooks.json");
+		let cursors = Arc::new(
+			crate::webhooks::CursorStore::open(cursors_path).map_err(ServiceError::Other)?,
+		);
+		for endpoint in webhooks.endpoints {
+			task_manager.spawn_handle().spawn(
+				"logion-webhook",
+				Some("logion-webhooks"),
+				crate::webhooks::run_endpoint(client.clone(), endpoint, cursors.clone()),
+			);
+		}
+	}
+
 	let role = config.role.clone();
 	let force_authoring = config.force_authoring;
-	let backoff_authoring_blocks: Option<()> = None;
+	let backoff_authoring_blocks = consensus.backoff_strategy();
 	let name = config.network.node_name.clone();
 	let enable_grandpa = !config.disable_grandpa;
 	let prometheus_registry = config.prometheus_registry().cloned();
 
+	let shared_voter_state = SharedVoterState::empty();
+
+	let (manual_seal_sink, manual_seal_commands) = match options.sealing {
+		Some(_) => {
+			let (sink, commands) = futures::channel::mpsc::channel(1024);
+			(Some(sink), Some(commands))
+		},
+		None => (None, None),
+	};
+
 	let rpc_extensions_builder = {
 		let client = client.clone();
+		let backend = backend.clone();
 		let pool = transaction_pool.clone();
+		let shared_voter_state = shared_voter_state.clone();
+		let shared_authority_set = grandpa_link.shared_authority_set().clone();
+		let justification_stream = grandpa_link.justification_stream();
+		let finality_proof_provider = sc_consensus_grandpa::FinalityProofProvider::new_for_service(
+			backend.clone(),
+			Some(shared_authority_set.clone()),
+		);
 
-		Box::new(move |deny_unsafe, _| {
-			let deps =
-				crate::rpc::FullDeps { client: client.clone(), pool: pool.clone(), deny_unsafe };
+		Box::new(move |deny_unsafe, subscription_executor| {
+			let deps = crate::rpc::FullDeps {
+				client: client.clone(),
+				backend: backend.clone(),
+				pool: pool.clone(),
+				deny_unsafe,
+				grandpa: crate::rpc::GrandpaDeps {
+					shared_voter_state: shared_voter_state.clone(),
+					shared_authority_set: shared_authority_set.clone(),
+					justification_stream: justification_stream.clone(),
+					subscription_executor,
+					finality_provider: finality_proof_provider.clone(),
+				},
+				indexer: indexer.clone(),
+				manual_seal: manual_seal_sink.clone(),
+			};
 			crate::rpc::create_full(deps).map_err(Into::into)
 		})
 	};
@@ -237,7 +407,7 @@ pub fn new_full(config: Configuration) -> Result<TaskManager, ServiceError> {
 		telemetry: telemetry.as_mut(),
 	})?;
 
-	if role.is_authority() {
+	if let (Some(sealing), Some(rpc_commands)) = (options.sealing, manual_seal_commands) {
 		let proposer_factory = sc_basic_authorship::ProposerFactory::new(
 			task_manager.spawn_handle(),
 			client.clone(),
@@ -245,6 +415,54 @@ pub fn new_full(config: Configuration) -> Result<TaskManager, ServiceError> {
 			prometheus_registry.as_ref(),
 			telemetry.as_ref().map(|x| x.handle()),
 		);
+		let slot_duration = sc_consensus_aura::slot_duration(&*client)?;
+		let inherents_client = client.clone();
+		let commands_stream =
+			crate::sealing::commands_stream(sealing, rpc_commands, transaction_pool.clone());
+
+		let manual_seal =
+			sc_consensus_manual_seal::run_manual_seal(sc_consensus_manual_seal::ManualSealParams {
+				block_import,
+				env: proposer_factory,
+				client: client.clone(),
+				pool: transaction_pool,
+				commands_stream,
+				select_chain,
+				consensus_data_provider: Some(Box::new(
+					sc_consensus_manual_seal::consensus::aura::AuraConsensusDataProvider::new(
+						client,
+					),
+				)),
+				create_inherent_data_providers: move |parent_hash, ()| {
+					let providers = crate::sealing::next_block(
+						&*inherents_client,
+						parent_hash,
+						slot_duration,
+					);
+					async move { providers.map_err(Into::into) }
+				},
+			});
+
+		// Aura and GRANDPA are replaced by the sealing task, blocks being finalized on sealing
+		// or with the `engine_finalizeBlock` RPC.
+		task_manager
+			.spawn_essential_handle()
+			.spawn_blocking("manual-seal", Some("block-authoring"), manual_seal);
+		network_starter.start_network();
+		return Ok(task_manager)
+	}
+
+	if role.is_authority() {
+		let proposer_factory = crate::double_sign::ProtectedProposerFactory::new(
+			sc_basic_authorship::ProposerFactory::new(
+				task_manager.spawn_handle(),
+				client.clone(),
+				transaction_pool.clone(),
+				prometheus_registry.as_ref(),
+				telemetry.as_ref().map(|x| x.handle()),
+			),
+			signing_record.expect("Authorities have a signing record; qed"),
+		);
 
 		let slot_duration = sc_consensus_aura::slot_duration(&*client)?;
 
@@ -268,10 +486,10 @@ pub fn new_full(config: Configuration) -> Result<TaskManager, ServiceError> {
 				},
 				force_authoring,
 				backoff_authoring_blocks,
-				keystore: keystore_container.keystore(),
+				keystore: consensus_keystore.clone(),
 				sync_oracle: sync_service.clone(),
 				justification_sync_link: sync_service.clone(),
-				block_proposal_slot_portion: SlotProportion::new(2f32 / 3f32),
+				block_proposal_slot_portion: consensus.slot_proportion(),
 				max_block_proposal_slot_portion: None,
 				telemetry: telemetry.as_ref().map(|x| x.handle()),
 				compatibility_mode: Default::default(),
@@ -288,12 +506,11 @@ pub fn new_full(config: Configuration) -> Result<TaskManager, ServiceError> {
 	if enable_grandpa {
 		// if the node isn't actively participating in consensus then it doesn't
 		// need a keystore, regardless of which protocol we use below.
-		let keystore = if role.is_authority() { Some(keystore_container.keystore()) } else { None };
+		let keystore = if role.is_authority() { Some(consensus_keystore) } else { None };
 
 		let grandpa_config = sc_consensus_grandpa::Config {
-			// FIXME #1578 make this available through chainspec
-			gossip_duration: Duration::from_millis(333),
-			justification_generation_period: GRANDPA_JUSTIFICATION_PERIOD,
+			gossip_duration: consensus.gossip_duration(),
+			justification_generation_period: consensus.grandpa_justification_period,
 			name: Some(name),
 			observer_enabled: false,
 			keystore,
@@ -314,9 +531,9 @@ pub fn new_full(config: Configuration) -> Result<TaskManager, ServiceError> {
 			network,
 			sync: Arc::new(sync_service),
 			notification_service: grandpa_notification_service,
-			voting_rule: sc_consensus_grandpa::VotingRulesBuilder::default().build(),
+			voting_rule: consensus.voting_rule(),
 			prometheus_registry,
-			shared_voter_state: SharedVoterState::empty(),
+			shared_voter_state,
 			telemetry: telemetry.as_ref().map(|x| x.handle()),
 			offchain_tx_pool_factory: OffchainTransactionPoolFactory::new(transaction_pool),
 		};
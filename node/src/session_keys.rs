@@ -0,0 +1,261 @@
+//! Session key management of validators.
+//!
+//! Both session keys (Aura sr25519 and GRANDPA ed25519) are derived from a single secret URI, so
+//! that they can be restored from one backup. The commands print the SCALE-encoded
+//! `opaque::SessionKeys` and the `Session::set_keys` call registering them, in the JSON format
+//! of `tx build`.
+
+use codec::{DecodeAll, Encode};
+use logion_node_runtime::{
+	apis::ValidatorRegistrationApi,
+	opaque::{Block, SessionKeys},
+	AccountId, RuntimeCall,
+};
+use sc_cli::{CliConfiguration, DatabaseParams, KeystoreParams, SharedParams};
+use sc_keystore::LocalKeystore;
+use sc_service::config::KeystoreConfig;
+use serde::Serialize;
+use serde_json::Value;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_consensus_aura::sr25519::{AuthorityId as AuraId, AuthorityPair as AuraPair};
+use sp_consensus_grandpa::{AuthorityId as GrandpaId, AuthorityPair as GrandpaPair};
+use sp_core::{
+	crypto::{key_types, ByteArray},
+	sr25519, Bytes, Pair,
+};
+use sp_keystore::{Keystore, KeystorePtr};
+
+use crate::{
+	scale_json::{decode_value, extrinsic_param, native_metadata},
+	validator_check::validator_setup_problems,
+};
+
+/// The `key session` subcommands.
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum SessionKeyCmd {
+	/// Generate a secret phrase and the session keys derived from it.
+	Generate(GenerateSessionKeysCmd),
+	/// Print the session keys derived from a secret URI, or decode encoded session keys.
+	Inspect(InspectSessionKeysCmd),
+	/// Insert the session keys derived from a secret URI into the node's keystore.
+	Insert(InsertSessionKeysCmd),
+	/// Check that the node's keystore holds the session keys registered on chain.
+	Verify(VerifySessionKeysCmd),
+}
+
+/// Session keys, and the call registering them.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionKeysInfo {
+	/// The secret phrase the keys are derived from, only given by `generate`.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub secret_phrase: Option<String>,
+	/// The Aura key.
+	pub aura: AuraId,
+	/// The GRANDPA key.
+	pub grandpa: GrandpaId,
+	/// The SCALE-encoded `opaque::SessionKeys`, as returned by `author_rotateKeys`.
+	pub keys: Bytes,
+	/// The `Session::set_keys` call registering the keys, to be given to `tx build`.
+	pub set_keys_call: Value,
+}
+
+impl SessionKeysInfo {
+	fn new(keys: SessionKeys, secret_phrase: Option<String>) -> Result<Self, String> {
+		let call = RuntimeCall::Session(pallet_session::Call::set_keys {
+			keys: keys.clone(),
+			proof: Vec::new(),
+		});
+		let metadata = native_metadata();
+		let ty = extrinsic_param(&metadata, "Call").ok_or("Call type not found in metadata")?;
+		let set_keys_call = decode_value(&metadata.types, ty, &mut &call.encode()[..])
+			.map_err(|e| e.to_string())?;
+		Ok(Self {
+			secret_phrase,
+			aura: keys.aura.clone(),
+			grandpa: keys.grandpa.clone(),
+			keys: keys.encode().into(),
+			set_keys_call,
+		})
+	}
+
+	fn print(&self) -> sc_cli::Result<()> {
+		let json = serde_json::to_string_pretty(self)
+			.map_err(|e| format!("Unable to serialize: {}", e))?;
+		println!("{}", json);
+		Ok(())
+	}
+}
+
+/// Derives the session keys of secret URI `suri`.
+fn derive_session_keys(suri: &str) -> Result<SessionKeys, String> {
+	let aura = AuraPair::from_string(suri, None).map_err(|e| format!("{:?}", e))?;
+	let grandpa = GrandpaPair::from_string(suri, None).map_err(|e| format!("{:?}", e))?;
+	Ok(SessionKeys { aura: aura.public(), grandpa: grandpa.public() })
+}
+
+/// The `key session generate` command.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct GenerateSessionKeysCmd {}
+
+impl GenerateSessionKeysCmd {
+	/// Prints a new secret phrase and its session keys.
+	pub fn run(&self) -> sc_cli::Result<()> {
+		let (_, phrase, _) = sr25519::Pair::generate_with_phrase(None);
+		SessionKeysInfo::new(derive_session_keys(&phrase)?, Some(phrase))?.print()
+	}
+}
+
+/// The `key session inspect` command.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct InspectSessionKeysCmd {
+	/// The secret URI the keys are derived from.
+	#[arg(long, required_unless_present = "keys", conflicts_with = "keys")]
+	pub suri: Option<String>,
+
+	/// Hex-encoded session keys, e.g. as returned by `author_rotateKeys`.
+	#[arg(long)]
+	pub keys: Option<Bytes>,
+}
+
+impl InspectSessionKeysCmd {
+	/// Prints the session keys.
+	pub fn run(&self) -> sc_cli::Result<()> {
+		let keys = match (&self.suri, &self.keys) {
+			(Some(suri), _) => derive_session_keys(suri)?,
+			(None, Some(keys)) => SessionKeys::decode_all(&mut &keys[..])
+				.map_err(|e| format!("Invalid session keys: {}", e))?,
+			(None, None) => return Err("Either --suri or --keys is required".into()),
+		};
+		SessionKeysInfo::new(keys, None)?.print()
+	}
+}
+
+/// The `key session insert` command.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct InsertSessionKeysCmd {
+	/// The secret URI the keys are derived from.
+	#[arg(long)]
+	pub suri: String,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub keystore_params: KeystoreParams,
+}
+
+impl InsertSessionKeysCmd {
+	/// Inserts both keys into the keystore configured by `keystore`.
+	pub fn run(&self, keystore: &KeystoreConfig) -> sc_cli::Result<()> {
+		let KeystoreConfig::Path { path, password } = keystore else {
+			return Err("Session keys can only be inserted into a keystore on disk".into())
+		};
+		let keystore = LocalKeystore::open(path, password.clone()).map_err(|e| e.to_string())?;
+		let keys = derive_session_keys(&self.suri)?;
+		keystore
+			.insert(key_types::AURA, &self.suri, keys.aura.as_slice())
+			.and_then(|()| keystore.insert(key_types::GRANDPA, &self.suri, keys.grandpa.as_slice()))
+			.map_err(|()| "Unable to insert keys into keystore")?;
+		SessionKeysInfo::new(keys, None)?.print()
+	}
+}
+
+impl CliConfiguration for InsertSessionKeysCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn keystore_params(&self) -> Option<&KeystoreParams> {
+		Some(&self.keystore_params)
+	}
+}
+
+/// The `key session verify` command.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct VerifySessionKeysCmd {
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub keystore_params: KeystoreParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl VerifySessionKeysCmd {
+	/// Compares the keystore with the session keys registered at the best block of the local
+	/// database. Fails if they differ.
+	pub fn run<C>(&self, client: &C, keystore: KeystorePtr) -> sc_cli::Result<()>
+	where
+		C: ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+		C::Api: ValidatorRegistrationApi<Block, AccountId>,
+	{
+		let problems = validator_setup_problems(client, &keystore, None, client.info().best_hash)?;
+		if problems.is_empty() {
+			println!("Keystore holds the session keys registered on chain");
+			Ok(())
+		} else {
+			Err(problems.join("; ").into())
+		}
+	}
+}
+
+impl CliConfiguration for VerifySessionKeysCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn keystore_params(&self) -> Option<&KeystoreParams> {
+		Some(&self.keystore_params)
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Decode;
+	use crate::{chain_spec::authority_keys_from_seed, scale_json::encode_value};
+
+	#[test]
+	fn keys_are_derived_from_one_seed() {
+		let (_, aura, grandpa) = authority_keys_from_seed("Alice");
+
+		let keys = derive_session_keys("//Alice").unwrap();
+
+		assert_eq!(keys.aura, aura);
+		assert_eq!(keys.grandpa, grandpa);
+	}
+
+	#[test]
+	fn set_keys_call_is_encodable() {
+		let keys = derive_session_keys("//Alice").unwrap();
+		let info = SessionKeysInfo::new(keys.clone(), None).unwrap();
+		let metadata = native_metadata();
+
+		let mut encoded = Vec::new();
+		encode_value(
+			&metadata.types,
+			extrinsic_param(&metadata, "Call").unwrap(),
+			&info.set_keys_call,
+			&mut encoded,
+		)
+		.unwrap();
+
+		assert_eq!(
+			RuntimeCall::decode(&mut &encoded[..]).unwrap(),
+			RuntimeCall::Session(pallet_session::Call::set_keys { keys, proof: Vec::new() })
+		);
+	}
+}
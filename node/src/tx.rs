@@ -0,0 +1,451 @@
+//! Offline construction and signing of transactions, for keys kept on air-gapped machines.
+//!
+//! `tx build` encodes a call given as JSON (in the format of `inspect`) into an unsigned
+//! transaction, holding everything the signature covers: nonce, tip, era, genesis hash and
+//! runtime versions. `tx sign` signs it with a secret URI or a keystore and produces a
+//! hex-encoded `UncheckedExtrinsic`, which `tx submit` sends to a node from an online machine.
+//!
+//! Calls are encoded with the node's runtime, which must be the runtime of the chain.
+
+use std::{fs, path::PathBuf};
+
+use codec::{Decode, DecodeAll, Encode};
+use hyper::{header::CONTENT_TYPE, Body, Request};
+use logion_node_runtime::{
+	AccountId, Balance, Hash, Nonce, Runtime, RuntimeCall, SignedExtra, SignedPayload,
+	UncheckedExtrinsic, VERSION,
+};
+use sc_keystore::LocalKeystore;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sp_core::{
+	crypto::{key_types::ACCOUNT, SecretString},
+	sr25519, Bytes, Pair,
+};
+use sp_keystore::Keystore;
+use sp_runtime::generic::Era;
+
+use crate::{
+	scale_json::{decode_value, encode_value, extrinsic_param, native_metadata},
+	webhooks::http_client,
+};
+
+/// The `tx` subcommands.
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum TxCmd {
+	/// Build an unsigned transaction from a call given as JSON.
+	Build(TxBuildCmd),
+	/// Sign a transaction built with `tx build`.
+	Sign(TxSignCmd),
+	/// Submit a signed transaction to a node.
+	Submit(TxSubmitCmd),
+}
+
+impl TxCmd {
+	/// Runs the subcommand.
+	pub fn run(&self) -> sc_cli::Result<()> {
+		match self {
+			Self::Build(cmd) => cmd.run(),
+			Self::Sign(cmd) => cmd.run(),
+			Self::Submit(cmd) => cmd.run(),
+		}
+	}
+}
+
+/// The era of a mortal transaction.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Mortality {
+	/// The number of blocks the transaction is valid for, rounded to a power of two.
+	pub period: u64,
+	/// The number of the block the era starts at.
+	pub block_number: u64,
+	/// The hash of block `block_number`.
+	pub block_hash: Hash,
+}
+
+/// A transaction ready to be signed.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UnsignedTransaction {
+	/// The SCALE-encoded call.
+	pub call: Bytes,
+	/// The nonce of the signer.
+	pub nonce: Nonce,
+	/// The tip.
+	pub tip: Balance,
+	/// The era of the transaction, immortal if none.
+	pub mortality: Option<Mortality>,
+	/// The hash of the chain's genesis block.
+	pub genesis_hash: Hash,
+	/// The `spec_version` of the chain's runtime.
+	pub spec_version: u32,
+	/// The `transaction_version` of the chain's runtime.
+	pub transaction_version: u32,
+}
+
+impl UnsignedTransaction {
+	fn call(&self) -> Result<RuntimeCall, String> {
+		RuntimeCall::decode_all(&mut &self.call[..]).map_err(|e| format!("Invalid call: {}", e))
+	}
+
+	fn extra(&self) -> SignedExtra {
+		let era = match &self.mortality {
+			Some(mortality) => Era::mortal(mortality.period, mortality.block_number),
+			None => Era::Immortal,
+		};
+		(
+			frame_system::CheckNonZeroSender::<Runtime>::new(),
+			frame_system::CheckSpecVersion::<Runtime>::new(),
+			frame_system::CheckTxVersion::<Runtime>::new(),
+			frame_system::CheckGenesis::<Runtime>::new(),
+			frame_system::CheckEra::<Runtime>::from(era),
+			frame_system::CheckNonce::<Runtime>::from(self.nonce),
+			frame_system::CheckWeight::<Runtime>::new(),
+			pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(self.tip),
+		)
+	}
+
+	/// Returns the payload to sign. The additional data of the signed extensions is given
+	/// explicitly, as it cannot be read from the state of an offline machine.
+	fn payload(&self) -> Result<SignedPayload, String> {
+		let checkpoint = match &self.mortality {
+			Some(mortality) => mortality.block_hash,
+			None => self.genesis_hash,
+		};
+		Ok(SignedPayload::from_raw(
+			self.call()?,
+			self.extra(),
+			(
+				(),
+				self.spec_version,
+				self.transaction_version,
+				self.genesis_hash,
+				checkpoint,
+				(),
+				(),
+				(),
+			),
+		))
+	}
+
+	fn sign(&self, signer: &Signer) -> Result<UncheckedExtrinsic, String> {
+		let payload = self.payload()?.using_encoded(|payload| payload.to_vec());
+		let signature = signer.sign(&payload)?;
+		Ok(UncheckedExtrinsic::new_signed(
+			self.call()?,
+			signer.account().into(),
+			signature.into(),
+			self.extra(),
+		))
+	}
+}
+
+/// The `tx build` command.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct TxBuildCmd {
+	/// The call as JSON, in the format of `inspect` (e.g.
+	/// `{"LogionLoc": {"close": {"loc_id": "1", ...}}}`), or `@<path>` to read it from a file.
+	pub call: String,
+
+	/// The nonce of the signer.
+	#[arg(long)]
+	pub nonce: Nonce,
+
+	/// The tip.
+	#[arg(long, default_value_t = 0)]
+	pub tip: Balance,
+
+	/// The hash of the chain's genesis block.
+	#[arg(long)]
+	pub genesis_hash: Hash,
+
+	/// Make the transaction mortal, valid for about this number of blocks after
+	/// `--block-number`. The transaction is immortal by default.
+	#[arg(long, requires_all = ["block_number", "block_hash"])]
+	pub era_period: Option<u64>,
+
+	/// The number of the block a mortal transaction's era starts at, usually the best block.
+	#[arg(long)]
+	pub block_number: Option<u64>,
+
+	/// The hash of block `--block-number`.
+	#[arg(long)]
+	pub block_hash: Option<Hash>,
+
+	/// The `spec_version` of the chain's runtime. Calls being encoded with the node's runtime,
+	/// the build fails if it differs from the node's one.
+	#[arg(long)]
+	pub spec_version: Option<u32>,
+
+	/// The `transaction_version` of the chain's runtime. Calls being encoded with the node's
+	/// runtime, the build fails if it differs from the node's one.
+	#[arg(long)]
+	pub transaction_version: Option<u32>,
+
+	/// The file to write the unsigned transaction to, standard output by default.
+	#[arg(long)]
+	pub output: Option<PathBuf>,
+}
+
+impl TxBuildCmd {
+	/// Encodes the call and writes the unsigned transaction.
+	pub fn run(&self) -> sc_cli::Result<()> {
+		let call = match self.call.strip_prefix('@') {
+			Some(path) => fs::read_to_string(path)?,
+			None => self.call.clone(),
+		};
+		let call: Value =
+			serde_json::from_str(&call).map_err(|e| format!("Invalid call JSON: {}", e))?;
+		check_version("spec_version", self.spec_version, VERSION.spec_version)?;
+		check_version(
+			"transaction_version",
+			self.transaction_version,
+			VERSION.transaction_version,
+		)?;
+		let mortality = match (self.era_period, self.block_number, self.block_hash) {
+			(Some(period), Some(block_number), Some(block_hash)) => {
+				// Long eras start at a quantized block, whose hash would be required instead.
+				if Era::mortal(period, block_number).birth(block_number) != block_number {
+					return Err("Era period too long, at most 4096 blocks are supported".into())
+				}
+				Some(Mortality { period, block_number, block_hash })
+			},
+			_ => None,
+		};
+		let transaction = UnsignedTransaction {
+			call: encode_call(&call)?.into(),
+			nonce: self.nonce,
+			tip: self.tip,
+			mortality,
+			genesis_hash: self.genesis_hash,
+			spec_version: VERSION.spec_version,
+			transaction_version: VERSION.transaction_version,
+		};
+		let json = serde_json::to_string_pretty(&transaction)
+			.map_err(|e| format!("Unable to serialize: {}", e))?;
+		match &self.output {
+			Some(path) => fs::write(path, json)?,
+			None => println!("{}", json),
+		}
+		Ok(())
+	}
+}
+
+/// Checks that the `version` of the chain's runtime, if given, is the one of the node's runtime:
+/// otherwise, the call encoded with the node's metadata may not be the one the chain decodes.
+fn check_version(name: &str, version: Option<u32>, native: u32) -> Result<(), String> {
+	match version {
+		Some(version) if version != native => Err(format!(
+			"The chain's runtime has {} {}, the node's runtime {}: build the transaction with a \
+			 node of the chain's runtime",
+			name, version, native
+		)),
+		_ => Ok(()),
+	}
+}
+
+/// Encodes a call given as JSON, checking that the runtime decodes it.
+fn encode_call(call: &Value) -> Result<Vec<u8>, String> {
+	let metadata = native_metadata();
+	let ty = extrinsic_param(&metadata, "Call").ok_or("Call type not found in metadata")?;
+	let mut encoded = Vec::new();
+	encode_value(&metadata.types, ty, call, &mut encoded)?;
+	RuntimeCall::decode_all(&mut &encoded[..]).map_err(|e| format!("Invalid call: {}", e))?;
+	Ok(encoded)
+}
+
+/// The key signing a transaction.
+enum Signer {
+	Pair(sr25519::Pair),
+	Keystore(LocalKeystore, sr25519::Public),
+}
+
+impl Signer {
+	fn account(&self) -> AccountId {
+		match self {
+			Self::Pair(pair) => pair.public().into(),
+			Self::Keystore(_, public) => (*public).into(),
+		}
+	}
+
+	fn sign(&self, message: &[u8]) -> Result<sr25519::Signature, String> {
+		match self {
+			Self::Pair(pair) => Ok(pair.sign(message)),
+			Self::Keystore(keystore, public) => keystore
+				.sr25519_sign(ACCOUNT, public, message)
+				.map_err(|e| e.to_string())?
+				.ok_or_else(|| format!("Key {} not found in keystore", self.account())),
+		}
+	}
+}
+
+/// The `tx sign` command.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct TxSignCmd {
+	/// The unsigned transaction, written by `tx build`.
+	pub transaction: PathBuf,
+
+	/// The secret URI (e.g. mnemonic phrase) of the sr25519 signing key.
+	#[arg(long, required_unless_present = "keystore_path", conflicts_with = "keystore_path")]
+	pub suri: Option<String>,
+
+	/// The keystore holding the signing key, as an account key.
+	#[arg(long, requires = "public")]
+	pub keystore_path: Option<PathBuf>,
+
+	/// The address of the signing key in `--keystore-path`.
+	#[arg(long)]
+	pub public: Option<AccountId>,
+
+	/// The password of the keystore.
+	#[arg(long)]
+	pub password: Option<String>,
+}
+
+impl TxSignCmd {
+	/// Signs the transaction and prints the hex-encoded extrinsic.
+	pub fn run(&self) -> sc_cli::Result<()> {
+		let content = fs::read(&self.transaction)?;
+		let transaction: UnsignedTransaction = serde_json::from_slice(&content)
+			.map_err(|e| format!("Unable to parse {}: {}", self.transaction.display(), e))?;
+		let signer = match (&self.suri, &self.keystore_path, &self.public) {
+			(Some(suri), _, _) => Signer::Pair(
+				sr25519::Pair::from_string(suri, None).map_err(|e| format!("{:?}", e))?,
+			),
+			(None, Some(path), Some(public)) => {
+				let password = self.password.clone().map(SecretString::new);
+				let keystore = LocalKeystore::open(path, password).map_err(|e| e.to_string())?;
+				let public = sr25519::Public::from_raw(public.clone().into());
+				Signer::Keystore(keystore, public)
+			},
+			_ => return Err("Either --suri or --keystore-path and --public are required".into()),
+		};
+
+		let metadata = native_metadata();
+		if let Some(ty) = extrinsic_param(&metadata, "Call") {
+			let call = decode_value(&metadata.types, ty, &mut &transaction.call[..])
+				.map_err(|e| format!("Invalid call: {}", e))?;
+			eprintln!("Signing as {}: {}", signer.account(), call);
+		}
+		let extrinsic = transaction.sign(&signer)?;
+		println!("{}", sp_core::bytes::to_hex(&extrinsic.encode(), false));
+		Ok(())
+	}
+}
+
+/// The `tx submit` command.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct TxSubmitCmd {
+	/// The hex-encoded signed transaction, written by `tx sign`.
+	pub transaction: String,
+
+	/// The HTTP RPC endpoint of the node to submit the transaction to.
+	#[arg(long, default_value = "http://localhost:9944")]
+	pub url: String,
+}
+
+impl TxSubmitCmd {
+	/// Submits the transaction and prints its hash.
+	pub fn run(&self) -> sc_cli::Result<()> {
+		let encoded = sp_core::bytes::from_hex(&self.transaction).map_err(|e| e.to_string())?;
+		UncheckedExtrinsic::decode(&mut &encoded[..])
+			.map_err(|e| format!("Invalid transaction: {}", e))?;
+		let body = serde_json::json!({
+			"jsonrpc": "2.0",
+			"id": 1,
+			"method": "author_submitExtrinsic",
+			"params": [self.transaction],
+		});
+		let request = Request::post(&self.url)
+			.header(CONTENT_TYPE, "application/json")
+			.body(Body::from(body.to_string()))
+			.map_err(|e| e.to_string())?;
+
+		let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+		let response: Value = runtime.block_on(async {
+			let response = http_client().request(request).await.map_err(|e| e.to_string())?;
+			let body =
+				hyper::body::to_bytes(response.into_body()).await.map_err(|e| e.to_string())?;
+			serde_json::from_slice(&body).map_err(|e| format!("Invalid response: {}", e))
+		})?;
+		match (&response["result"], &response["error"]) {
+			(Value::String(hash), _) => {
+				println!("{}", hash);
+				Ok(())
+			},
+			(_, error) => Err(format!("Transaction rejected: {}", error).into()),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_keyring::Sr25519Keyring;
+	use sp_runtime::{traits::Verify, MultiAddress};
+
+	fn transaction(mortality: Option<Mortality>) -> UnsignedTransaction {
+		let call = serde_json::json!({ "System": { "remark": { "remark": "0x0102" } } });
+		UnsignedTransaction {
+			call: encode_call(&call).unwrap().into(),
+			nonce: 3,
+			tip: 10,
+			mortality,
+			genesis_hash: Hash::repeat_byte(1),
+			spec_version: 174,
+			transaction_version: 5,
+		}
+	}
+
+	fn assert_signed_by(transaction: &UnsignedTransaction, extrinsic: UncheckedExtrinsic) {
+		let (address, signature, extra) = extrinsic.signature.unwrap();
+		let account = Sr25519Keyring::Alice.to_account_id();
+		assert_eq!(address, MultiAddress::Id(account.clone()));
+		assert_eq!(extra.encode(), transaction.extra().encode());
+		let payload = transaction.payload().unwrap().using_encoded(|payload| payload.to_vec());
+		assert!(signature.verify(&payload[..], &account));
+	}
+
+	#[test]
+	fn signs_with_secret_uri() {
+		let transaction = transaction(Some(Mortality {
+			period: 64,
+			block_number: 1_000,
+			block_hash: Hash::repeat_byte(2),
+		}));
+		let signer = Signer::Pair(Sr25519Keyring::Alice.pair());
+
+		let extrinsic = transaction.sign(&signer).unwrap();
+
+		assert_signed_by(&transaction, extrinsic);
+	}
+
+	#[test]
+	fn signs_with_keystore() {
+		let transaction = transaction(None);
+		let dir = tempfile::tempdir().unwrap();
+		let keystore = LocalKeystore::open(dir.path(), None).unwrap();
+		let public = keystore.sr25519_generate_new(ACCOUNT, Some("//Alice")).unwrap();
+		let signer = Signer::Keystore(keystore, public);
+
+		let extrinsic = transaction.sign(&signer).unwrap();
+
+		assert_signed_by(&transaction, extrinsic);
+	}
+
+	#[test]
+	fn invalid_call_is_rejected() {
+		let call = serde_json::json!({ "System": { "remark": { "wrong_field": "0x" } } });
+
+		assert_eq!(encode_call(&call).unwrap_err(), "System: remark: Missing field remark");
+	}
+
+	#[test]
+	fn other_runtime_versions_are_rejected() {
+		assert!(check_version("spec_version", None, 174).is_ok());
+		assert!(check_version("spec_version", Some(174), 174).is_ok());
+		let error = check_version("spec_version", Some(175), 174).unwrap_err();
+		assert!(error.starts_with("The chain's runtime has spec_version 175"), "{}", error);
+	}
+}
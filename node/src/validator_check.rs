@@ -0,0 +1,140 @@
+//! Check of an authority node's setup against its on-chain registration.
+//!
+//! A validator started with the wrong node key, or without its session keys in the keystore, is
+//! otherwise only noticed when it stops producing blocks or when its peers reject it.
+//!
+//! The check runs once the node is synced, against the first block finalized while not major
+//! syncing: the state of a syncing node's database may predate the registration.
+
+use std::sync::Arc;
+
+use futures::StreamExt;
+use logion_node_runtime::{
+	apis::{ValidatorRegistration, ValidatorRegistrationApi},
+	opaque::Block,
+	AccountId, Hash,
+};
+use sc_client_api::BlockchainEvents;
+use sc_network::PeerId;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_consensus::SyncOracle;
+use sp_consensus_aura::sr25519::AuthorityId as AuraId;
+use sp_core::crypto::{key_types, ByteArray};
+use sp_keystore::KeystorePtr;
+
+const LOG_TARGET: &str = "logion-validator-check";
+
+/// Checks, once the node is synced, that the keystore contains session keys set on chain by a
+/// validator, and that the local peer ID is the node registered for this validator in
+/// `LoAuthorityList`.
+///
+/// Each problem is logged as a warning. If `strict` is true, problems are logged as errors and
+/// the function returns if at least one was found: it is meant to run as an essential task, so
+/// that the node stops. Otherwise, it never returns.
+pub async fn check_validator_setup<C, S>(
+	client: Arc<C>,
+	keystore: KeystorePtr,
+	local_peer_id: PeerId,
+	sync_oracle: S,
+	strict: bool,
+) where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + BlockchainEvents<Block>,
+	C::Api: ValidatorRegistrationApi<Block, AccountId>,
+	S: SyncOracle,
+{
+	let mut finality_notifications = client.finality_notification_stream();
+	while let Some(notification) = finality_notifications.next().await {
+		if sync_oracle.is_major_syncing() {
+			continue
+		}
+		let problems = validator_setup_problems(
+			client.as_ref(),
+			&keystore,
+			Some(local_peer_id),
+			notification.hash,
+		)
+		.unwrap_or_else(|e| vec![e]);
+		for problem in problems.iter() {
+			if strict {
+				log::error!(target: LOG_TARGET, "❌ {}", problem);
+			} else {
+				log::warn!(target: LOG_TARGET, "⚠️  {}", problem);
+			}
+		}
+		if strict && !problems.is_empty() {
+			log::error!(
+				target: LOG_TARGET,
+				"Validator setup does not match on-chain registration, stopping (--strict)"
+			);
+			return
+		}
+		break
+	}
+	futures::future::pending::<()>().await
+}
+
+/// Returns the differences between the keystore (and the local peer ID, if given) and the
+/// on-chain registration of the validator, in the state of block `at`.
+pub(crate) fn validator_setup_problems<C>(
+	client: &C,
+	keystore: &KeystorePtr,
+	local_peer_id: Option<PeerId>,
+	at: Hash,
+) -> Result<Vec<String>, String>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: ValidatorRegistrationApi<Block, AccountId>,
+{
+	let aura_keys = keystore.sr25519_public_keys(key_types::AURA);
+	if aura_keys.is_empty() {
+		return Ok(vec!["no Aura key found in keystore, insert session keys".into()])
+	}
+
+	let mut registration: Option<ValidatorRegistration<AccountId>> = None;
+	for aura_key in aura_keys {
+		registration = client
+			.runtime_api()
+			.validator_registration(at, AuraId::from(aura_key))
+			.map_err(|e| format!("Unable to read validator registration: {}", e))?;
+		if registration.is_some() {
+			break
+		}
+	}
+	let Some(registration) = registration else {
+		return Ok(vec![
+			"no Aura key of the keystore is registered as next session key, call session.setKeys"
+				.into(),
+		])
+	};
+
+	let mut problems = Vec::new();
+	match &registration.grandpa_key {
+		Some(grandpa_key)
+			if keystore.has_keys(&[(grandpa_key.to_raw_vec(), key_types::GRANDPA)]) => {},
+		Some(grandpa_key) => problems.push(format!(
+			"GRANDPA key {:?} registered by {} not found in keystore",
+			grandpa_key, registration.account
+		)),
+		None => problems.push(format!("no GRANDPA key registered by {}", registration.account)),
+	}
+	if !registration.is_validator {
+		problems.push(format!("account {} is not in the validator set", registration.account));
+	}
+	if let Some(local_peer_id) = local_peer_id.filter(|_| registration.is_legal_officer) {
+		let registered_peer_id =
+			registration.node_id.as_ref().and_then(|node_id| PeerId::from_bytes(&node_id.0).ok());
+		match registered_peer_id {
+			Some(peer_id) if peer_id == local_peer_id => {},
+			Some(peer_id) => problems.push(format!(
+				"local peer ID {} differs from node {} registered by {}, check --node-key",
+				local_peer_id, peer_id, registration.account
+			)),
+			None => problems.push(format!(
+				"no valid node registered in LoAuthorityList by {}",
+				registration.account
+			)),
+		}
+	}
+	Ok(problems)
+}
@@ -0,0 +1,430 @@
+//! Delivers the events of finalized blocks to HTTP endpoints.
+//!
+//! Each endpoint receives, for each finalized block with at least one of the events it subscribed
+//! to, a JSON payload signed with the endpoint's secret: the `X-Logion-Signature` header holds
+//! `sha256=` followed by the hex-encoded HMAC-SHA256 of the body.
+//!
+//! Blocks are delivered in order. A failed delivery is retried with an exponential backoff, the
+//! next blocks waiting for it to succeed. Client errors (4xx statuses) being unlikely to resolve by
+//! themselves, a payload rejected [`MAX_REJECTED_ATTEMPTS`] times is dropped. Blocks whose events
+//! cannot be read (e.g. their state was pruned) are skipped. Both leave a gap in the delivered
+//! blocks, which is logged. The last delivered block of each endpoint is persisted, so that
+//! delivery resumes where it stopped after a restart.
+//!
+//! While a delivery is pending, only the last finalized block is kept, not every finality
+//! notification: the blocks finalized in between are delivered next, read from the database.
+
+use std::{
+	collections::HashMap,
+	path::{Path, PathBuf},
+	sync::{Arc, Mutex},
+	time::Duration,
+};
+
+use futures::StreamExt;
+use hmac::{Hmac, Mac};
+use hyper::{client::HttpConnector, header::CONTENT_TYPE, Body, Request, StatusCode};
+use hyper_rustls::HttpsConnector;
+use logion_node_runtime::{opaque::Block, BlockNumber, Hash};
+use sc_client_api::{Backend, BlockchainEvents, StorageProvider};
+use serde::{Deserialize, Serialize};
+use sp_blockchain::HeaderBackend;
+
+use crate::events::{DecodedEvent, EventDecoder};
+
+const LOG_TARGET: &str = "logion-webhooks";
+
+/// The header holding the signature of the payload.
+pub const SIGNATURE_HEADER: &str = "X-Logion-Signature";
+
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The number of times a payload is posted to an endpoint answering with a client error before
+/// it is dropped.
+pub const MAX_REJECTED_ATTEMPTS: u32 = 3;
+
+pub(crate) type HttpClient = hyper::Client<HttpsConnector<HttpConnector>>;
+
+/// The webhooks configuration file.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhooksConfig {
+	/// The endpoints to deliver events to.
+	pub endpoints: Vec<Endpoint>,
+}
+
+impl WebhooksConfig {
+	/// Reads the configuration file at `path`.
+	pub fn load(path: &Path) -> Result<Self, String> {
+		let content = std::fs::read(path)
+			.map_err(|e| format!("Unable to read {}: {}", path.display(), e))?;
+		serde_json::from_slice(&content)
+			.map_err(|e| format!("Invalid webhooks configuration {}: {}", path.display(), e))
+	}
+}
+
+/// An HTTP endpoint and the events delivered to it.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Endpoint {
+	/// The URL payloads are posted to.
+	pub url: String,
+	/// The secret payloads are signed with.
+	pub secret: String,
+	/// The delivered event types, as `Pallet.Event` (e.g. `LogionLoc.LocCreated`).
+	pub events: Vec<String>,
+}
+
+impl Endpoint {
+	fn accepts(&self, event: &DecodedEvent) -> bool {
+		let event_type = format!("{}.{}", event.pallet, event.variant);
+		self.events.iter().any(|accepted| *accepted == event_type)
+	}
+}
+
+/// The payload posted for a finalized block.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Payload {
+	/// The number of the finalized block.
+	pub block_number: BlockNumber,
+	/// The hash of the finalized block.
+	pub block_hash: Hash,
+	/// The events of the block the endpoint subscribed to.
+	pub events: Vec<DecodedEvent>,
+}
+
+/// Returns the hex-encoded HMAC-SHA256 of `body` with key `secret`.
+pub fn sign(secret: &[u8], body: &[u8]) -> String {
+	let mut mac =
+		Hmac::<sha2::Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any size; qed");
+	mac.update(body);
+	hex::encode(mac.finalize().into_bytes())
+}
+
+/// The last delivered block of each endpoint, persisted in a JSON file.
+pub struct CursorStore {
+	path: PathBuf,
+	cursors: Mutex<HashMap<String, BlockNumber>>,
+}
+
+impl CursorStore {
+	/// Loads the cursors stored at `path`, if any.
+	pub fn open(path: PathBuf) -> Result<Self, String> {
+		let cursors = if path.exists() {
+			let content = std::fs::read(&path)
+				.map_err(|e| format!("Unable to read {}: {}", path.display(), e))?;
+			serde_json::from_slice(&content)
+				.map_err(|e| format!("Invalid webhook cursors {}: {}", path.display(), e))?
+		} else {
+			HashMap::new()
+		};
+		Ok(Self { path, cursors: Mutex::new(cursors) })
+	}
+
+	/// Returns the last block delivered to `url`.
+	pub fn get(&self, url: &str) -> Option<BlockNumber> {
+		self.cursors.lock().expect("Cursors lock is not poisoned; qed").get(url).copied()
+	}
+
+	/// Records `block_number` as the last block delivered to `url`.
+	pub fn set(&self, url: &str, block_number: BlockNumber) -> Result<(), String> {
+		let mut cursors = self.cursors.lock().expect("Cursors lock is not poisoned; qed");
+		cursors.insert(url.to_string(), block_number);
+		let content = serde_json::to_vec(&*cursors).map_err(|e| e.to_string())?;
+		// Written aside then renamed, so that a crash never leaves a truncated file.
+		let tmp_path = self.path.with_extension("tmp");
+		std::fs::write(&tmp_path, content)
+			.and_then(|_| std::fs::rename(&tmp_path, &self.path))
+			.map_err(|e| format!("Unable to write {}: {}", self.path.display(), e))
+	}
+}
+
+pub(crate) fn http_client() -> HttpClient {
+	let connector = hyper_rustls::HttpsConnectorBuilder::new()
+		.with_native_roots()
+		.https_or_http()
+		.enable_http1()
+		.build();
+	hyper::Client::builder().build(connector)
+}
+
+/// Delivers the events of each newly finalized block to `endpoint`. On first run, delivery starts
+/// with the blocks finalized after the node started.
+pub async fn run_endpoint<C, B>(client: Arc<C>, endpoint: Endpoint, cursors: Arc<CursorStore>)
+where
+	C: StorageProvider<Block, B> + HeaderBackend<Block> + BlockchainEvents<Block>,
+	B: Backend<Block>,
+{
+	let http = http_client();
+	let decoder = EventDecoder::default();
+	let mut finality_notifications = client.finality_notification_stream();
+	if cursors.get(&endpoint.url).is_none() {
+		if let Err(e) = cursors.set(&endpoint.url, client.info().finalized_number) {
+			log::error!(target: LOG_TARGET, "{}", e);
+		}
+	}
+
+	// Notifications are consumed as they come, only the last finalized block being kept.
+	let (finalized_sender, mut finalized_receiver) =
+		tokio::sync::watch::channel(client.info().finalized_number);
+	let follow_finality = async move {
+		while let Some(notification) = finality_notifications.next().await {
+			let finalized_number = *sp_runtime::traits::Header::number(&notification.header);
+			if finalized_sender.send(finalized_number).is_err() {
+				break
+			}
+		}
+	};
+	let deliver_blocks = async {
+		loop {
+			let finalized_number = *finalized_receiver.borrow_and_update();
+			deliver_finalized(
+				client.as_ref(),
+				&decoder,
+				&http,
+				&endpoint,
+				&cursors,
+				finalized_number,
+			)
+			.await;
+			if finalized_receiver.changed().await.is_err() {
+				break
+			}
+		}
+	};
+	futures::future::join(follow_finality, deliver_blocks).await;
+}
+
+async fn deliver_finalized<C, B>(
+	client: &C,
+	decoder: &EventDecoder,
+	http: &HttpClient,
+	endpoint: &Endpoint,
+	cursors: &CursorStore,
+	finalized_number: BlockNumber,
+) where
+	C: StorageProvider<Block, B> + HeaderBackend<Block>,
+	B: Backend<Block>,
+{
+	let first = cursors.get(&endpoint.url).map_or(0, |number| number + 1);
+	for block_number in first..=finalized_number {
+		match block_payload(client, decoder, endpoint, block_number) {
+			Ok(Some(payload)) => {
+				let body = serde_json::to_vec(&payload).expect("Payload is serializable; qed");
+				if let Err(e) = deliver(http, endpoint, body).await {
+					log::error!(
+						target: LOG_TARGET,
+						"Gap in delivery to {}: block {} dropped: {}",
+						endpoint.url,
+						block_number,
+						e
+					);
+				}
+			},
+			Ok(None) => {},
+			Err(e) => log::error!(
+				target: LOG_TARGET,
+				"Gap in delivery to {}: block {} skipped, unable to read it: {}",
+				endpoint.url,
+				block_number,
+				e
+			),
+		}
+		if let Err(e) = cursors.set(&endpoint.url, block_number) {
+			log::error!(target: LOG_TARGET, "{}", e);
+		}
+	}
+}
+
+fn block_payload<C, B>(
+	client: &C,
+	decoder: &EventDecoder,
+	endpoint: &Endpoint,
+	block_number: BlockNumber,
+) -> Result<Option<Payload>, String>
+where
+	C: StorageProvider<Block, B> + HeaderBackend<Block>,
+	B: Backend<Block>,
+{
+	let block_hash = client
+		.hash(block_number)
+		.map_err(|e| e.to_string())?
+		.ok_or_else(|| format!("Block {} not found", block_number))?;
+	let events: Vec<DecodedEvent> = decoder
+		.block_events(client, block_hash)?
+		.into_iter()
+		.filter(|event| endpoint.accepts(event))
+		.collect();
+	Ok((!events.is_empty()).then_some(Payload { block_number, block_hash, events }))
+}
+
+/// A failed delivery attempt.
+enum PostError {
+	/// The endpoint rejected the payload with a client error.
+	Rejected(StatusCode),
+	/// Any other failure, e.g. a server error or a timeout.
+	Failed(String),
+}
+
+impl std::fmt::Display for PostError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::Rejected(status) => write!(f, "Endpoint responded with status {}", status),
+			Self::Failed(e) => write!(f, "{}", e),
+		}
+	}
+}
+
+/// Posts `body` to `endpoint` until it succeeds, or until it was rejected
+/// [`MAX_REJECTED_ATTEMPTS`] times.
+async fn deliver(http: &HttpClient, endpoint: &Endpoint, body: Vec<u8>) -> Result<(), String> {
+	let mut backoff = MIN_BACKOFF;
+	let mut rejected_attempts = 0;
+	loop {
+		let e = match post(http, endpoint, body.clone()).await {
+			Ok(()) => return Ok(()),
+			Err(e) => e,
+		};
+		if let PostError::Rejected(_) = e {
+			rejected_attempts += 1;
+			if rejected_attempts >= MAX_REJECTED_ATTEMPTS {
+				return Err(format!("{}, giving up after {} attempts", e, rejected_attempts))
+			}
+		}
+		log::warn!(
+			target: LOG_TARGET,
+			"Delivery to {} failed, retrying in {:?}: {}",
+			endpoint.url,
+			backoff,
+			e
+		);
+		tokio::time::sleep(backoff).await;
+		backoff = (backoff * 2).min(MAX_BACKOFF);
+	}
+}
+
+async fn post(http: &HttpClient, endpoint: &Endpoint, body: Vec<u8>) -> Result<(), PostError> {
+	let signature = sign(endpoint.secret.as_bytes(), &body);
+	let request = Request::post(&endpoint.url)
+		.header(CONTENT_TYPE, "application/json")
+		.header(SIGNATURE_HEADER, format!("sha256={}", signature))
+		.body(Body::from(body))
+		.map_err(|e| PostError::Failed(e.to_string()))?;
+	let response = tokio::time::timeout(REQUEST_TIMEOUT, http.request(request))
+		.await
+		.map_err(|_| PostError::Failed("Request timed out".into()))?
+		.map_err(|e| PostError::Failed(e.to_string()))?;
+	let status = response.status();
+	if status.is_success() {
+		Ok(())
+	} else if status.is_client_error() &&
+		status != StatusCode::REQUEST_TIMEOUT &&
+		status != StatusCode::TOO_MANY_REQUESTS
+	{
+		Err(PostError::Rejected(status))
+	} else {
+		Err(PostError::Failed(format!("Endpoint responded with status {}", status)))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use hyper::{
+		service::{make_service_fn, service_fn},
+		Response, Server, StatusCode,
+	};
+	use std::convert::Infallible;
+
+	#[test]
+	fn sign_computes_hmac_sha256() {
+		// RFC 4231, test case 2
+		assert_eq!(
+			sign(b"Jefe", b"what do ya want for nothing?"),
+			"5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+		);
+	}
+
+	#[test]
+	fn cursors_are_persisted() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("cursors.json");
+
+		let cursors = CursorStore::open(path.clone()).unwrap();
+		assert_eq!(cursors.get("http://localhost/hook"), None);
+		cursors.set("http://localhost/hook", 42).unwrap();
+
+		let cursors = CursorStore::open(path).unwrap();
+		assert_eq!(cursors.get("http://localhost/hook"), Some(42));
+	}
+
+	type Received = Arc<Mutex<Vec<(StatusCode, String, Vec<u8>)>>>;
+
+	/// Starts an endpoint answering the `n`-th request with `status(n)`.
+	fn start_endpoint(status: fn(usize) -> StatusCode) -> (Endpoint, Received) {
+		let received: Received = Default::default();
+		let server_received = received.clone();
+		let make_service = make_service_fn(move |_| {
+			let received = server_received.clone();
+			async move {
+				Ok::<_, Infallible>(service_fn(move |request: Request<Body>| {
+					let received = received.clone();
+					async move {
+						let signature = request.headers()[SIGNATURE_HEADER].to_str().unwrap();
+						let signature = signature.to_string();
+						let body = hyper::body::to_bytes(request.into_body()).await.unwrap();
+						let mut received = received.lock().unwrap();
+						let status = status(received.len());
+						received.push((status, signature, body.to_vec()));
+						Ok::<_, Infallible>(
+							Response::builder().status(status).body(Body::empty()).unwrap(),
+						)
+					}
+				}))
+			}
+		});
+		let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+		let endpoint = Endpoint {
+			url: format!("http://{}/hook", server.local_addr()),
+			secret: "secret".into(),
+			events: vec!["LogionLoc.LocCreated".into()],
+		};
+		tokio::spawn(server);
+		(endpoint, received)
+	}
+
+	#[tokio::test]
+	async fn delivery_is_signed_and_retried() {
+		// The first attempt fails.
+		let (endpoint, received) = start_endpoint(|n| {
+			if n == 0 {
+				StatusCode::INTERNAL_SERVER_ERROR
+			} else {
+				StatusCode::OK
+			}
+		});
+
+		deliver(&http_client(), &endpoint, b"{\"blockNumber\":1}".to_vec()).await.unwrap();
+
+		let received = received.lock().unwrap();
+		assert_eq!(received.len(), 2);
+		assert_eq!(received[0].0, StatusCode::INTERNAL_SERVER_ERROR);
+		assert_eq!(received[1].0, StatusCode::OK);
+		let expected_signature = format!("sha256={}", sign(b"secret", b"{\"blockNumber\":1}"));
+		assert_eq!(received[1].1, expected_signature);
+		assert_eq!(received[1].2, b"{\"blockNumber\":1}".to_vec());
+	}
+
+	#[tokio::test]
+	async fn rejected_delivery_is_dropped() {
+		let (endpoint, received) = start_endpoint(|_| StatusCode::BAD_REQUEST);
+
+		let result = deliver(&http_client(), &endpoint, b"{\"blockNumber\":1}".to_vec()).await;
+
+		assert!(result.is_err());
+		assert_eq!(received.lock().unwrap().len(), MAX_REJECTED_ATTEMPTS as usize);
+	}
+}
@@ -0,0 +1,30 @@
+//! Benchmarks of the hash anchoring pallet.
+
+use super::*;
+use frame_benchmarking::v2::*;
+use frame_system::RawOrigin;
+use pallet_logion_loc::Hasher;
+
+/// Prepares the state of the benchmarks.
+pub trait AnchoringSetup<AccountId, LocId> {
+	/// Returns a LOC and an account which may anchor roots linked to it.
+	fn setup_anchoring() -> (LocId, AccountId);
+}
+
+#[benchmarks]
+mod benchmarks {
+	use super::*;
+
+	#[benchmark]
+	fn anchor() {
+		let (loc_id, submitter) = T::AnchoringSetup::setup_anchoring();
+		let root = T::Hasher::hash(&b"root".to_vec());
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(submitter), loc_id, root, 1_000);
+
+		assert!(Anchors::<T>::contains_key(loc_id, root));
+	}
+
+	impl_benchmark_test_suite!(Pallet, crate::mock::new_test_ext(), crate::mock::Test);
+}
@@ -0,0 +1,185 @@
+//! # Hash anchoring pallet
+//!
+//! Lets the owner or the requester of a closed transaction LOC anchor the Merkle root of a batch
+//! of document hashes, linked to the LOC. Anchoring thousands of documents then costs a single
+//! extrinsic, instead of one file item per document.
+//!
+//! Roots are anchored per LOC: anchoring a root in a LOC does not prevent anchoring it in another
+//! one, so that nobody can squat a root before its legitimate submitter.
+//!
+//! The inclusion of a given document is checked with [`Pallet::verify_inclusion`], which returns
+//! the anchor (LOC, block and timestamp) of the root in a LOC if the Merkle proof is valid. See
+//! [`merkle`] for the structure of the trees.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub use pallet::*;
+
+pub mod merkle;
+pub mod runtime_api;
+pub mod weights;
+
+#[cfg(feature = "runtime-benchmarks")]
+pub mod benchmarking;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_system::pallet_prelude::BlockNumberFor;
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_std::prelude::*;
+
+pub use weights::WeightInfo;
+
+/// Decides who may anchor roots linked to a LOC.
+pub trait AnchoringAuthorizer<AccountId, LocId> {
+	/// Returns true if `who` may anchor roots linked to `loc_id`.
+	fn can_anchor(who: &AccountId, loc_id: &LocId) -> bool;
+}
+
+/// An anchored Merkle root.
+#[derive(
+	Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug, Serialize, Deserialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct Anchor<AccountId, LocId, BlockNumber> {
+	/// The LOC the documents are linked to.
+	pub loc_id: LocId,
+	/// The account which anchored the root.
+	pub submitter: AccountId,
+	/// The number of documents in the tree, as declared by the submitter.
+	pub documents: u32,
+	/// The block in which the root was anchored.
+	pub block_number: BlockNumber,
+	/// The timestamp of the block, in milliseconds since Unix epoch.
+	pub timestamp: u64,
+}
+
+pub type AnchorOf<T> =
+	Anchor<<T as frame_system::Config>::AccountId, <T as Config>::LocId, BlockNumberFor<T>>;
+
+pub type HashOf<T> = <T as Config>::Hash;
+
+/// The depth of a tree with `u32::MAX` documents, i.e. the maximum length of a valid proof.
+const MAX_PROOF_LENGTH: usize = 32;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::{pallet_prelude::*, traits::UnixTime};
+	use frame_system::pallet_prelude::*;
+	use pallet_logion_loc::Hasher;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The overarching event type.
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// The type of LOC IDs.
+		type LocId: Parameter + Member + MaxEncodedLen + Copy;
+
+		/// The type of document hashes and Merkle roots.
+		type Hash: Parameter + Member + MaxEncodedLen + Copy + Ord + AsRef<[u8]>;
+
+		/// The hash function of the Merkle trees.
+		type Hasher: Hasher<<Self as Config>::Hash>;
+
+		/// Decides who may anchor roots linked to a LOC.
+		type AnchoringAuthorizer: AnchoringAuthorizer<Self::AccountId, Self::LocId>;
+
+		/// Provides the timestamp of the current block.
+		type UnixTime: UnixTime;
+
+		/// Weight information for extrinsics in this pallet.
+		type WeightInfo: WeightInfo;
+
+		/// Prepares the state of the benchmarks.
+		#[cfg(feature = "runtime-benchmarks")]
+		type AnchoringSetup: benchmarking::AnchoringSetup<Self::AccountId, Self::LocId>;
+	}
+
+	/// The anchored Merkle roots, per LOC.
+	#[pallet::storage]
+	pub type Anchors<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::LocId,
+		Blake2_128Concat,
+		HashOf<T>,
+		AnchorOf<T>,
+	>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A Merkle root of documents was anchored.
+		RootAnchored { loc_id: T::LocId, root: HashOf<T>, documents: u32 },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The submitter is neither the owner nor the requester of a closed transaction LOC.
+		Unauthorized,
+		/// The root was already anchored in the LOC.
+		AlreadyAnchored,
+		/// A batch must contain at least one document.
+		EmptyBatch,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Anchors the Merkle `root` of a batch of `documents` hashes, linked to given LOC.
+		#[pallet::call_index(0)]
+		#[pallet::weight(T::WeightInfo::anchor())]
+		pub fn anchor(
+			origin: OriginFor<T>,
+			loc_id: T::LocId,
+			root: HashOf<T>,
+			documents: u32,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(documents > 0, Error::<T>::EmptyBatch);
+			ensure!(T::AnchoringAuthorizer::can_anchor(&who, &loc_id), Error::<T>::Unauthorized);
+			ensure!(!Anchors::<T>::contains_key(loc_id, root), Error::<T>::AlreadyAnchored);
+
+			Anchors::<T>::insert(
+				loc_id,
+				root,
+				Anchor {
+					loc_id,
+					submitter: who,
+					documents,
+					block_number: frame_system::Pallet::<T>::block_number(),
+					timestamp: T::UnixTime::now().as_millis() as u64,
+				},
+			);
+			Self::deposit_event(Event::RootAnchored { loc_id, root, documents });
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Returns the anchor of `root` in `loc_id` if `proof` proves the inclusion of `document`
+		/// in the tree with this root.
+		pub fn verify_inclusion(
+			loc_id: T::LocId,
+			root: HashOf<T>,
+			document: HashOf<T>,
+			proof: Vec<HashOf<T>>,
+		) -> Option<AnchorOf<T>> {
+			if proof.len() > MAX_PROOF_LENGTH {
+				return None
+			}
+			let anchor = Anchors::<T>::get(loc_id, root)?;
+			merkle::verify_proof::<HashOf<T>, T::Hasher>(&root, &document, &proof).then_some(anchor)
+		}
+	}
+}
@@ -0,0 +1,95 @@
+//! Merkle trees of document hashes.
+//!
+//! Leaves are hashed with a `0x00` prefix and inner nodes with a `0x01` prefix, so that a leaf
+//! cannot be passed off as an inner node. The children of an inner node are sorted before being
+//! hashed, a proof is thus only the list of the sibling hashes from the leaf up to the root. The
+//! last node of a level with an odd number of nodes is promoted as is to the next level.
+
+use pallet_logion_loc::Hasher;
+use sp_std::prelude::*;
+
+const LEAF_PREFIX: u8 = 0;
+const NODE_PREFIX: u8 = 1;
+
+fn leaf_hash<Hash, H>(document: &Hash) -> Hash
+where
+	Hash: AsRef<[u8]>,
+	H: Hasher<Hash>,
+{
+	let mut data = vec![LEAF_PREFIX];
+	data.extend_from_slice(document.as_ref());
+	H::hash(&data)
+}
+
+fn node_hash<Hash, H>(a: &Hash, b: &Hash) -> Hash
+where
+	Hash: AsRef<[u8]> + Ord,
+	H: Hasher<Hash>,
+{
+	let (first, second) = if a <= b { (a, b) } else { (b, a) };
+	let mut data = vec![NODE_PREFIX];
+	data.extend_from_slice(first.as_ref());
+	data.extend_from_slice(second.as_ref());
+	H::hash(&data)
+}
+
+fn next_level<Hash, H>(level: &[Hash]) -> Vec<Hash>
+where
+	Hash: AsRef<[u8]> + Ord + Clone,
+	H: Hasher<Hash>,
+{
+	level
+		.chunks(2)
+		.map(|pair| match pair {
+			[a, b] => node_hash::<Hash, H>(a, b),
+			[a] => a.clone(),
+			_ => unreachable!("chunks have 1 or 2 elements"),
+		})
+		.collect()
+}
+
+/// Computes the root of the tree of `documents`, `None` if there are no documents.
+pub fn root<Hash, H>(documents: &[Hash]) -> Option<Hash>
+where
+	Hash: AsRef<[u8]> + Ord + Clone,
+	H: Hasher<Hash>,
+{
+	let mut level: Vec<Hash> = documents.iter().map(leaf_hash::<Hash, H>).collect();
+	while level.len() > 1 {
+		level = next_level::<Hash, H>(&level);
+	}
+	level.pop()
+}
+
+/// Computes the inclusion proof of the document at `index`, `None` if out of bounds.
+pub fn proof<Hash, H>(documents: &[Hash], mut index: usize) -> Option<Vec<Hash>>
+where
+	Hash: AsRef<[u8]> + Ord + Clone,
+	H: Hasher<Hash>,
+{
+	if index >= documents.len() {
+		return None
+	}
+	let mut level: Vec<Hash> = documents.iter().map(leaf_hash::<Hash, H>).collect();
+	let mut proof = Vec::new();
+	while level.len() > 1 {
+		if let Some(sibling) = level.get(index ^ 1) {
+			proof.push(sibling.clone());
+		}
+		level = next_level::<Hash, H>(&level);
+		index /= 2;
+	}
+	Some(proof)
+}
+
+/// Returns true if `proof` proves the inclusion of `document` in the tree with given `root`.
+pub fn verify_proof<Hash, H>(root: &Hash, document: &Hash, proof: &[Hash]) -> bool
+where
+	Hash: AsRef<[u8]> + Ord,
+	H: Hasher<Hash>,
+{
+	let computed_root = proof
+		.iter()
+		.fold(leaf_hash::<Hash, H>(document), |node, sibling| node_hash::<Hash, H>(&node, sibling));
+	computed_root == *root
+}
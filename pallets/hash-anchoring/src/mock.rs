@@ -0,0 +1,75 @@
+use crate as pallet_hash_anchoring;
+use frame_support::{derive_impl, traits::UnixTime};
+use pallet_logion_loc::Hasher;
+use sp_core::H256;
+use sp_runtime::BuildStorage;
+
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test {
+		System: frame_system,
+		HashAnchoring: pallet_hash_anchoring,
+	}
+);
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig as frame_system::DefaultConfig)]
+impl frame_system::Config for Test {
+	type Block = Block;
+}
+
+pub const LOC_ID: u32 = 1;
+pub const SUBMITTER: u64 = 1;
+pub const OTHER_ACCOUNT: u64 = 2;
+pub const OTHER_LOC_ID: u32 = 2;
+pub const NOW_MILLIS: u64 = 1_700_000_000_000;
+
+pub struct AnchoringAuthorizerMock;
+impl crate::AnchoringAuthorizer<u64, u32> for AnchoringAuthorizerMock {
+	fn can_anchor(who: &u64, loc_id: &u32) -> bool {
+		(*who == SUBMITTER && *loc_id == LOC_ID) ||
+			(*who == OTHER_ACCOUNT && *loc_id == OTHER_LOC_ID)
+	}
+}
+
+pub struct UnixTimeMock;
+impl UnixTime for UnixTimeMock {
+	fn now() -> core::time::Duration {
+		core::time::Duration::from_millis(NOW_MILLIS)
+	}
+}
+
+pub struct SHA256;
+impl Hasher<H256> for SHA256 {
+	fn hash(data: &Vec<u8>) -> H256 {
+		H256(sp_io::hashing::sha2_256(data))
+	}
+}
+
+impl pallet_hash_anchoring::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type LocId = u32;
+	type Hash = H256;
+	type Hasher = SHA256;
+	type AnchoringAuthorizer = AnchoringAuthorizerMock;
+	type UnixTime = UnixTimeMock;
+	type WeightInfo = ();
+	#[cfg(feature = "runtime-benchmarks")]
+	type AnchoringSetup = AnchoringSetupMock;
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+pub struct AnchoringSetupMock;
+#[cfg(feature = "runtime-benchmarks")]
+impl crate::benchmarking::AnchoringSetup<u64, u32> for AnchoringSetupMock {
+	fn setup_anchoring() -> (u32, u64) {
+		(LOC_ID, SUBMITTER)
+	}
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut ext: sp_io::TestExternalities =
+		frame_system::GenesisConfig::<Test>::default().build_storage().unwrap().into();
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
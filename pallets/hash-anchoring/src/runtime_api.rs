@@ -0,0 +1,23 @@
+use codec::Codec;
+use sp_std::prelude::*;
+
+use crate::Anchor;
+
+sp_api::decl_runtime_apis! {
+	pub trait HashAnchoringApi<AccountId, LocId, Hash, BlockNumber> where
+		AccountId: Codec,
+		LocId: Codec,
+		Hash: Codec,
+		BlockNumber: Codec,
+	{
+		/// Returns the anchor of `root` in `loc_id` if `proof` proves the inclusion of `document`
+		/// in the tree with this root, `None` if the root is not anchored in the LOC or the proof
+		/// is invalid.
+		fn verify_inclusion(
+			loc_id: LocId,
+			root: Hash,
+			document: Hash,
+			proof: Vec<Hash>,
+		) -> Option<Anchor<AccountId, LocId, BlockNumber>>;
+	}
+}
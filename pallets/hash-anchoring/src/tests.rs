@@ -0,0 +1,161 @@
+use frame_support::{assert_err, assert_ok};
+use sp_core::H256;
+use sp_runtime::traits::BadOrigin;
+
+use crate::{merkle, mock::*, Anchor, Error, Event};
+
+fn documents(count: u8) -> Vec<H256> {
+	(0..count).map(|i| H256::repeat_byte(i)).collect()
+}
+
+fn root_of(documents: &[H256]) -> H256 {
+	merkle::root::<H256, SHA256>(documents).unwrap()
+}
+
+#[test]
+fn it_anchors_root() {
+	new_test_ext().execute_with(|| {
+		let root = root_of(&documents(3));
+
+		assert_ok!(HashAnchoring::anchor(RuntimeOrigin::signed(SUBMITTER), LOC_ID, root, 3));
+
+		assert_eq!(
+			crate::Anchors::<Test>::get(LOC_ID, root),
+			Some(Anchor {
+				loc_id: LOC_ID,
+				submitter: SUBMITTER,
+				documents: 3,
+				block_number: 1,
+				timestamp: NOW_MILLIS,
+			})
+		);
+		System::assert_last_event(
+			Event::RootAnchored { loc_id: LOC_ID, root, documents: 3 }.into(),
+		);
+	});
+}
+
+#[test]
+fn it_fails_anchoring_if_unauthorized() {
+	new_test_ext().execute_with(|| {
+		let root = root_of(&documents(3));
+		assert_err!(
+			HashAnchoring::anchor(RuntimeOrigin::signed(OTHER_ACCOUNT), LOC_ID, root, 3),
+			Error::<Test>::Unauthorized
+		);
+		assert_err!(
+			HashAnchoring::anchor(RuntimeOrigin::signed(SUBMITTER), LOC_ID + 1, root, 3),
+			Error::<Test>::Unauthorized
+		);
+		assert_err!(HashAnchoring::anchor(RuntimeOrigin::root(), LOC_ID, root, 3), BadOrigin);
+	});
+}
+
+#[test]
+fn it_fails_anchoring_twice() {
+	new_test_ext().execute_with(|| {
+		let root = root_of(&documents(3));
+		assert_ok!(HashAnchoring::anchor(RuntimeOrigin::signed(SUBMITTER), LOC_ID, root, 3));
+		assert_err!(
+			HashAnchoring::anchor(RuntimeOrigin::signed(SUBMITTER), LOC_ID, root, 3),
+			Error::<Test>::AlreadyAnchored
+		);
+	});
+}
+
+#[test]
+fn it_anchors_same_root_in_other_loc() {
+	new_test_ext().execute_with(|| {
+		let root = root_of(&documents(3));
+		assert_ok!(HashAnchoring::anchor(
+			RuntimeOrigin::signed(OTHER_ACCOUNT),
+			OTHER_LOC_ID,
+			root,
+			3
+		));
+
+		assert_ok!(HashAnchoring::anchor(RuntimeOrigin::signed(SUBMITTER), LOC_ID, root, 3));
+
+		assert_eq!(crate::Anchors::<Test>::get(LOC_ID, root).unwrap().submitter, SUBMITTER);
+		assert_eq!(
+			crate::Anchors::<Test>::get(OTHER_LOC_ID, root).unwrap().submitter,
+			OTHER_ACCOUNT
+		);
+	});
+}
+
+#[test]
+fn it_fails_anchoring_empty_batch() {
+	new_test_ext().execute_with(|| {
+		assert_err!(
+			HashAnchoring::anchor(RuntimeOrigin::signed(SUBMITTER), LOC_ID, H256::zero(), 0),
+			Error::<Test>::EmptyBatch
+		);
+	});
+}
+
+#[test]
+fn it_verifies_inclusion_of_all_documents() {
+	new_test_ext().execute_with(|| {
+		for count in [1, 2, 5, 8, 13] {
+			let documents = documents(count);
+			let root = root_of(&documents);
+			assert_ok!(HashAnchoring::anchor(
+				RuntimeOrigin::signed(SUBMITTER),
+				LOC_ID,
+				root,
+				count.into()
+			));
+
+			for (index, document) in documents.iter().enumerate() {
+				let proof = merkle::proof::<H256, SHA256>(&documents, index).unwrap();
+				let anchor =
+					HashAnchoring::verify_inclusion(LOC_ID, root, *document, proof).unwrap();
+				assert_eq!(anchor.loc_id, LOC_ID);
+				assert_eq!(anchor.timestamp, NOW_MILLIS);
+			}
+		}
+	});
+}
+
+#[test]
+fn it_rejects_invalid_proof() {
+	new_test_ext().execute_with(|| {
+		let documents = documents(5);
+		let root = root_of(&documents);
+		assert_ok!(HashAnchoring::anchor(RuntimeOrigin::signed(SUBMITTER), LOC_ID, root, 5));
+
+		let proof = merkle::proof::<H256, SHA256>(&documents, 1).unwrap();
+		assert_eq!(
+			HashAnchoring::verify_inclusion(LOC_ID, root, documents[2], proof.clone()),
+			None
+		);
+		assert_eq!(
+			HashAnchoring::verify_inclusion(LOC_ID, root, H256::repeat_byte(42), proof.clone()),
+			None
+		);
+		assert_eq!(HashAnchoring::verify_inclusion(OTHER_LOC_ID, root, documents[1], proof), None);
+	});
+}
+
+#[test]
+fn it_rejects_proof_for_unknown_root() {
+	new_test_ext().execute_with(|| {
+		let documents = documents(5);
+		let root = root_of(&documents);
+		let proof = merkle::proof::<H256, SHA256>(&documents, 0).unwrap();
+
+		assert_eq!(HashAnchoring::verify_inclusion(LOC_ID, root, documents[0], proof), None);
+	});
+}
+
+#[test]
+fn inner_node_is_not_a_valid_document() {
+	let documents = documents(4);
+	let root = root_of(&documents);
+	let leaves_proof = merkle::proof::<H256, SHA256>(&documents, 0).unwrap();
+	// The parent of the 2 first leaves, proven with the sibling of that parent.
+	let inner_node = merkle::root::<H256, SHA256>(&documents[0..2]).unwrap();
+
+	assert!(!merkle::verify_proof::<H256, SHA256>(&root, &inner_node, &leaves_proof[1..]));
+}
@@ -0,0 +1,225 @@
+//! # LOC index pallet
+//!
+//! Feeds a reverse index from hashes (files, metadata values, collection items and their files)
+//! to the LOCs they belong to, kept in the offchain database. Nothing is indexed in the chain's
+//! state: the index is only available on nodes running with `--enable-offchain-indexing true`,
+//! which expose it with the `logion_findByHash` RPC.
+//!
+//! At the end of each block, the runtime tells which LOCs and collection items changed (see
+//! [`LocChangesProvider::changed_in_block`]). They are kept in [`PendingChanges`] and read at the
+//! beginning of the next block, in `on_initialize`, so that the weight of reading them is
+//! accounted before any extrinsic. The resulting [`IndexChange`]s are written, with offchain
+//! indexing, to a journal keyed by the hash of the block which made the changes. Siblings share
+//! their parent's state, so they all write the same journal.
+//!
+//! The node merges the journals of finalized blocks into index entries keyed by hash and LOC ID,
+//! see [`IndexKey`]. A hash found in several LOCs has an entry for each, entries of void LOCs are
+//! removed.
+//!
+//! When the pallet is added to a running chain, the LOCs and collection items which already
+//! exist are indexed over the following blocks, at most [`Config::MaxBackfillPerBlock`] per
+//! block.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+use codec::{Decode, Encode};
+use frame_support::weights::Weight;
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_std::prelude::*;
+
+/// The prefix of the offchain index keys.
+pub const INDEXING_PREFIX: &[u8] = b"logion-loc-index";
+
+/// The keys of the offchain index, see [`IndexKey::offchain_key`].
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub enum IndexKey<BlockHash, Hash, LocId> {
+	/// The index changes made by a block, a `Vec<IndexChange>` written by the runtime.
+	Journal(BlockHash),
+	/// The LOCs a hash was found in, a `Vec<LocId>`.
+	Locs(Hash),
+	/// The items of a LOC a hash was found in, a `Vec<IndexedItem>`.
+	Entry(Hash, LocId),
+	/// The hashes found in a LOC, a `Vec<Hash>`.
+	Hashes(LocId),
+	/// Present if the LOC is void.
+	Void(LocId),
+	/// The number of the last block whose journal was merged.
+	LastMerged,
+}
+
+impl<BlockHash: Encode, Hash: Encode, LocId: Encode> IndexKey<BlockHash, Hash, LocId> {
+	/// Returns the key in the offchain database.
+	pub fn offchain_key(&self) -> Vec<u8> {
+		(INDEXING_PREFIX, self).encode()
+	}
+}
+
+/// The LOC item a hash was found in.
+#[derive(Encode, Decode, TypeInfo, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum IndexedItem<LocId, CollectionItemId> {
+	/// A file of a LOC.
+	File { loc_id: LocId },
+	/// The value of a metadata item of a LOC.
+	Metadata { loc_id: LocId },
+	/// The ID of a collection item.
+	CollectionItem { loc_id: LocId, item_id: CollectionItemId },
+	/// A file of a collection item.
+	CollectionItemFile { loc_id: LocId, item_id: CollectionItemId },
+}
+
+impl<LocId, CollectionItemId> IndexedItem<LocId, CollectionItemId> {
+	/// The LOC the item belongs to.
+	pub fn loc_id(&self) -> &LocId {
+		match self {
+			Self::File { loc_id } |
+			Self::Metadata { loc_id } |
+			Self::CollectionItem { loc_id, .. } |
+			Self::CollectionItemFile { loc_id, .. } => loc_id,
+		}
+	}
+}
+
+/// A LOC or a collection item to (re-)index.
+#[derive(Encode, Decode, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub enum LocChange<LocId, CollectionItemId> {
+	/// A LOC was closed or voided.
+	Loc(LocId),
+	/// An item was added to a collection.
+	CollectionItem(LocId, CollectionItemId),
+}
+
+/// A change of the index, as written in block journals.
+#[derive(Encode, Decode, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub enum IndexChange<Hash, LocId, CollectionItemId> {
+	/// A hash was found in a LOC item.
+	Add(Hash, IndexedItem<LocId, CollectionItemId>),
+	/// A LOC is void, its entries must be removed.
+	Void(LocId),
+}
+
+/// Tells which LOCs and collection items to index, and what to index.
+pub trait LocChangesProvider<Hash, LocId, CollectionItemId> {
+	/// Returns the LOCs and collection items changed in the current block, and the weight of
+	/// finding them. Called at the end of the block.
+	fn changed_in_block() -> (Vec<LocChange<LocId, CollectionItemId>>, Weight);
+
+	/// Returns at most `limit` existing LOCs and collection items, starting at `cursor` (empty
+	/// at first), the cursor of the next ones if any, and the weight of reading them.
+	fn existing(
+		cursor: &[u8],
+		limit: u32,
+	) -> (Vec<LocChange<LocId, CollectionItemId>>, Option<Vec<u8>>, Weight);
+
+	/// Returns the index changes of a LOC or collection item, and the weight of reading it.
+	fn index_changes(
+		change: &LocChange<LocId, CollectionItemId>,
+	) -> (Vec<IndexChange<Hash, LocId, CollectionItemId>>, Weight);
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+
+	#[pallet::pallet]
+	#[pallet::storage_version(STORAGE_VERSION)]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The type of LOC IDs.
+		type LocId: Parameter + Member;
+
+		/// The type of collection item IDs.
+		type CollectionItemId: Parameter + Member;
+
+		/// The type of indexed hashes.
+		type Hash: Parameter + Member;
+
+		/// Tells what to index.
+		type LocChangesProvider: LocChangesProvider<
+			<Self as Config>::Hash,
+			Self::LocId,
+			Self::CollectionItemId,
+		>;
+
+		/// The maximum number of existing LOCs or collection items indexed per block.
+		#[pallet::constant]
+		type MaxBackfillPerBlock: Get<u32>;
+	}
+
+	/// The LOCs and collection items changed in the previous block, indexed in the current one.
+	#[pallet::storage]
+	#[pallet::unbounded]
+	pub type PendingChanges<T: Config> =
+		StorageValue<_, Vec<LocChange<T::LocId, T::CollectionItemId>>, ValueQuery>;
+
+	/// The cursor of the indexing of existing LOCs and collection items, if not complete.
+	#[pallet::storage]
+	#[pallet::unbounded]
+	pub type Backfill<T: Config> = StorageValue<_, Vec<u8>, OptionQuery>;
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_runtime_upgrade() -> Weight {
+			if StorageVersion::get::<Pallet<T>>() < STORAGE_VERSION {
+				Backfill::<T>::put(Vec::<u8>::new());
+				STORAGE_VERSION.put::<Pallet<T>>();
+				T::DbWeight::get().reads_writes(1, 2)
+			} else {
+				T::DbWeight::get().reads(1)
+			}
+		}
+
+		fn on_initialize(_: BlockNumberFor<T>) -> Weight {
+			let db_weight = T::DbWeight::get();
+			// Taking the pending changes and the backfill cursor, then writing the changes of
+			// this block in `on_finalize`.
+			let mut weight = db_weight.reads_writes(2, 2);
+			let mut changes = PendingChanges::<T>::take();
+			if let Some(cursor) = Backfill::<T>::get() {
+				let (existing, next, backfill_weight) =
+					T::LocChangesProvider::existing(&cursor, T::MaxBackfillPerBlock::get());
+				changes.extend(existing);
+				Backfill::<T>::set(next);
+				weight = weight.saturating_add(backfill_weight).saturating_add(db_weight.writes(1));
+			}
+
+			let mut journal = Vec::new();
+			for change in changes.iter() {
+				let (index_changes, index_weight) = T::LocChangesProvider::index_changes(change);
+				journal.extend(index_changes);
+				weight = weight.saturating_add(index_weight);
+			}
+			if !journal.is_empty() {
+				let key = IndexKey::<_, (), ()>::Journal(frame_system::Pallet::<T>::parent_hash());
+				sp_io::offchain_index::set(&key.offchain_key(), &journal.encode());
+			}
+			weight
+		}
+
+		fn on_finalize(_: BlockNumberFor<T>) {
+			let (changes, weight) = T::LocChangesProvider::changed_in_block();
+			frame_system::Pallet::<T>::register_extra_weight_unchecked(
+				weight,
+				DispatchClass::Mandatory,
+			);
+			if !changes.is_empty() {
+				PendingChanges::<T>::put(changes);
+			}
+		}
+	}
+}
@@ -0,0 +1,81 @@
+use crate::{self as pallet_loc_index, IndexChange, IndexedItem, LocChange};
+use codec::{Decode, Encode};
+use frame_support::{derive_impl, parameter_types, weights::Weight};
+use sp_core::H256;
+use sp_runtime::BuildStorage;
+use std::cell::RefCell;
+
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test {
+		System: frame_system,
+		LocIndex: pallet_loc_index,
+	}
+);
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig as frame_system::DefaultConfig)]
+impl frame_system::Config for Test {
+	type Block = Block;
+}
+
+pub type Change = LocChange<u32, H256>;
+
+/// The weight of finding or indexing a change.
+pub const CHANGE_WEIGHT: Weight = Weight::from_parts(1_000, 0);
+
+thread_local! {
+	pub static CHANGED_IN_BLOCK: RefCell<Vec<Change>> = RefCell::new(vec![]);
+	pub static EXISTING: RefCell<Vec<Change>> = RefCell::new(vec![]);
+}
+
+/// Indexes the LOC itself as a file, or the item itself as a collection item.
+pub fn index_changes(change: &Change) -> Vec<IndexChange<H256, u32, H256>> {
+	match change {
+		LocChange::Loc(loc_id) =>
+			vec![IndexChange::Add(H256::from_low_u64_be(*loc_id as u64), IndexedItem::File {
+				loc_id: *loc_id,
+			})],
+		LocChange::CollectionItem(loc_id, item_id) => vec![IndexChange::Add(
+			*item_id,
+			IndexedItem::CollectionItem { loc_id: *loc_id, item_id: *item_id },
+		)],
+	}
+}
+
+pub struct LocChangesProviderMock;
+impl crate::LocChangesProvider<H256, u32, H256> for LocChangesProviderMock {
+	fn changed_in_block() -> (Vec<Change>, Weight) {
+		let changes = CHANGED_IN_BLOCK.with(|changes| changes.borrow().clone());
+		let weight = CHANGE_WEIGHT.saturating_mul(changes.len() as u64);
+		(changes, weight)
+	}
+
+	fn existing(cursor: &[u8], limit: u32) -> (Vec<Change>, Option<Vec<u8>>, Weight) {
+		let start = u32::decode(&mut &cursor[..]).unwrap_or(0) as usize;
+		let existing = EXISTING.with(|existing| existing.borrow().clone());
+		let end = existing.len().min(start + limit as usize);
+		let next = (end < existing.len()).then(|| (end as u32).encode());
+		(existing[start..end].to_vec(), next, CHANGE_WEIGHT.saturating_mul((end - start) as u64))
+	}
+
+	fn index_changes(change: &Change) -> (Vec<IndexChange<H256, u32, H256>>, Weight) {
+		(index_changes(change), CHANGE_WEIGHT)
+	}
+}
+
+parameter_types! {
+	pub const MaxBackfillPerBlock: u32 = 2;
+}
+
+impl pallet_loc_index::Config for Test {
+	type LocId = u32;
+	type CollectionItemId = H256;
+	type Hash = H256;
+	type LocChangesProvider = LocChangesProviderMock;
+	type MaxBackfillPerBlock = MaxBackfillPerBlock;
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	frame_system::GenesisConfig::<Test>::default().build_storage().unwrap().into()
+}
@@ -0,0 +1,78 @@
+use codec::Encode;
+use frame_support::{
+	dispatch::DispatchClass,
+	traits::{Get, GetStorageVersion, Hooks, StorageVersion},
+};
+use sp_core::H256;
+
+use crate::{mock::*, Backfill, IndexChange, IndexKey, LocChange, PendingChanges};
+
+fn journal_key(block_hash: H256) -> Vec<u8> {
+	IndexKey::<_, (), ()>::Journal(block_hash).offchain_key()
+}
+
+fn journal_of(changes: &[Change]) -> Vec<u8> {
+	changes.iter().flat_map(index_changes).collect::<Vec<IndexChange<_, _, _>>>().encode()
+}
+
+#[test]
+fn it_indexes_changes_at_beginning_of_next_block() {
+	let item_id = H256::repeat_byte(2);
+	let changes = vec![LocChange::Loc(1), LocChange::CollectionItem(2, item_id)];
+	CHANGED_IN_BLOCK.with(|changed| *changed.borrow_mut() = changes.clone());
+
+	let parent_hash = H256::repeat_byte(42);
+	let mut ext = new_test_ext();
+	ext.execute_with(|| {
+		System::set_block_number(1);
+		LocIndex::on_finalize(1);
+		assert_eq!(PendingChanges::<Test>::get(), changes);
+		assert_eq!(
+			System::block_weight().get(DispatchClass::Mandatory).ref_time(),
+			CHANGE_WEIGHT.ref_time() * 2
+		);
+
+		CHANGED_IN_BLOCK.with(|changed| changed.borrow_mut().clear());
+		System::initialize(&2, &parent_hash, &Default::default());
+		let weight = LocIndex::on_initialize(2);
+		assert!(weight.ref_time() >= CHANGE_WEIGHT.ref_time() * 2);
+		assert!(PendingChanges::<Test>::get().is_empty());
+		LocIndex::on_finalize(2);
+		assert!(!PendingChanges::<Test>::exists());
+	});
+	ext.persist_offchain_overlay();
+
+	let offchain_db = ext.offchain_db();
+	assert_eq!(offchain_db.get(&journal_key(parent_hash)), Some(journal_of(&changes)));
+	assert_eq!(offchain_db.get(&journal_key(H256::repeat_byte(3))), None);
+}
+
+#[test]
+fn it_indexes_existing_locs_after_upgrade() {
+	let existing: Vec<Change> = (1..=5).map(LocChange::Loc).collect();
+	EXISTING.with(|to_backfill| *to_backfill.borrow_mut() = existing.clone());
+	CHANGED_IN_BLOCK.with(|changed| changed.borrow_mut().clear());
+
+	let mut ext = new_test_ext();
+	ext.execute_with(|| {
+		StorageVersion::new(0).put::<LocIndex>();
+		LocIndex::on_runtime_upgrade();
+		assert_eq!(LocIndex::on_chain_storage_version(), 1);
+		assert_eq!(Backfill::<Test>::get(), Some(vec![]));
+		LocIndex::on_runtime_upgrade();
+		assert_eq!(Backfill::<Test>::get(), Some(vec![]));
+
+		for block in 1..=3u64 {
+			System::initialize(&block, &H256::from_low_u64_be(block), &Default::default());
+			LocIndex::on_initialize(block);
+		}
+		assert_eq!(Backfill::<Test>::get(), None);
+	});
+	ext.persist_offchain_overlay();
+
+	let offchain_db = ext.offchain_db();
+	for (block, batch) in existing.chunks(MaxBackfillPerBlock::get() as usize).enumerate() {
+		let parent_hash = H256::from_low_u64_be(block as u64 + 1);
+		assert_eq!(offchain_db.get(&journal_key(parent_hash)), Some(journal_of(batch)));
+	}
+}
@@ -0,0 +1,239 @@
+//! # Reward accounting pallet
+//!
+//! Keeps track of the cumulative amounts paid out to the logion and community treasuries, and to
+//! the legal officers, per category of inflow (inclusion fees, inflation, storage fees...).
+//!
+//! The pallet does not move any funds. The runtime's reward distributors record each payout, with
+//! its category, using [`Pallet::record_treasury_inflow`] or
+//! [`Pallet::record_legal_officer_inflow`].
+//!
+//! Distributors which cannot know the category of a payout (e.g. the one of LOC fees, shared by
+//! several kinds of fees) may read [`Pallet::current_category`]. It is a transient value, set with
+//! [`Pallet::set_current_category`] right before a distribution and killed at the end of the
+//! block.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub use pallet::*;
+
+pub mod runtime_api;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_runtime::traits::Saturating;
+use sp_std::prelude::*;
+
+/// The source of the funds received by a treasury or a legal officer.
+#[derive(
+	Encode, Decode, MaxEncodedLen, TypeInfo, Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize,
+)]
+pub enum InflowCategory {
+	InclusionFees,
+	Inflation,
+	StorageFees,
+	CertificateFees,
+	ValueFees,
+	RecurrentFees,
+	LegalFees,
+	/// Funds distributed without a category.
+	Other,
+}
+
+/// A treasury receiving a share of the distributed rewards.
+#[derive(
+	Encode, Decode, MaxEncodedLen, TypeInfo, Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize,
+)]
+pub enum Treasury {
+	Logion,
+	Community,
+}
+
+/// Cumulative inflows per category.
+pub type CategoryInflows<Balance> = Vec<(InflowCategory, Balance)>;
+
+/// Cumulative inflows of the treasuries and legal officers.
+#[derive(Encode, Decode, TypeInfo, Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Inflows<AccountId, Balance> {
+	pub logion_treasury: CategoryInflows<Balance>,
+	pub community_treasury: CategoryInflows<Balance>,
+	pub legal_officers: Vec<(AccountId, CategoryInflows<Balance>)>,
+}
+
+impl<AccountId: PartialEq, Balance: Saturating + Copy> Inflows<AccountId, Balance> {
+	/// Returns the inflows received since `earlier`, given that `self` and `earlier` are
+	/// cumulative inflows taken at two different blocks.
+	pub fn since(self, earlier: &Self) -> Self {
+		Self {
+			logion_treasury: categories_since(self.logion_treasury, &earlier.logion_treasury),
+			community_treasury: categories_since(
+				self.community_treasury,
+				&earlier.community_treasury,
+			),
+			legal_officers: self
+				.legal_officers
+				.into_iter()
+				.map(|(legal_officer, inflows)| {
+					let earlier_inflows = earlier
+						.legal_officers
+						.iter()
+						.find(|(earlier_legal_officer, _)| *earlier_legal_officer == legal_officer)
+						.map(|(_, inflows)| inflows.as_slice())
+						.unwrap_or_default();
+					(legal_officer, categories_since(inflows, earlier_inflows))
+				})
+				.collect(),
+		}
+	}
+
+	/// Converts all balances with `f`.
+	pub fn map_balances<B>(self, f: impl Fn(Balance) -> B) -> Inflows<AccountId, B> {
+		let map_categories = |inflows: CategoryInflows<Balance>| {
+			inflows.into_iter().map(|(category, amount)| (category, f(amount))).collect()
+		};
+		Inflows {
+			logion_treasury: map_categories(self.logion_treasury),
+			community_treasury: map_categories(self.community_treasury),
+			legal_officers: self
+				.legal_officers
+				.into_iter()
+				.map(|(legal_officer, inflows)| (legal_officer, map_categories(inflows)))
+				.collect(),
+		}
+	}
+}
+
+fn categories_since<Balance: Saturating + Copy>(
+	inflows: CategoryInflows<Balance>,
+	earlier: &[(InflowCategory, Balance)],
+) -> CategoryInflows<Balance> {
+	inflows
+		.into_iter()
+		.map(|(category, amount)| {
+			let earlier_amount = earlier
+				.iter()
+				.find(|(earlier_category, _)| *earlier_category == category)
+				.map(|(_, earlier_amount)| *earlier_amount);
+			match earlier_amount {
+				Some(earlier_amount) => (category, amount.saturating_sub(earlier_amount)),
+				None => (category, amount),
+			}
+		})
+		.collect()
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+	use sp_runtime::traits::AtLeast32BitUnsigned;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The type of the recorded amounts.
+		type Balance: Parameter + Member + AtLeast32BitUnsigned + Default + Copy + MaxEncodedLen;
+	}
+
+	/// Cumulative inflows of each treasury, per category.
+	#[pallet::storage]
+	pub type TreasuryInflows<T: Config> = StorageDoubleMap<
+		_,
+		Twox64Concat,
+		Treasury,
+		Twox64Concat,
+		InflowCategory,
+		T::Balance,
+		ValueQuery,
+	>;
+
+	/// Cumulative inflows of each legal officer, per category.
+	#[pallet::storage]
+	pub type LegalOfficerInflows<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Twox64Concat,
+		InflowCategory,
+		T::Balance,
+		ValueQuery,
+	>;
+
+	/// The category of the distribution in progress. Transient, never kept past the end of the
+	/// block.
+	#[pallet::storage]
+	#[pallet::whitelist_storage]
+	pub type CurrentCategory<T: Config> = StorageValue<_, InflowCategory, OptionQuery>;
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(_n: BlockNumberFor<T>) -> Weight {
+			// The kill of `on_finalize`.
+			T::DbWeight::get().writes(1)
+		}
+
+		fn on_finalize(_n: BlockNumberFor<T>) {
+			CurrentCategory::<T>::kill();
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Sets the category of the distribution about to start.
+		pub fn set_current_category(category: InflowCategory) {
+			CurrentCategory::<T>::put(category);
+		}
+
+		/// The category set for the distribution in progress, `Other` if none.
+		pub fn current_category() -> InflowCategory {
+			CurrentCategory::<T>::get().unwrap_or(InflowCategory::Other)
+		}
+
+		/// Records a payout to given treasury.
+		pub fn record_treasury_inflow(
+			treasury: Treasury,
+			category: InflowCategory,
+			amount: T::Balance,
+		) {
+			TreasuryInflows::<T>::mutate(treasury, category, |total| {
+				total.saturating_accrue(amount)
+			});
+		}
+
+		/// Records a payout to given legal officer.
+		pub fn record_legal_officer_inflow(
+			legal_officer: &T::AccountId,
+			category: InflowCategory,
+			amount: T::Balance,
+		) {
+			LegalOfficerInflows::<T>::mutate(legal_officer, category, |total| {
+				total.saturating_accrue(amount)
+			});
+		}
+
+		/// Returns all cumulative inflows recorded so far.
+		pub fn inflows() -> Inflows<T::AccountId, T::Balance> {
+			let mut legal_officers: Vec<(T::AccountId, CategoryInflows<T::Balance>)> = Vec::new();
+			for (legal_officer, category, amount) in LegalOfficerInflows::<T>::iter() {
+				match legal_officers.iter_mut().find(|(account, _)| *account == legal_officer) {
+					Some((_, inflows)) => inflows.push((category, amount)),
+					None => legal_officers.push((legal_officer, vec![(category, amount)])),
+				}
+			}
+			Inflows {
+				logion_treasury: TreasuryInflows::<T>::iter_prefix(Treasury::Logion).collect(),
+				community_treasury: TreasuryInflows::<T>::iter_prefix(Treasury::Community)
+					.collect(),
+				legal_officers,
+			}
+		}
+	}
+}
@@ -0,0 +1,14 @@
+use codec::Codec;
+
+use crate::Inflows;
+
+sp_api::decl_runtime_apis! {
+	pub trait RewardAccountingApi<AccountId, Balance> where
+		AccountId: Codec,
+		Balance: Codec,
+	{
+		/// Returns the cumulative inflows of the treasuries and legal officers, up to the
+		/// queried block included.
+		fn inflows() -> Inflows<AccountId, Balance>;
+	}
+}
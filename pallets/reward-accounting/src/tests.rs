@@ -0,0 +1,80 @@
+use crate::{mock::*, InflowCategory, Inflows, Treasury};
+use frame_support::traits::Hooks;
+
+const LEGAL_OFFICER: u64 = 1;
+
+#[test]
+fn it_records_inflows_per_category() {
+	new_test_ext().execute_with(|| {
+		RewardAccounting::record_treasury_inflow(Treasury::Logion, InflowCategory::Inflation, 35);
+		RewardAccounting::record_treasury_inflow(
+			Treasury::Community,
+			InflowCategory::Inflation,
+			30,
+		);
+		RewardAccounting::record_legal_officer_inflow(
+			&LEGAL_OFFICER,
+			InflowCategory::Inflation,
+			35,
+		);
+
+		RewardAccounting::record_treasury_inflow(
+			Treasury::Community,
+			InflowCategory::StorageFees,
+			20,
+		);
+		RewardAccounting::record_legal_officer_inflow(
+			&LEGAL_OFFICER,
+			InflowCategory::StorageFees,
+			80,
+		);
+
+		RewardAccounting::record_treasury_inflow(Treasury::Logion, InflowCategory::Inflation, 35);
+
+		let inflows = RewardAccounting::inflows();
+		assert_eq!(inflows.logion_treasury, vec![(InflowCategory::Inflation, 70)]);
+		assert_eq!(inflows.community_treasury.len(), 2);
+		assert!(inflows.community_treasury.contains(&(InflowCategory::Inflation, 30)));
+		assert!(inflows.community_treasury.contains(&(InflowCategory::StorageFees, 20)));
+		assert_eq!(inflows.legal_officers.len(), 1);
+		assert_eq!(inflows.legal_officers[0].0, LEGAL_OFFICER);
+		assert!(inflows.legal_officers[0].1.contains(&(InflowCategory::Inflation, 35)));
+		assert!(inflows.legal_officers[0].1.contains(&(InflowCategory::StorageFees, 80)));
+	});
+}
+
+#[test]
+fn it_clears_current_category_at_end_of_block() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(RewardAccounting::current_category(), InflowCategory::Other);
+
+		RewardAccounting::set_current_category(InflowCategory::StorageFees);
+		assert_eq!(RewardAccounting::current_category(), InflowCategory::StorageFees);
+
+		RewardAccounting::on_finalize(1);
+		assert_eq!(RewardAccounting::current_category(), InflowCategory::Other);
+	});
+}
+
+#[test]
+fn it_computes_inflows_between_two_snapshots() {
+	let earlier: Inflows<u64, u128> = Inflows {
+		logion_treasury: vec![(InflowCategory::Inflation, 70)],
+		community_treasury: vec![],
+		legal_officers: vec![(LEGAL_OFFICER, vec![(InflowCategory::Inflation, 35)])],
+	};
+	let later: Inflows<u64, u128> = Inflows {
+		logion_treasury: vec![(InflowCategory::Inflation, 105), (InflowCategory::InclusionFees, 5)],
+		community_treasury: vec![(InflowCategory::Inflation, 30)],
+		legal_officers: vec![(LEGAL_OFFICER, vec![(InflowCategory::Inflation, 70)])],
+	};
+
+	let inflows = later.since(&earlier);
+
+	assert_eq!(
+		inflows.logion_treasury,
+		vec![(InflowCategory::Inflation, 35), (InflowCategory::InclusionFees, 5)]
+	);
+	assert_eq!(inflows.community_treasury, vec![(InflowCategory::Inflation, 30)]);
+	assert_eq!(inflows.legal_officers, vec![(LEGAL_OFFICER, vec![(InflowCategory::Inflation, 35)])]);
+}
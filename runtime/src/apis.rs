@@ -0,0 +1,258 @@
+//! Runtime APIs specific to the logion runtime.
+
+use codec::{Codec, Decode, Encode};
+use frame_support::{dispatch::DispatchResultWithPostInfo, weights::Weight};
+use pallet_transaction_payment::FeeDetails;
+use logion_shared::DistributionKey;
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_consensus_aura::sr25519::AuthorityId as AuraId;
+use sp_consensus_grandpa::AuthorityId as GrandpaId;
+use sp_core::OpaquePeerId;
+use sp_runtime::{DispatchError, Percent, Permill};
+use sp_std::prelude::*;
+
+/// The origin a call is dry-run with.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug, TypeInfo)]
+pub enum DryRunOrigin<AccountId> {
+	Root,
+	Signed(AccountId),
+}
+
+/// The outcome of a dry-run.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug, TypeInfo)]
+pub struct DryRunResult<Event, Balance> {
+	/// The result of the dispatch.
+	pub result: DispatchResultWithPostInfo,
+	/// The events deposited by the call.
+	pub events: Vec<Event>,
+	/// The weight actually used by the call.
+	pub weight: Weight,
+	/// The inclusion fee that a signed extrinsic wrapping the call would pay.
+	pub fee: FeeDetails<Balance>,
+}
+
+/// A human-readable description of a `DispatchError`.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug, TypeInfo)]
+pub struct DecodedError {
+	/// The pallet that returned the error, `None` if the error is not a module error.
+	pub pallet: Option<PalletErrorOrigin>,
+	/// The name of the error variant (e.g. `CannotCloseUnacknowledged` or `BadOrigin`).
+	pub error: Vec<u8>,
+	/// The index of the error variant.
+	pub error_index: u8,
+	/// The doc comment of the error variant.
+	pub docs: Vec<u8>,
+}
+
+/// The pallet a module error comes from.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug, TypeInfo)]
+pub struct PalletErrorOrigin {
+	/// The name of the pallet in the runtime (e.g. `LogionLoc`).
+	pub name: Vec<u8>,
+	/// The index of the pallet in the runtime.
+	pub index: u8,
+}
+
+/// The limits, fees and periods configured in the runtime.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug, TypeInfo, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogionConfig<Balance, BlockNumber> {
+	/// Maximum number of open LOCs per account.
+	pub max_account_locs: u32,
+	/// Maximum number of metadata items in a LOC.
+	pub max_loc_metadata: u32,
+	/// Maximum number of files in a LOC.
+	pub max_loc_files: u32,
+	/// Maximum number of links in a LOC.
+	pub max_loc_links: u32,
+	/// Maximum number of files in a collection item.
+	pub max_collection_item_files: u32,
+	/// Maximum number of terms and conditions in a collection item.
+	pub max_collection_item_tcs: u32,
+	/// Maximum number of files in a tokens record.
+	pub max_tokens_record_files: u32,
+	/// Maximum number of ballots in a vote.
+	pub max_ballots: u32,
+	/// Maximum number of signatories of a multisig.
+	pub max_signatories: u16,
+	/// Maximum number of friends of a recoverable account.
+	pub max_friends: u16,
+	/// Maximum number of well-known nodes.
+	pub max_well_known_nodes: u32,
+	/// Maximum length of a legal officer's base URL.
+	pub max_base_url_len: u32,
+	/// Maximum length of a legal officer's peer ID.
+	pub max_peer_id_length: u32,
+	/// The minimum balance an account must hold.
+	pub existential_deposit: Balance,
+	/// The base deposit of a multisig operation.
+	pub multisig_deposit_base: Balance,
+	/// The deposit of a multisig operation per signatory.
+	pub multisig_deposit_factor: Balance,
+	/// The base deposit of a recovery configuration.
+	pub recovery_config_deposit_base: Balance,
+	/// The deposit of a recovery configuration per friend.
+	pub recovery_friend_deposit_factor: Balance,
+	/// The deposit of a recovery process.
+	pub recovery_deposit: Balance,
+	/// The file storage fee per byte.
+	pub file_storage_byte_fee: Balance,
+	/// The file storage fee per file.
+	pub file_storage_entry_fee: Balance,
+	/// The certificate fee per token.
+	pub certificate_fee: Balance,
+	/// The amount minted at every block.
+	pub inflation_amount: Balance,
+	/// The factor converting weight and length to inclusion fee.
+	pub weight_to_fee_multiplier: Balance,
+	/// The bond of a treasury proposal.
+	pub proposal_bond: Permill,
+	/// The minimum bond of a treasury proposal.
+	pub proposal_bond_minimum: Balance,
+	/// The period between treasury spends.
+	pub spend_period: BlockNumber,
+	/// The period during which an approved treasury spend can be claimed.
+	pub spend_payout_period: BlockNumber,
+	/// The length of a session.
+	pub session_period: BlockNumber,
+	/// The expected duration of a block, in milliseconds.
+	pub block_duration_millis: u64,
+	/// The distribution keys of the fees and of the inflation.
+	pub distribution_keys: DistributionKeys,
+}
+
+impl<Balance, BlockNumber> LogionConfig<Balance, BlockNumber> {
+	/// Converts all balances with `f`.
+	pub fn map_balances<B>(self, f: impl Fn(Balance) -> B) -> LogionConfig<B, BlockNumber> {
+		LogionConfig {
+			max_account_locs: self.max_account_locs,
+			max_loc_metadata: self.max_loc_metadata,
+			max_loc_files: self.max_loc_files,
+			max_loc_links: self.max_loc_links,
+			max_collection_item_files: self.max_collection_item_files,
+			max_collection_item_tcs: self.max_collection_item_tcs,
+			max_tokens_record_files: self.max_tokens_record_files,
+			max_ballots: self.max_ballots,
+			max_signatories: self.max_signatories,
+			max_friends: self.max_friends,
+			max_well_known_nodes: self.max_well_known_nodes,
+			max_base_url_len: self.max_base_url_len,
+			max_peer_id_length: self.max_peer_id_length,
+			existential_deposit: f(self.existential_deposit),
+			multisig_deposit_base: f(self.multisig_deposit_base),
+			multisig_deposit_factor: f(self.multisig_deposit_factor),
+			recovery_config_deposit_base: f(self.recovery_config_deposit_base),
+			recovery_friend_deposit_factor: f(self.recovery_friend_deposit_factor),
+			recovery_deposit: f(self.recovery_deposit),
+			file_storage_byte_fee: f(self.file_storage_byte_fee),
+			file_storage_entry_fee: f(self.file_storage_entry_fee),
+			certificate_fee: f(self.certificate_fee),
+			inflation_amount: f(self.inflation_amount),
+			weight_to_fee_multiplier: f(self.weight_to_fee_multiplier),
+			proposal_bond_minimum: f(self.proposal_bond_minimum),
+			proposal_bond: self.proposal_bond,
+			spend_period: self.spend_period,
+			spend_payout_period: self.spend_payout_period,
+			session_period: self.session_period,
+			block_duration_millis: self.block_duration_millis,
+			distribution_keys: self.distribution_keys,
+		}
+	}
+}
+
+/// The distribution keys of the runtime.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug, TypeInfo, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DistributionKeys {
+	pub inclusion_fees: DistributionKeyShares,
+	pub inflation: DistributionKeyShares,
+	pub file_storage_fee: DistributionKeyShares,
+	pub certificate_fee: DistributionKeyShares,
+	pub value_fee: DistributionKeyShares,
+	pub collection_item_fee: DistributionKeyShares,
+	pub tokens_record_fee: DistributionKeyShares,
+	pub identity_loc_legal_fee: DistributionKeyShares,
+	pub transaction_loc_legal_fee: DistributionKeyShares,
+	pub collection_loc_legal_fee: DistributionKeyShares,
+}
+
+/// The shares of a [`DistributionKey`].
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug, TypeInfo, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DistributionKeyShares {
+	pub legal_officers_percent: Percent,
+	pub community_treasury_percent: Percent,
+	pub logion_treasury_percent: Percent,
+	pub loc_owner_percent: Percent,
+}
+
+impl From<DistributionKey> for DistributionKeyShares {
+	fn from(key: DistributionKey) -> Self {
+		Self {
+			legal_officers_percent: key.legal_officers_percent,
+			community_treasury_percent: key.community_treasury_percent,
+			logion_treasury_percent: key.logion_treasury_percent,
+			loc_owner_percent: key.loc_owner_percent,
+		}
+	}
+}
+
+/// The on-chain registration of the validator owning a given Aura key.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug, TypeInfo)]
+pub struct ValidatorRegistration<AccountId> {
+	/// The account which set the Aura key as next session key.
+	pub account: AccountId,
+	/// The GRANDPA key set by `account` as next session key.
+	pub grandpa_key: Option<GrandpaId>,
+	/// True if `account` is in the validator set.
+	pub is_validator: bool,
+	/// True if `account` is a legal officer.
+	pub is_legal_officer: bool,
+	/// The node registered in `LoAuthorityList` for `account`, through its host if `account` is
+	/// a guest legal officer.
+	pub node_id: Option<OpaquePeerId>,
+}
+
+sp_api::decl_runtime_apis! {
+	/// Executes calls against the state of a given block and rolls back all changes.
+	pub trait DryRunApi<AccountId, Call, Event, Balance> where
+		AccountId: Codec,
+		Call: Codec,
+		Event: Codec,
+		Balance: Codec,
+	{
+		/// Dispatches `call` with given origin.
+		fn dry_run(origin: DryRunOrigin<AccountId>, call: Call) -> DryRunResult<Event, Balance>;
+	}
+
+	/// Maps dispatch errors to the names and docs of the runtime's metadata.
+	pub trait ErrorDecoderApi {
+		/// Describes given error, `None` if the error is unknown to the runtime.
+		fn decode_error(error: DispatchError) -> Option<DecodedError>;
+	}
+
+	/// Exposes the runtime's configuration, including values missing from pallet constants.
+	pub trait LogionConfigApi<Balance, BlockNumber> where
+		Balance: Codec,
+		BlockNumber: Codec,
+	{
+		/// Returns the limits, fees and periods configured in the runtime.
+		fn logion_config() -> LogionConfig<Balance, BlockNumber>;
+	}
+
+	/// Exposes the nodes of the legal officers, i.e. the nodes allowed in a permissioned network.
+	pub trait LegalOfficerNodesApi {
+		/// Returns the peer IDs of the nodes registered by legal officers.
+		fn legal_officer_nodes() -> Vec<OpaquePeerId>;
+	}
+
+	/// Exposes what the chain knows about a validator, so that a node can check its setup.
+	pub trait ValidatorRegistrationApi<AccountId> where
+		AccountId: Codec,
+	{
+		/// Returns the registration of the validator whose next session keys include `aura_key`,
+		/// `None` if no account set it as session key.
+		fn validator_registration(aura_key: AuraId) -> Option<ValidatorRegistration<AccountId>>;
+	}
+}
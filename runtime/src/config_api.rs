@@ -0,0 +1,62 @@
+//! Snapshot of the runtime's configuration.
+
+use crate::{
+	apis::{DistributionKeys, LogionConfig},
+	Balance, BlockNumber, CertificateFee, CertificateFeeDistributionKey, FileStorageByteFee,
+	FileStorageEntryFee, FileStorageFeeDistributionKey, IdentityLocLegalFeeDistributionKey,
+	InclusionFeesDistributionKey, InflationAmount, InflationDistributionKey, MaxAccountLocs,
+	MaxBallots, MaxBaseUrlLen, MaxCollectionItemFiles, MaxCollectionItemTCs, MaxFriends,
+	MaxLocFiles, MaxLocLinks, MaxLocMetadata, MaxPeerIdLength, MaxSignatories,
+	MaxTokensRecordFiles, MaxWellKnownNodes, MultiSigDepositBase, MultiSigDepositFactor,
+	OtherLocLegalFeeDistributionKey, Period, ProposalBond, ProposalBondMinimum,
+	RecoveryConfigDepositBase, RecoveryDeposit, RecoveryFrieldDepositFactor,
+	RecurentFeeDistributionKey, SpendPayoutPeriod, SpendPeriod, ValueFeeDistributionKey,
+	WeightToFeeMultiplier, EXISTENTIAL_DEPOSIT, MILLISECS_PER_BLOCK,
+};
+
+pub fn logion_config() -> LogionConfig<Balance, BlockNumber> {
+	LogionConfig {
+		max_account_locs: MaxAccountLocs::get(),
+		max_loc_metadata: MaxLocMetadata::get(),
+		max_loc_files: MaxLocFiles::get(),
+		max_loc_links: MaxLocLinks::get(),
+		max_collection_item_files: MaxCollectionItemFiles::get(),
+		max_collection_item_tcs: MaxCollectionItemTCs::get(),
+		max_tokens_record_files: MaxTokensRecordFiles::get(),
+		max_ballots: MaxBallots::get(),
+		max_signatories: MaxSignatories::get(),
+		max_friends: MaxFriends::get(),
+		max_well_known_nodes: MaxWellKnownNodes::get(),
+		max_base_url_len: MaxBaseUrlLen::get(),
+		max_peer_id_length: MaxPeerIdLength::get(),
+		existential_deposit: EXISTENTIAL_DEPOSIT,
+		multisig_deposit_base: MultiSigDepositBase::get(),
+		multisig_deposit_factor: MultiSigDepositFactor::get(),
+		recovery_config_deposit_base: RecoveryConfigDepositBase::get().into(),
+		recovery_friend_deposit_factor: RecoveryFrieldDepositFactor::get().into(),
+		recovery_deposit: RecoveryDeposit::get().into(),
+		file_storage_byte_fee: FileStorageByteFee::get(),
+		file_storage_entry_fee: FileStorageEntryFee::get(),
+		certificate_fee: CertificateFee::get(),
+		inflation_amount: InflationAmount::get(),
+		weight_to_fee_multiplier: WeightToFeeMultiplier::get(),
+		proposal_bond: ProposalBond::get(),
+		proposal_bond_minimum: ProposalBondMinimum::get(),
+		spend_period: SpendPeriod::get(),
+		spend_payout_period: SpendPayoutPeriod::get(),
+		session_period: Period::get(),
+		block_duration_millis: MILLISECS_PER_BLOCK,
+		distribution_keys: DistributionKeys {
+			inclusion_fees: InclusionFeesDistributionKey::get().into(),
+			inflation: InflationDistributionKey::get().into(),
+			file_storage_fee: FileStorageFeeDistributionKey::get().into(),
+			certificate_fee: CertificateFeeDistributionKey::get().into(),
+			value_fee: ValueFeeDistributionKey::get().into(),
+			collection_item_fee: RecurentFeeDistributionKey::get().into(),
+			tokens_record_fee: RecurentFeeDistributionKey::get().into(),
+			identity_loc_legal_fee: IdentityLocLegalFeeDistributionKey::get().into(),
+			transaction_loc_legal_fee: OtherLocLegalFeeDistributionKey::get().into(),
+			collection_loc_legal_fee: OtherLocLegalFeeDistributionKey::get().into(),
+		},
+	}
+}
@@ -0,0 +1,62 @@
+//! Execution of calls against the current state without persisting any change.
+
+use crate::{
+	apis::{DryRunOrigin, DryRunResult},
+	AccountId, Balance, BlockWeights, RuntimeCall, RuntimeEvent, RuntimeOrigin, System,
+	TransactionPayment, Weight,
+};
+use codec::Encode;
+use frame_support::{
+	dispatch::GetDispatchInfo,
+	storage::{with_transaction, TransactionOutcome},
+};
+use sp_runtime::{traits::Dispatchable, DispatchError};
+use sp_std::prelude::*;
+
+pub fn dry_run(
+	origin: DryRunOrigin<AccountId>,
+	call: RuntimeCall,
+) -> DryRunResult<RuntimeEvent, Balance> {
+	let info = call.get_dispatch_info();
+	// Signature and signed extensions are not known, only the call contributes to the length fee.
+	let len = call.encoded_size() as u32;
+	// Like block authoring, do not execute calls which would not fit in an extrinsic.
+	let weights = BlockWeights::get();
+	let max_weight = weights.get(info.class).max_extrinsic.unwrap_or(weights.max_block);
+	if info.weight.any_gt(max_weight) {
+		return DryRunResult {
+			result: Err(DispatchError::Exhausted.into()),
+			events: Vec::new(),
+			weight: Weight::zero(),
+			fee: TransactionPayment::compute_fee_details(len, &info, 0),
+		}
+	}
+	let origin = match origin {
+		DryRunOrigin::Root => RuntimeOrigin::root(),
+		DryRunOrigin::Signed(account) => RuntimeOrigin::signed(account),
+	};
+
+	let (result, events) = with_transaction(|| {
+		// The state is the one of an imported block: drop its events and move to the next
+		// block number, events being ignored at genesis.
+		System::reset_events();
+		System::set_block_number(System::block_number().saturating_add(1));
+
+		let result = call.dispatch(origin);
+		let events: Vec<RuntimeEvent> =
+			System::read_events_no_consensus().map(|record| record.event).collect();
+		TransactionOutcome::Rollback(Ok::<_, DispatchError>((result, events)))
+	})
+	.unwrap_or_else(|error| (Err(error.into()), Vec::new()));
+
+	let post_info = match result {
+		Ok(post_info) => post_info,
+		Err(error) => error.post_info,
+	};
+	DryRunResult {
+		result,
+		events,
+		weight: post_info.calc_actual_weight(&info),
+		fee: TransactionPayment::compute_actual_fee_details(len, &info, &post_info, 0),
+	}
+}
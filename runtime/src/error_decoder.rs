@@ -0,0 +1,60 @@
+//! Description of dispatch errors based on the runtime's type information.
+
+use crate::{
+	apis::{DecodedError, PalletErrorOrigin},
+	RuntimeError,
+};
+use codec::Encode;
+use scale_info::{form::MetaForm, Type, TypeDef, TypeInfo, Variant};
+use sp_runtime::DispatchError;
+use sp_std::prelude::*;
+
+pub fn decode_error(error: DispatchError) -> Option<DecodedError> {
+	match error {
+		DispatchError::Module(module_error) => {
+			// `RuntimeError` has one variant per pallet, indexed like the pallet in the runtime,
+			// wrapping the pallet's `Error` type.
+			let pallet = variant_at(RuntimeError::type_info(), module_error.index)?;
+			let pallet_error_type = pallet.fields.first()?.ty.type_info();
+			let error_index = module_error.error[0];
+			let error = variant_at(pallet_error_type, error_index)?;
+			Some(DecodedError {
+				pallet: Some(PalletErrorOrigin {
+					name: pallet.name.as_bytes().to_vec(),
+					index: module_error.index,
+				}),
+				error: error.name.as_bytes().to_vec(),
+				error_index,
+				docs: join_docs(&error.docs),
+			})
+		},
+		other => {
+			let error_index = other.encode()[0];
+			let error = variant_at(DispatchError::type_info(), error_index)?;
+			Some(DecodedError {
+				pallet: None,
+				error: error.name.as_bytes().to_vec(),
+				error_index,
+				docs: join_docs(&error.docs),
+			})
+		},
+	}
+}
+
+fn variant_at(ty: Type<MetaForm>, index: u8) -> Option<Variant<MetaForm>> {
+	match ty.type_def {
+		TypeDef::Variant(def) => def.variants.into_iter().find(|variant| variant.index == index),
+		_ => None,
+	}
+}
+
+fn join_docs(docs: &[&'static str]) -> Vec<u8> {
+	let mut joined = Vec::new();
+	for line in docs.iter().map(|line| line.trim()).filter(|line| !line.is_empty()) {
+		if !joined.is_empty() {
+			joined.push(b' ');
+		}
+		joined.extend_from_slice(line.as_bytes());
+	}
+	joined
+}
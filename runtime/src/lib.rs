@@ -9,15 +9,18 @@ include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
 use sp_api::impl_runtime_apis;
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use sp_consensus_grandpa::AuthorityId as GrandpaId;
-use sp_core::{crypto::KeyTypeId, H160, OpaqueMetadata, H256};
+use sp_core::{crypto::KeyTypeId, H160, OpaqueMetadata, OpaquePeerId, H256};
 use sp_io::hashing::sha2_256;
 use sp_runtime::{
 	create_runtime_str, generic, impl_opaque_keys,
 	traits::{
 		AccountIdConversion, AccountIdLookup, BlakeTwo256, Block as BlockT, IdentifyAccount,
-		NumberFor, One, Verify, OpaqueKeys,
+		Keccak256, NumberFor, One, Verify, OpaqueKeys, StaticLookup,
+	},
+	transaction_validity::{
+		TransactionLongevity, TransactionPriority, TransactionSource, TransactionValidity,
+		ValidTransaction,
 	},
-	transaction_validity::{TransactionSource, TransactionValidity},
 	ApplyExtrinsicResult, MultiSignature, Percent
 };
 use sp_std::prelude::*;
@@ -40,7 +43,7 @@ pub use frame_support::{
 	StorageValue,
 };
 use frame_support::PalletId;
-use frame_support::traits::{Currency, OnUnbalanced};
+use frame_support::traits::{Currency, Get, OnUnbalanced};
 use frame_support::weights::ConstantMultiplier;
 use frame_support::traits::tokens::{UnityAssetBalanceConversion, PayFromAccount};
 pub use frame_system::Call as SystemCall;
@@ -54,9 +57,10 @@ pub use sp_runtime::{Perbill, Permill};
 // Additional imports
 use codec::{Decode, Encode, MaxEncodedLen};
 use frame_system::EnsureRoot;
-use logion_shared::{CreateRecoveryCallFactory, MultisigApproveAsMultiCallFactory, MultisigAsMultiCallFactory, DistributionKey, RewardDistributor as RewardDistributorTrait};
+use logion_shared::{CreateRecoveryCallFactory, MultisigApproveAsMultiCallFactory, MultisigAsMultiCallFactory, DistributionKey, IsLegalOfficer, RewardDistributor as RewardDistributorTrait};
 use pallet_logion_loc::{Hasher};
 use pallet_multisig::Timepoint;
+use pallet_reward_accounting::{InflowCategory, Treasury};
 use scale_info::TypeInfo;
 use sp_runtime::traits::IdentityLookup;
 
@@ -127,7 +131,7 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	//   `spec_version`, and `authoring_version` are the same between Wasm and native.
 	// This value is set to 100 to notify Polkadot-JS App (https://polkadot.js.org/apps) to use
 	//   the compatible custom types.
-	spec_version: 164,
+	spec_version: 174,
 	impl_version: 2,
 	apis: RUNTIME_API_VERSIONS,
 	transaction_version: 5,
@@ -193,6 +197,11 @@ impl Contains<RuntimeCall> for BaseCallFilter {
 }
 
 mod weights;
+pub mod apis;
+mod dry_run;
+mod error_decoder;
+mod config_api;
+mod validator_registration;
 
 /// The default types are being injected by [`derive_impl`](`frame_support::derive_impl`) from
 /// [`SoloChainDefaultConfig`](`struct@frame_system::config_preludes::SolochainDefaultConfig`),
@@ -381,7 +390,44 @@ impl OnUnbalanced<NegativeImbalance> for DealWithInclusionFees {
 
 	fn on_nonzero_unbalanced(fees: NegativeImbalance) {
 
-		RewardDistributor::distribute(fees, InclusionFeesDistributionKey::get());
+		CategorizedRewardDistributor::<InclusionFeesCategory>::distribute(fees, InclusionFeesDistributionKey::get());
+	}
+}
+
+parameter_types! {
+	pub const InclusionFeesCategory: InflowCategory = InflowCategory::InclusionFees;
+	pub const InflationCategory: InflowCategory = InflowCategory::Inflation;
+	pub const StorageFeesCategory: InflowCategory = InflowCategory::StorageFees;
+	pub const CertificateFeesCategory: InflowCategory = InflowCategory::CertificateFees;
+	pub const ValueFeesCategory: InflowCategory = InflowCategory::ValueFees;
+	pub const RecurrentFeesCategory: InflowCategory = InflowCategory::RecurrentFees;
+	pub const LegalFeesCategory: InflowCategory = InflowCategory::LegalFees;
+	pub const OtherCategory: InflowCategory = InflowCategory::Other;
+}
+
+/// The category of the LOC fee distribution in progress, see `CategorizedDistributionKey`.
+pub struct CurrentLocFeesCategory;
+
+impl Get<InflowCategory> for CurrentLocFeesCategory {
+	fn get() -> InflowCategory {
+		RewardAccounting::current_category()
+	}
+}
+
+/// A distribution key which sets the category of the rewards that will be distributed with it.
+///
+/// `pallet_logion_loc` distributes several kinds of fees with a single distributor, and fetches
+/// the distribution key right before calling it. The category is a transient value (see
+/// `RewardAccounting`), only read by `LocFeesRewardDistributor`: other distributors pass their
+/// category explicitly.
+pub struct CategorizedDistributionKey<Key, Category>(sp_std::marker::PhantomData<(Key, Category)>);
+
+impl<Key: Get<DistributionKey>, Category: Get<InflowCategory>> Get<DistributionKey>
+	for CategorizedDistributionKey<Key, Category>
+{
+	fn get() -> DistributionKey {
+		RewardAccounting::set_current_category(Category::get());
+		Key::get()
 	}
 }
 
@@ -524,19 +570,19 @@ impl pallet_logion_loc::Config for Runtime {
 	type Currency = Balances;
 	type FileStorageByteFee = FileStorageByteFee;
 	type FileStorageEntryFee = FileStorageEntryFee;
-	type RewardDistributor = RewardDistributor;
-	type FileStorageFeeDistributionKey = FileStorageFeeDistributionKey;
+	type RewardDistributor = LocFeesRewardDistributor;
+	type FileStorageFeeDistributionKey = CategorizedDistributionKey<FileStorageFeeDistributionKey, StorageFeesCategory>;
 	type EthereumAddress = EthereumAddress;
 	type SponsorshipId = SponsorshipId;
 	type CertificateFee = CertificateFee;
-    type CertificateFeeDistributionKey = CertificateFeeDistributionKey;
+    type CertificateFeeDistributionKey = CategorizedDistributionKey<CertificateFeeDistributionKey, CertificateFeesCategory>;
     type TokenIssuance = TokenIssuance;
-	type ValueFeeDistributionKey = ValueFeeDistributionKey;
-	type CollectionItemFeeDistributionKey = RecurentFeeDistributionKey;
-	type TokensRecordFeeDistributionKey = RecurentFeeDistributionKey;
-	type IdentityLocLegalFeeDistributionKey = IdentityLocLegalFeeDistributionKey;
-	type TransactionLocLegalFeeDistributionKey = OtherLocLegalFeeDistributionKey;
-	type CollectionLocLegalFeeDistributionKey = OtherLocLegalFeeDistributionKey;
+	type ValueFeeDistributionKey = CategorizedDistributionKey<ValueFeeDistributionKey, ValueFeesCategory>;
+	type CollectionItemFeeDistributionKey = CategorizedDistributionKey<RecurentFeeDistributionKey, RecurrentFeesCategory>;
+	type TokensRecordFeeDistributionKey = CategorizedDistributionKey<RecurentFeeDistributionKey, RecurrentFeesCategory>;
+	type IdentityLocLegalFeeDistributionKey = CategorizedDistributionKey<IdentityLocLegalFeeDistributionKey, LegalFeesCategory>;
+	type TransactionLocLegalFeeDistributionKey = CategorizedDistributionKey<OtherLocLegalFeeDistributionKey, LegalFeesCategory>;
+	type CollectionLocLegalFeeDistributionKey = CategorizedDistributionKey<OtherLocLegalFeeDistributionKey, LegalFeesCategory>;
 	#[cfg(feature = "runtime-benchmarks")]
 	type LocIdFactory = ();
 	#[cfg(feature = "runtime-benchmarks")]
@@ -694,8 +740,6 @@ use pallet_logion_vote::benchmarking::{
 	LocSetup,
 };
 #[cfg(feature = "runtime-benchmarks")]
-use logion_shared::IsLegalOfficer;
-#[cfg(feature = "runtime-benchmarks")]
 use pallet_logion_loc::ItemsParams;
 #[cfg(feature = "runtime-benchmarks")]
 pub struct VoteLocSetup;
@@ -802,24 +846,37 @@ impl pallet_treasury::Config<CommunityTreasuryType> for Runtime {
 	type PayoutPeriod = SpendPayoutPeriod;
 }
 
-pub struct RewardDistributor;
-impl logion_shared::RewardDistributor<NegativeImbalance, Balance, AccountId, RuntimeOrigin, LoAuthorityList>
-    for RewardDistributor
+/// Distributes rewards, recording the payouts under `Category`.
+pub struct CategorizedRewardDistributor<Category>(sp_std::marker::PhantomData<Category>);
+
+/// Distributes rewards without a known category.
+pub type RewardDistributor = CategorizedRewardDistributor<OtherCategory>;
+
+/// Distributes LOC fees, under the category set by the distribution key.
+pub type LocFeesRewardDistributor = CategorizedRewardDistributor<CurrentLocFeesCategory>;
+
+impl<Category: Get<InflowCategory>> logion_shared::RewardDistributor<NegativeImbalance, Balance, AccountId, RuntimeOrigin, LoAuthorityList>
+    for CategorizedRewardDistributor<Category>
 {
     fn payout_community_treasury(reward: NegativeImbalance) {
 		if reward != NegativeImbalance::zero() {
+			RewardAccounting::record_treasury_inflow(Treasury::Community, Category::get(), reward.peek());
 			Balances::resolve_creating(&CommunityTreasuryPalletId::get().into_account_truncating(), reward);
 		}
     }
 
 	fn payout_logion_treasury(reward: NegativeImbalance) {
 		if reward != NegativeImbalance::zero() {
+			RewardAccounting::record_treasury_inflow(Treasury::Logion, Category::get(), reward.peek());
 			Balances::resolve_creating(&LogionTreasuryPalletId::get().into_account_truncating(), reward);
 		}
 	}
 
 	fn payout_to(reward: NegativeImbalance, account: &AccountId) {
 		if reward != NegativeImbalance::zero() {
+			if LoAuthorityList::is_legal_officer(account) {
+				RewardAccounting::record_legal_officer_inflow(account, Category::get(), reward.peek());
+			}
 			Balances::resolve_creating(account, reward);
 		}
 	}
@@ -828,11 +885,221 @@ impl logion_shared::RewardDistributor<NegativeImbalance, Balance, AccountId, Run
 impl pallet_block_reward::Config for Runtime {
     type Currency = Balances;
     type RewardAmount = InflationAmount;
-    type RewardDistributor = RewardDistributor;
+    type RewardDistributor = CategorizedRewardDistributor<InflationCategory>;
     type DistributionKey = InflationDistributionKey;
 	type IsLegalOfficer = LoAuthorityList;
 }
 
+impl pallet_reward_accounting::Config for Runtime {
+	type Balance = Balance;
+}
+
+/// Lets the owner or the requester of a closed transaction LOC anchor document hashes.
+pub struct LocAnchoringAuthorizer;
+impl pallet_hash_anchoring::AnchoringAuthorizer<AccountId, LocId> for LocAnchoringAuthorizer {
+	fn can_anchor(who: &AccountId, loc_id: &LocId) -> bool {
+		let Some(loc) = pallet_logion_loc::LocMap::<Runtime>::get(loc_id) else { return false };
+		let is_requester = match &loc.requester {
+			pallet_logion_loc::Requester::Account(requester) => requester == who,
+			_ => false,
+		};
+		matches!(loc.loc_type, pallet_logion_loc::LocType::Transaction) &&
+			loc.closed && loc.void_info.is_none() &&
+			(loc.owner == *who || is_requester)
+	}
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+pub struct HashAnchoringSetup;
+#[cfg(feature = "runtime-benchmarks")]
+impl pallet_hash_anchoring::benchmarking::AnchoringSetup<AccountId, LocId> for HashAnchoringSetup {
+
+	fn setup_anchoring() -> (LocId, AccountId) {
+		let loc_id: LocId = 0;
+		let requester: AccountId = [0u8;32].into();
+		Balances::make_free_balance_be(&requester, Balance::max_value());
+		let legal_officer_id = LoAuthorityList::legal_officers()[0].clone();
+		let _ = LogionLoc::create_polkadot_transaction_loc(
+			RuntimeOrigin::signed(requester.clone()),
+			loc_id,
+			legal_officer_id.clone(),
+			0u32.into(),
+			ItemsParams::empty(),
+		);
+		let _ = LogionLoc::close(
+			RuntimeOrigin::signed(legal_officer_id),
+			loc_id,
+			None,
+			false,
+		);
+		(loc_id, requester)
+	}
+}
+
+impl pallet_hash_anchoring::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type LocId = LocId;
+	type Hash = Hash;
+	type Hasher = SHA256;
+	type AnchoringAuthorizer = LocAnchoringAuthorizer;
+	type UnixTime = Timestamp;
+	type WeightInfo = pallet_hash_anchoring::weights::SubstrateWeight<Runtime>;
+	#[cfg(feature = "runtime-benchmarks")]
+	type AnchoringSetup = HashAnchoringSetup;
+}
+
+/// Indexes the files and metadata of closed LOCs, and the collection items with their files.
+/// LOCs are indexed when closed (files and metadata are not indexed when added because no event
+/// is deposited then, and only the items of closed LOCs can be verified anyway) and removed from
+/// the index when voided.
+pub struct LocChanges;
+
+/// A conservative estimate of the weight of decoding an event record. Events are whitelisted, so
+/// reading them at the end of a block costs no database access.
+const EVENT_SCAN_WEIGHT: Weight = Weight::from_parts(2_000_000, 0);
+
+/// The cursor of the indexing of existing LOCs, then collection items.
+#[derive(Encode, Decode)]
+enum LocIndexBackfill {
+	Locs(Vec<u8>),
+	CollectionItems(Vec<u8>),
+}
+
+impl pallet_loc_index::LocChangesProvider<Hash, LocId, Hash> for LocChanges {
+	fn changed_in_block() -> (Vec<pallet_loc_index::LocChange<LocId, Hash>>, Weight) {
+		use pallet_loc_index::LocChange;
+
+		let records = System::read_events_no_consensus();
+		let mut changes = Vec::new();
+		let mut scanned = 0u64;
+		for record in records {
+			scanned += 1;
+			match record.event {
+				RuntimeEvent::LogionLoc(pallet_logion_loc::Event::LocClosed(loc_id, ..)) |
+				RuntimeEvent::LogionLoc(pallet_logion_loc::Event::LocVoid(loc_id, ..)) =>
+					changes.push(LocChange::Loc(loc_id)),
+				RuntimeEvent::LogionLoc(pallet_logion_loc::Event::ItemAdded(loc_id, item_id, ..)) =>
+					changes.push(LocChange::CollectionItem(loc_id, item_id)),
+				_ => {},
+			}
+		}
+		(changes, EVENT_SCAN_WEIGHT.saturating_mul(scanned))
+	}
+
+	fn existing(
+		cursor: &[u8],
+		limit: u32,
+	) -> (Vec<pallet_loc_index::LocChange<LocId, Hash>>, Option<Vec<u8>>, Weight) {
+		use pallet_loc_index::LocChange;
+
+		let limit = limit as usize;
+		let (changes, next) = match LocIndexBackfill::decode(&mut &cursor[..])
+			.unwrap_or(LocIndexBackfill::Locs(Vec::new()))
+		{
+			LocIndexBackfill::Locs(key) => {
+				let mut keys = if key.is_empty() {
+					pallet_logion_loc::LocMap::<Runtime>::iter_keys()
+				} else {
+					pallet_logion_loc::LocMap::<Runtime>::iter_keys_from(key)
+				};
+				let changes: Vec<_> = keys.by_ref().take(limit).map(LocChange::Loc).collect();
+				let next = if changes.len() < limit {
+					LocIndexBackfill::CollectionItems(Vec::new())
+				} else {
+					LocIndexBackfill::Locs(keys.last_raw_key().to_vec())
+				};
+				(changes, Some(next))
+			},
+			LocIndexBackfill::CollectionItems(key) => {
+				let mut keys = if key.is_empty() {
+					pallet_logion_loc::CollectionItemsMap::<Runtime>::iter_keys()
+				} else {
+					pallet_logion_loc::CollectionItemsMap::<Runtime>::iter_keys_from(key)
+				};
+				let changes: Vec<_> = keys
+					.by_ref()
+					.take(limit)
+					.map(|(loc_id, item_id)| LocChange::CollectionItem(loc_id, item_id))
+					.collect();
+				let next = (changes.len() == limit)
+					.then(|| LocIndexBackfill::CollectionItems(keys.last_raw_key().to_vec()));
+				(changes, next)
+			},
+		};
+		let weight = RocksDbWeight::get().reads(changes.len() as u64 + 1);
+		(changes, next.map(|next| next.encode()), weight)
+	}
+
+	fn index_changes(
+		change: &pallet_loc_index::LocChange<LocId, Hash>,
+	) -> (Vec<pallet_loc_index::IndexChange<Hash, LocId, Hash>>, Weight) {
+		use pallet_loc_index::{IndexChange, IndexedItem, LocChange};
+
+		let mut changes = Vec::new();
+		match *change {
+			LocChange::Loc(loc_id) => match pallet_logion_loc::LocMap::<Runtime>::get(loc_id) {
+				Some(loc) if loc.void_info.is_some() => changes.push(IndexChange::Void(loc_id)),
+				Some(loc) if loc.closed => {
+					changes.extend(loc.files.iter().map(|file| {
+						IndexChange::Add(file.hash, IndexedItem::File { loc_id })
+					}));
+					changes.extend(loc.metadata.iter().map(|item| {
+						IndexChange::Add(item.value, IndexedItem::Metadata { loc_id })
+					}));
+				},
+				_ => {},
+			},
+			LocChange::CollectionItem(loc_id, item_id) => {
+				changes.push(IndexChange::Add(
+					item_id,
+					IndexedItem::CollectionItem { loc_id, item_id },
+				));
+				if let Some(item) =
+					pallet_logion_loc::CollectionItemsMap::<Runtime>::get(loc_id, item_id)
+				{
+					changes.extend(item.files.iter().map(|file| {
+						IndexChange::Add(
+							file.hash,
+							IndexedItem::CollectionItemFile { loc_id, item_id },
+						)
+					}));
+				}
+			},
+		}
+		(changes, RocksDbWeight::get().reads(1))
+	}
+}
+
+parameter_types! {
+	pub const MaxLocIndexBackfillPerBlock: u32 = 100;
+}
+
+impl pallet_loc_index::Config for Runtime {
+	type LocId = LocId;
+	type CollectionItemId = Hash;
+	type Hash = Hash;
+	type LocChangesProvider = LocChanges;
+	type MaxBackfillPerBlock = MaxLocIndexBackfillPerBlock;
+}
+
+impl pallet_mmr::Config for Runtime {
+	const INDEXING_PREFIX: &'static [u8] = b"mmr";
+	type Hashing = Keccak256;
+	type LeafData = pallet_mmr::ParentNumberAndHash<Self>;
+	type OnNewRoot = ();
+	type WeightInfo = ();
+}
+
+/// MMR helper types.
+mod mmr {
+	use super::Runtime;
+	pub use pallet_mmr::primitives::*;
+
+	pub type Leaf = <<Runtime as pallet_mmr::Config>::LeafData as LeafDataProvider>::LeafData;
+	pub type Hash = <Hashing as sp_runtime::traits::Hash>::Output;
+	pub type Hashing = <Runtime as pallet_mmr::Config>::Hashing;
+}
+
 impl pallet_utility::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
 	type RuntimeCall = RuntimeCall;
@@ -867,6 +1134,10 @@ construct_runtime!(
 		LogionTreasury: pallet_treasury::<Instance1> = 21,
 		CommunityTreasury: pallet_treasury::<Instance2> = 22,
 		Utility: pallet_utility = 23,
+		RewardAccounting: pallet_reward_accounting = 24,
+		Mmr: pallet_mmr = 25,
+		HashAnchoring: pallet_hash_anchoring = 26,
+		LocIndex: pallet_loc_index = 27,
 	}
 );
 
@@ -912,6 +1183,112 @@ pub type Executive = frame_executive::Executive<
 	Migrations,
 >;
 
+/// A priority lane gives the operations of a class of signers precedence over other
+/// transactions in the pool, and keeps them there for longer.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PriorityLane {
+	/// Added to the priority computed by the signed extensions.
+	pub priority_boost: TransactionPriority,
+	/// The minimum number of blocks the transaction stays valid in the pool.
+	pub min_longevity: TransactionLongevity,
+}
+
+impl PriorityLane {
+	fn apply(&self, mut validity: ValidTransaction) -> ValidTransaction {
+		validity.priority = validity.priority.saturating_add(self.priority_boost);
+		validity.longevity = validity.longevity.max(self.min_longevity);
+		validity
+	}
+}
+
+// The lanes are kept in storage, under `twox_128(":LegalOfficerPriorityLane:")` and
+// `twox_128(":ValidatorPriorityLane:")`, so that root may tune them with `System::set_storage`.
+parameter_types! {
+	// Legal officers' operations (acknowledgements, LOC closing, votes...) must not be delayed
+	// by bulk imports or transfers, even when those are tipped.
+	pub storage LegalOfficerPriorityLane: PriorityLane = PriorityLane {
+		priority_boost: TransactionPriority::MAX / 4,
+		min_longevity: HOURS as TransactionLongevity,
+	};
+	// Validators' operations (e.g. `Session::set_keys`) are critical to block production.
+	pub storage ValidatorPriorityLane: PriorityLane = PriorityLane {
+		priority_boost: TransactionPriority::MAX / 4,
+		min_longevity: HOURS as TransactionLongevity,
+	};
+}
+
+/// Tells if `call` is an operation of legal officers. Bulk data imports are not.
+fn is_legal_officer_operation(call: &RuntimeCall) -> bool {
+	matches!(
+		call,
+		RuntimeCall::LogionLoc(..) |
+			RuntimeCall::Vote(..) |
+			RuntimeCall::Vault(..) |
+			RuntimeCall::VerifiedRecovery(..) |
+			RuntimeCall::LoAuthorityList(..)
+	) && !is_import(call)
+}
+
+/// Tells if `call` imports data, e.g. when migrating from another chain.
+fn is_import(call: &RuntimeCall) -> bool {
+	use pallet_lo_authority_list::Call as AuthorityCall;
+	use pallet_logion_loc::Call as LocCall;
+
+	matches!(
+		call,
+		RuntimeCall::LogionLoc(
+			LocCall::import_loc { .. } |
+				LocCall::import_collection_item { .. } |
+				LocCall::import_tokens_record { .. } |
+				LocCall::import_invited_contributor_selection { .. } |
+				LocCall::import_verified_issuer { .. } |
+				LocCall::import_verified_issuer_selection { .. } |
+				LocCall::import_sponsorship { .. }
+		) | RuntimeCall::LoAuthorityList(
+			AuthorityCall::import_host_legal_officer { .. } |
+				AuthorityCall::import_guest_legal_officer { .. }
+		)
+	)
+}
+
+/// Tells if `call` is an operation of validators.
+fn is_validator_operation(call: &RuntimeCall) -> bool {
+	matches!(call, RuntimeCall::Session(..))
+}
+
+/// Selects the priority lane of given extrinsic, if any: the lane of its signer's class, if the
+/// call is an operation of this class. Other calls of the same signer, and calls wrapped in
+/// another one (e.g. `Utility::batch`), are not prioritized.
+///
+/// When the signer is both a legal officer and a validator, the lane with the highest boost
+/// is selected.
+pub fn priority_lane(tx: &UncheckedExtrinsic) -> Option<PriorityLane> {
+	let (address, _, _) = tx.signature.as_ref()?;
+	let signer = <Runtime as frame_system::Config>::Lookup::lookup(address.clone()).ok()?;
+
+	let mut lanes = Vec::new();
+	if is_legal_officer_operation(&tx.function) && LoAuthorityList::is_legal_officer(&signer) {
+		lanes.push(LegalOfficerPriorityLane::get());
+	}
+	if is_validator_operation(&tx.function) && ValidatorSet::validators().contains(&signer) {
+		lanes.push(ValidatorPriorityLane::get());
+	}
+	lanes.into_iter().max_by_key(|lane| lane.priority_boost)
+}
+
+fn validate_transaction_in_lane(
+	source: TransactionSource,
+	tx: UncheckedExtrinsic,
+	block_hash: Hash,
+) -> TransactionValidity {
+	let lane = priority_lane(&tx);
+	let validity = Executive::validate_transaction(source, tx, block_hash)?;
+	Ok(match lane {
+		Some(lane) => lane.apply(validity),
+		None => validity,
+	})
+}
+
 #[cfg(feature = "runtime-benchmarks")]
 #[macro_use]
 extern crate frame_benchmarking;
@@ -923,6 +1300,7 @@ mod benches {
 		[frame_system, SystemBench::<Runtime>]
 		[pallet_balances, Balances]
 		[pallet_grandpa, Grandpa]
+		[pallet_hash_anchoring, HashAnchoring]
 		[pallet_lo_authority_list, LoAuthorityList]
 		[pallet_logion_loc, LogionLoc]
 		[pallet_logion_vote, Vote]
@@ -992,7 +1370,7 @@ impl_runtime_apis! {
 			tx: <Block as BlockT>::Extrinsic,
 			block_hash: <Block as BlockT>::Hash,
 		) -> TransactionValidity {
-			Executive::validate_transaction(source, tx, block_hash)
+			validate_transaction_in_lane(source, tx, block_hash)
 		}
 	}
 
@@ -1091,6 +1469,109 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl mmr::MmrApi<Block, mmr::Hash, BlockNumber> for Runtime {
+		fn mmr_root() -> Result<mmr::Hash, mmr::Error> {
+			Ok(pallet_mmr::RootHash::<Runtime>::get())
+		}
+
+		fn mmr_leaf_count() -> Result<mmr::LeafIndex, mmr::Error> {
+			Ok(pallet_mmr::NumberOfLeaves::<Runtime>::get())
+		}
+
+		fn generate_proof(
+			block_numbers: Vec<BlockNumber>,
+			best_known_block_number: Option<BlockNumber>,
+		) -> Result<(Vec<mmr::EncodableOpaqueLeaf>, mmr::Proof<mmr::Hash>), mmr::Error> {
+			Mmr::generate_proof(block_numbers, best_known_block_number).map(|(leaves, proof)| {
+				(
+					leaves
+						.into_iter()
+						.map(|leaf| mmr::EncodableOpaqueLeaf::from_leaf(&leaf))
+						.collect(),
+					proof,
+				)
+			})
+		}
+
+		fn verify_proof(
+			leaves: Vec<mmr::EncodableOpaqueLeaf>,
+			proof: mmr::Proof<mmr::Hash>,
+		) -> Result<(), mmr::Error> {
+			let leaves = leaves
+				.into_iter()
+				.map(|leaf| leaf.into_opaque_leaf().try_decode().ok_or(mmr::Error::Verify))
+				.collect::<Result<Vec<mmr::Leaf>, mmr::Error>>()?;
+			Mmr::verify_leaves(leaves, proof)
+		}
+
+		fn verify_proof_stateless(
+			root: mmr::Hash,
+			leaves: Vec<mmr::EncodableOpaqueLeaf>,
+			proof: mmr::Proof<mmr::Hash>,
+		) -> Result<(), mmr::Error> {
+			let nodes = leaves
+				.into_iter()
+				.map(|leaf| mmr::DataOrHash::Data(leaf.into_opaque_leaf()))
+				.collect();
+			pallet_mmr::verify_leaves_lean::<mmr::Hashing, _>(root, nodes, proof)
+		}
+	}
+
+	impl pallet_hash_anchoring::runtime_api::HashAnchoringApi<Block, AccountId, LocId, Hash, BlockNumber> for Runtime {
+		fn verify_inclusion(
+			loc_id: LocId,
+			root: Hash,
+			document: Hash,
+			proof: Vec<Hash>,
+		) -> Option<pallet_hash_anchoring::Anchor<AccountId, LocId, BlockNumber>> {
+			HashAnchoring::verify_inclusion(loc_id, root, document, proof)
+		}
+	}
+
+	impl pallet_reward_accounting::runtime_api::RewardAccountingApi<Block, AccountId, Balance> for Runtime {
+		fn inflows() -> pallet_reward_accounting::Inflows<AccountId, Balance> {
+			RewardAccounting::inflows()
+		}
+	}
+
+	impl apis::LegalOfficerNodesApi<Block> for Runtime {
+		fn legal_officer_nodes() -> Vec<OpaquePeerId> {
+			pallet_lo_authority_list::LegalOfficerNodes::<Runtime>::get()
+				.into_iter()
+				.map(|node_id| OpaquePeerId(node_id.to_vec()))
+				.collect()
+		}
+	}
+
+	impl apis::ValidatorRegistrationApi<Block, AccountId> for Runtime {
+		fn validator_registration(
+			aura_key: AuraId,
+		) -> Option<apis::ValidatorRegistration<AccountId>> {
+			validator_registration::validator_registration(aura_key)
+		}
+	}
+
+	impl apis::DryRunApi<Block, AccountId, RuntimeCall, RuntimeEvent, Balance> for Runtime {
+		fn dry_run(
+			origin: apis::DryRunOrigin<AccountId>,
+			call: RuntimeCall,
+		) -> apis::DryRunResult<RuntimeEvent, Balance> {
+			dry_run::dry_run(origin, call)
+		}
+	}
+
+	impl apis::ErrorDecoderApi<Block> for Runtime {
+		fn decode_error(error: sp_runtime::DispatchError) -> Option<apis::DecodedError> {
+			error_decoder::decode_error(error)
+		}
+	}
+
+	impl apis::LogionConfigApi<Block, Balance, BlockNumber> for Runtime {
+		fn logion_config() -> apis::LogionConfig<Balance, BlockNumber> {
+			config_api::logion_config()
+		}
+	}
+
 	#[cfg(feature = "runtime-benchmarks")]
 	impl frame_benchmarking::Benchmark<Block> for Runtime {
 		fn benchmark_metadata(extra: bool) -> (
@@ -1168,9 +1649,14 @@ impl_runtime_apis! {
 #[cfg(test)]
 mod tests {
 	use super::*;
-	use frame_support::traits::WhitelistedStorageKeys;
-	use sp_core::hexdisplay::HexDisplay;
-	use std::collections::HashSet;
+	use frame_support::{
+		dispatch::{DispatchClass, GetDispatchInfo},
+		traits::{Hooks, WhitelistedStorageKeys},
+	};
+	use pallet_lo_authority_list::GenesisHostData;
+	use sp_core::{crypto::ByteArray, hexdisplay::HexDisplay};
+	use sp_keyring::{Ed25519Keyring, Sr25519Keyring};
+	use std::{cmp::Reverse, collections::HashSet};
 
 	#[test]
 	fn check_whitelist() {
@@ -1200,4 +1686,440 @@ mod tests {
 			whitelist.contains("26aa394eea5630e07c48ae0c9558cef780d41e5e16056765bc8461851072c9d7")
 		);
 	}
+
+	fn new_test_ext(
+		legal_officers: Vec<AccountId>,
+		validators: Vec<AccountId>,
+	) -> sp_io::TestExternalities {
+		let mut ext = sp_io::TestExternalities::default();
+		ext.execute_with(|| {
+			let mut config: serde_json::Value =
+				serde_json::from_slice(&create_default_config::<RuntimeGenesisConfig>()).unwrap();
+			config["validatorSet"]["initialValidators"] = serde_json::json!(validators);
+			config["loAuthorityList"]["legalOfficers"] = serde_json::json!(legal_officers
+				.into_iter()
+				.map(|id| (
+					id,
+					GenesisHostData { node_id: None, base_url: None, region: "Europe".into() }
+				))
+				.collect::<Vec<_>>());
+			build_config::<RuntimeGenesisConfig>(serde_json::to_vec(&config).unwrap()).unwrap();
+		});
+		ext
+	}
+
+	fn signed_extrinsic(
+		signer: Sr25519Keyring,
+		call: RuntimeCall,
+		tip: Balance,
+	) -> UncheckedExtrinsic {
+		signed_extrinsic_with_nonce(signer, 0, call, tip)
+	}
+
+	fn signed_extrinsic_with_nonce(
+		signer: Sr25519Keyring,
+		nonce: Nonce,
+		call: RuntimeCall,
+		tip: Balance,
+	) -> UncheckedExtrinsic {
+		let extra: SignedExtra = (
+			frame_system::CheckNonZeroSender::<Runtime>::new(),
+			frame_system::CheckSpecVersion::<Runtime>::new(),
+			frame_system::CheckTxVersion::<Runtime>::new(),
+			frame_system::CheckGenesis::<Runtime>::new(),
+			frame_system::CheckEra::<Runtime>::from(generic::Era::Immortal),
+			frame_system::CheckNonce::<Runtime>::from(nonce),
+			frame_system::CheckWeight::<Runtime>::new(),
+			pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(tip),
+		);
+		let payload =
+			SignedPayload::new(call, extra).expect("Additional signed data is available; qed");
+		let signature = Signature::Sr25519(payload.using_encoded(|payload| signer.sign(payload)));
+		let (call, extra, _) = payload.deconstruct();
+		UncheckedExtrinsic::new_signed(call, signer.to_account_id().into(), signature, extra)
+	}
+
+	fn transfer() -> RuntimeCall {
+		RuntimeCall::Balances(BalancesCall::transfer_keep_alive {
+			dest: Sr25519Keyring::Dave.to_account_id().into(),
+			value: LGNT,
+		})
+	}
+
+	fn set_keys(validator: Sr25519Keyring, grandpa: Ed25519Keyring) -> RuntimeCall {
+		RuntimeCall::Session(pallet_session::Call::set_keys {
+			keys: opaque::SessionKeys {
+				aura: validator.public().into(),
+				grandpa: grandpa.public().into(),
+			},
+			proof: vec![],
+		})
+	}
+
+	fn loc_operation() -> RuntimeCall {
+		RuntimeCall::LogionLoc(pallet_logion_loc::Call::close {
+			loc_id: 1,
+			seal: None,
+			auto_ack: false,
+		})
+	}
+
+	fn loc_import() -> RuntimeCall {
+		RuntimeCall::LogionLoc(pallet_logion_loc::Call::import_verified_issuer_selection {
+			collection_loc_id: 1,
+			issuer: Sr25519Keyring::Charlie.to_account_id(),
+		})
+	}
+
+	fn endow(accounts: &[Sr25519Keyring]) {
+		for account in accounts {
+			let _ = Balances::deposit_creating(&account.to_account_id(), 1_000 * LGNT);
+		}
+	}
+
+	/// Validates `tx` like the transaction pool does.
+	fn validate(tx: UncheckedExtrinsic) -> ValidTransaction {
+		validate_transaction_in_lane(TransactionSource::External, tx, System::parent_hash())
+			.expect("Transaction is valid; qed")
+	}
+
+	#[test]
+	fn legal_officer_operations_are_prioritized() {
+		new_test_ext(
+			vec![Sr25519Keyring::Alice.to_account_id()],
+			vec![Sr25519Keyring::Bob.to_account_id()],
+		)
+		.execute_with(|| {
+			endow(&[Sr25519Keyring::Alice, Sr25519Keyring::Charlie]);
+			let lane = LegalOfficerPriorityLane::get();
+
+			let operation = validate(signed_extrinsic(Sr25519Keyring::Alice, loc_operation(), 0));
+			let by_other = validate(signed_extrinsic(Sr25519Keyring::Charlie, loc_operation(), 0));
+			assert_eq!(operation.priority, by_other.priority + lane.priority_boost);
+			assert!(operation.longevity >= lane.min_longevity);
+
+			// Other calls of legal officers are not prioritized.
+			let transfer_by_lo = validate(signed_extrinsic(Sr25519Keyring::Alice, transfer(), 0));
+			let transfer_by_other =
+				validate(signed_extrinsic(Sr25519Keyring::Charlie, transfer(), 0));
+			assert_eq!(transfer_by_lo.priority, transfer_by_other.priority);
+
+			// Neither are their bulk imports.
+			let import_by_lo = validate(signed_extrinsic(Sr25519Keyring::Alice, loc_import(), 0));
+			let import_by_other =
+				validate(signed_extrinsic(Sr25519Keyring::Charlie, loc_import(), 0));
+			assert_eq!(import_by_lo.priority, import_by_other.priority);
+		});
+	}
+
+	#[test]
+	fn validator_operations_are_prioritized() {
+		new_test_ext(
+			vec![Sr25519Keyring::Alice.to_account_id()],
+			vec![Sr25519Keyring::Bob.to_account_id()],
+		)
+		.execute_with(|| {
+			endow(&[Sr25519Keyring::Bob, Sr25519Keyring::Charlie]);
+			let signed_set_keys =
+				|signer| signed_extrinsic(signer, set_keys(signer, Ed25519Keyring::Bob), 0);
+
+			let operation = validate(signed_set_keys(Sr25519Keyring::Bob));
+			let by_other = validate(signed_set_keys(Sr25519Keyring::Charlie));
+			assert_eq!(
+				operation.priority,
+				by_other.priority + ValidatorPriorityLane::get().priority_boost
+			);
+
+			let transfer_by_validator =
+				validate(signed_extrinsic(Sr25519Keyring::Bob, transfer(), 0));
+			let transfer_by_other =
+				validate(signed_extrinsic(Sr25519Keyring::Charlie, transfer(), 0));
+			assert_eq!(transfer_by_validator.priority, transfer_by_other.priority);
+		});
+	}
+
+	#[test]
+	fn unsigned_transactions_are_not_prioritized() {
+		new_test_ext(
+			vec![Sr25519Keyring::Alice.to_account_id()],
+			vec![Sr25519Keyring::Bob.to_account_id()],
+		)
+		.execute_with(|| {
+			let unsigned = UncheckedExtrinsic::new_unsigned(loc_operation());
+			assert_eq!(priority_lane(&unsigned), None);
+		});
+	}
+
+	#[test]
+	fn untipped_operations_outrank_tipped_transactions() {
+		new_test_ext(
+			vec![Sr25519Keyring::Alice.to_account_id()],
+			vec![Sr25519Keyring::Bob.to_account_id()],
+		)
+		.execute_with(|| {
+			endow(&[Sr25519Keyring::Alice, Sr25519Keyring::Bob, Sr25519Keyring::Charlie]);
+
+			let tipped =
+				validate(signed_extrinsic(Sr25519Keyring::Charlie, transfer(), LGNT / 1_000_000));
+			let untipped = validate(signed_extrinsic(Sr25519Keyring::Charlie, transfer(), 0));
+			assert!(tipped.priority > untipped.priority);
+
+			let legal_officer =
+				validate(signed_extrinsic(Sr25519Keyring::Alice, loc_operation(), 0));
+			let validator = validate(signed_extrinsic(
+				Sr25519Keyring::Bob,
+				set_keys(Sr25519Keyring::Bob, Ed25519Keyring::Bob),
+				0,
+			));
+			assert!(legal_officer.priority > tipped.priority);
+			assert!(validator.priority > tipped.priority);
+		});
+	}
+
+	#[test]
+	fn prioritized_transactions_are_included_before_bulk_ones_in_a_full_block() {
+		use sp_runtime::traits::Header as _;
+
+		new_test_ext(
+			vec![Sr25519Keyring::Alice.to_account_id()],
+			vec![Sr25519Keyring::Bob.to_account_id()],
+		)
+		.execute_with(|| {
+			endow(&[
+				Sr25519Keyring::Alice,
+				Sr25519Keyring::Bob,
+				Sr25519Keyring::Charlie,
+				Sr25519Keyring::Dave,
+			]);
+			// Each bulk transaction takes a tenth of the block length: not all of them fit.
+			let max_length = *BlockLength::get().max.get(DispatchClass::Normal) as usize;
+			let bulk = |signer, nonce, tip| {
+				let remark =
+					RuntimeCall::System(SystemCall::remark { remark: vec![0; max_length / 10] });
+				signed_extrinsic_with_nonce(signer, nonce, remark, tip)
+			};
+			let mut pool: Vec<UncheckedExtrinsic> = Vec::new();
+			pool.extend((0..8).map(|nonce| bulk(Sr25519Keyring::Charlie, nonce, LGNT / 1_000_000)));
+			pool.extend((0..8).map(|nonce| bulk(Sr25519Keyring::Dave, nonce, 0)));
+			let legal_officer = pool.len();
+			pool.push(signed_extrinsic(Sr25519Keyring::Alice, loc_operation(), 0));
+			let validator = pool.len();
+			pool.push(signed_extrinsic(
+				Sr25519Keyring::Bob,
+				set_keys(Sr25519Keyring::Bob, Ed25519Keyring::Bob),
+				0,
+			));
+
+			// Like the block builder: transactions are taken by decreasing priority, those
+			// exhausting the block's resources are left out.
+			let mut ready: Vec<(TransactionPriority, usize, UncheckedExtrinsic)> = pool
+				.into_iter()
+				.enumerate()
+				.map(|(index, tx)| (validate(tx.clone()).priority, index, tx))
+				.collect();
+			ready.sort_by_key(|(priority, index, _)| (Reverse(*priority), *index));
+			Executive::initialize_block(&Header::new(
+				System::block_number() + 1,
+				Default::default(),
+				Default::default(),
+				System::parent_hash(),
+				Default::default(),
+			));
+			let included: Vec<usize> = ready
+				.into_iter()
+				.filter_map(|(_, index, tx)| {
+					Executive::apply_extrinsic(tx).is_ok().then_some(index)
+				})
+				.collect();
+
+			assert!(included.contains(&legal_officer));
+			assert!(included.contains(&validator));
+			// The tipped bulk transactions fill most of the block, the untipped ones overflow.
+			assert!((0..8).all(|index| included.contains(&index)));
+			let untipped_included = (8..16).filter(|index| included.contains(index)).count();
+			assert!(untipped_included < 8, "{} untipped transactions included", untipped_included);
+		});
+	}
+
+	#[test]
+	fn priority_lanes_are_set_in_storage() {
+		new_test_ext(
+			vec![Sr25519Keyring::Alice.to_account_id()],
+			vec![Sr25519Keyring::Bob.to_account_id()],
+		)
+		.execute_with(|| {
+			endow(&[Sr25519Keyring::Alice, Sr25519Keyring::Charlie]);
+			let lane = PriorityLane { priority_boost: 1_000, min_longevity: 10 };
+			LegalOfficerPriorityLane::set(&lane);
+
+			let operation = validate(signed_extrinsic(Sr25519Keyring::Alice, loc_operation(), 0));
+			let by_other = validate(signed_extrinsic(Sr25519Keyring::Charlie, loc_operation(), 0));
+			assert_eq!(operation.priority, by_other.priority + 1_000);
+			assert_eq!(
+				sp_io::storage::get(&sp_core::twox_128(b":LegalOfficerPriorityLane:")),
+				Some(lane.encode().into())
+			);
+		});
+	}
+
+	#[test]
+	fn validator_registration_is_found_from_aura_key() {
+		let alice = Sr25519Keyring::Alice.to_account_id();
+		new_test_ext(vec![alice.clone()], vec![alice.clone()]).execute_with(|| {
+			let aura: AuraId = Sr25519Keyring::Alice.public().into();
+			let grandpa: GrandpaId = Ed25519Keyring::Alice.public().into();
+			pallet_session::NextKeys::<Runtime>::insert(
+				&alice,
+				opaque::SessionKeys { aura: aura.clone(), grandpa: grandpa.clone() },
+			);
+			pallet_session::KeyOwner::<Runtime>::insert(
+				(sp_core::crypto::key_types::AURA, aura.to_raw_vec()),
+				&alice,
+			);
+
+			let registration = validator_registration::validator_registration(aura).unwrap();
+			assert_eq!(registration.account, alice);
+			assert_eq!(registration.grandpa_key, Some(grandpa));
+			assert!(registration.is_validator);
+			assert!(registration.is_legal_officer);
+			assert_eq!(registration.node_id, None);
+
+			let unknown: AuraId = Sr25519Keyring::Bob.public().into();
+			assert_eq!(validator_registration::validator_registration(unknown), None);
+		});
+	}
+
+	#[test]
+	fn signed_calls_are_dry_run_without_persisting_changes() {
+		let alice = Sr25519Keyring::Alice.to_account_id();
+		let dave = Sr25519Keyring::Dave.to_account_id();
+		new_test_ext(vec![alice.clone()], vec![Sr25519Keyring::Bob.to_account_id()]).execute_with(
+			|| {
+				let _ = Balances::deposit_creating(&alice, 10 * LGNT);
+
+				let outcome =
+					dry_run::dry_run(apis::DryRunOrigin::Signed(alice.clone()), transfer());
+
+				assert!(outcome.result.is_ok());
+				assert!(outcome.events.contains(&RuntimeEvent::Balances(
+					pallet_balances::Event::Transfer {
+						from: alice.clone(),
+						to: dave.clone(),
+						amount: LGNT,
+					}
+				)));
+				assert!(outcome.fee.inclusion_fee.is_some());
+				assert_eq!(Balances::free_balance(&dave), 0);
+				assert_eq!(Balances::free_balance(&alice), 10 * LGNT);
+			},
+		);
+	}
+
+	#[test]
+	fn failing_calls_are_dry_run_with_module_error() {
+		let charlie = Sr25519Keyring::Charlie.to_account_id();
+		new_test_ext(
+			vec![Sr25519Keyring::Alice.to_account_id()],
+			vec![Sr25519Keyring::Bob.to_account_id()],
+		)
+		.execute_with(|| {
+			let purge_keys = RuntimeCall::Session(pallet_session::Call::purge_keys {});
+
+			let outcome = dry_run::dry_run(apis::DryRunOrigin::Signed(charlie), purge_keys);
+
+			let error = outcome.result.unwrap_err().error;
+			assert!(matches!(error, sp_runtime::DispatchError::Module(_)));
+			let decoded = error_decoder::decode_error(error).unwrap();
+			assert_eq!(decoded.pallet.unwrap().name, b"Session".to_vec());
+			assert!(outcome.events.is_empty());
+		});
+	}
+
+	#[test]
+	fn module_errors_are_decoded() {
+		let error: sp_runtime::DispatchError = frame_system::Error::<Runtime>::CallFiltered.into();
+
+		let decoded = error_decoder::decode_error(error).unwrap();
+
+		let pallet = decoded.pallet.unwrap();
+		assert_eq!(pallet.name, b"System".to_vec());
+		assert_eq!(pallet.index, 0);
+		assert_eq!(decoded.error, b"CallFiltered".to_vec());
+		assert!(!decoded.docs.is_empty());
+	}
+
+	#[test]
+	fn other_errors_are_decoded() {
+		let decoded = error_decoder::decode_error(sp_runtime::DispatchError::BadOrigin).unwrap();
+
+		assert_eq!(decoded.pallet, None);
+		assert_eq!(decoded.error, b"BadOrigin".to_vec());
+	}
+
+	#[test]
+	fn distributed_rewards_are_accounted_per_category() {
+		let legal_officer = Sr25519Keyring::Alice.to_account_id();
+		new_test_ext(vec![legal_officer.clone()], vec![Sr25519Keyring::Bob.to_account_id()])
+			.execute_with(|| {
+				DealWithInclusionFees::on_unbalanced(Balances::issue(1_000 * LGNT));
+
+				let inflows = RewardAccounting::inflows();
+				assert_eq!(
+					inflows.logion_treasury,
+					vec![(InflowCategory::InclusionFees, 350 * LGNT)]
+				);
+				assert_eq!(
+					inflows.community_treasury,
+					vec![(InflowCategory::InclusionFees, 300 * LGNT)]
+				);
+				assert_eq!(
+					inflows.legal_officers,
+					vec![(legal_officer, vec![(InflowCategory::InclusionFees, 350 * LGNT)])]
+				);
+			});
+	}
+
+	#[test]
+	fn categorized_distribution_keys_set_category() {
+		new_test_ext(
+			vec![Sr25519Keyring::Alice.to_account_id()],
+			vec![Sr25519Keyring::Bob.to_account_id()],
+		)
+		.execute_with(|| {
+			let _ = CategorizedDistributionKey::<FileStorageFeeDistributionKey, StorageFeesCategory>::get();
+
+			LocFeesRewardDistributor::payout_logion_treasury(Balances::issue(LGNT));
+			assert_eq!(
+				RewardAccounting::inflows().logion_treasury,
+				vec![(InflowCategory::StorageFees, LGNT)]
+			);
+		});
+	}
+
+	#[test]
+	fn uncategorized_payouts_after_categorized_ones_are_accounted_as_other() {
+		new_test_ext(
+			vec![Sr25519Keyring::Alice.to_account_id()],
+			vec![Sr25519Keyring::Bob.to_account_id()],
+		)
+		.execute_with(|| {
+			let key = CategorizedDistributionKey::<
+				FileStorageFeeDistributionKey,
+				StorageFeesCategory,
+			>::get();
+			LocFeesRewardDistributor::distribute(Balances::issue(10 * LGNT), key);
+
+			RewardDistributor::payout_logion_treasury(Balances::issue(LGNT));
+
+			let inflows = RewardAccounting::inflows();
+			assert_eq!(inflows.logion_treasury, vec![(InflowCategory::Other, LGNT)]);
+			assert_eq!(inflows.community_treasury, vec![(InflowCategory::StorageFees, 2 * LGNT)]);
+
+			RewardAccounting::on_finalize(1);
+			LocFeesRewardDistributor::payout_community_treasury(Balances::issue(LGNT));
+			assert!(RewardAccounting::inflows()
+				.community_treasury
+				.contains(&(InflowCategory::Other, LGNT)));
+		});
+	}
 }
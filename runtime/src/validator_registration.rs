@@ -0,0 +1,33 @@
+//! Lookup of a validator's on-chain registration from its session keys.
+
+use crate::{apis::ValidatorRegistration, AccountId, AuraId, LoAuthorityList, Runtime, ValidatorSet};
+use logion_shared::IsLegalOfficer;
+use pallet_lo_authority_list::{LegalOfficerData, LegalOfficerSet};
+use pallet_session::{KeyOwner, NextKeys};
+use sp_core::{
+	crypto::{key_types::AURA, ByteArray},
+	OpaquePeerId,
+};
+
+pub fn validator_registration(aura_key: AuraId) -> Option<ValidatorRegistration<AccountId>> {
+	let account = KeyOwner::<Runtime>::get((AURA, aura_key.to_raw_vec()))?;
+	Some(ValidatorRegistration {
+		grandpa_key: NextKeys::<Runtime>::get(&account).map(|keys| keys.grandpa),
+		is_validator: ValidatorSet::validators().contains(&account),
+		is_legal_officer: LoAuthorityList::is_legal_officer(&account),
+		node_id: legal_officer_node(&account),
+		account,
+	})
+}
+
+fn legal_officer_node(account: &AccountId) -> Option<OpaquePeerId> {
+	match LegalOfficerSet::<Runtime>::get(account)? {
+		LegalOfficerData::Host(host) => host.node_id.map(|node_id| OpaquePeerId(node_id.to_vec())),
+		// Guests share the node of their host, which cannot be a guest itself.
+		LegalOfficerData::Guest(host) => match LegalOfficerSet::<Runtime>::get(&host)? {
+			LegalOfficerData::Host(host) =>
+				host.node_id.map(|node_id| OpaquePeerId(node_id.to_vec())),
+			LegalOfficerData::Guest(_) => None,
+		},
+	}
+}
@@ -6,6 +6,7 @@
 PALLETS=(
   "frame_system" \
   "pallet_balances" \
+  "pallet_hash_anchoring" \
   "pallet_lo_authority_list" \
   "pallet_logion_loc" \
   "pallet_logion_vote" \